@@ -0,0 +1,50 @@
+//! Prints the width, in pixels, of some text set in a PNG glyph sheet font.
+//!
+//! This takes the same `--charset`, `--first`, and `--first-char` options as
+//! the binaries:
+//!
+//! ```text
+//! cargo run --features std --example width -- -c cp437 fonts/ibm-8x8.png "Hello"
+//! ```
+
+use clap::Parser;
+
+#[path = "../src/bin/common/mod.rs"]
+mod common;
+use common::{FirstArgs, GlyphOrderArg};
+
+use seff::load::GlyphOrder;
+
+#[derive(Debug, Parser)]
+struct Width {
+    #[clap(arg_enum, short, long)]
+    charset: Option<GlyphOrderArg>,
+    #[clap(flatten)]
+    first: FirstArgs,
+
+    font: std::path::PathBuf,
+    text: String,
+}
+
+fn main() {
+    let args = Width::parse();
+
+    let font = std::fs::File::open(args.font).unwrap();
+    let font = std::io::BufReader::new(font);
+
+    // The library itself only deals in `GlyphOrder`, which can also be an
+    // explicit list of chars.
+    let order: GlyphOrder = args.charset
+        .unwrap_or(GlyphOrderArg::Iso8859_1)
+        .into();
+
+    let result = seff::load::load_font_from_png(font, order, args.first.first(), &Default::default(), |font| {
+        for line in font.lines_layout(&args.text, Default::default()) {
+            println!("{}\t{:?}", line.width, line.text);
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        common::exit_with_error(e);
+    }
+}
@@ -0,0 +1,104 @@
+//! Command-line plumbing shared by the binaries.
+
+use seff::load::GlyphOrder;
+
+/// The glyph orders that can be chosen on the command line. (There's no way to
+/// give a `GlyphOrder::Explicit` list this way.)
+#[derive(Copy, Clone, Eq, PartialEq, Debug, clap::ArgEnum)]
+pub enum GlyphOrderArg {
+    Iso8859_1,
+    Cp437,
+}
+
+impl From<GlyphOrderArg> for GlyphOrder<'_> {
+    fn from(a: GlyphOrderArg) -> Self {
+        match a {
+            GlyphOrderArg::Iso8859_1 => Self::Iso8859_1,
+            GlyphOrderArg::Cp437 => Self::Cp437,
+        }
+    }
+}
+
+// Options for giving the first char of a glyph sheet, instead of letting the
+// loader guess it from the positions of blank glyphs.
+//
+// (Not a doc comment, because clap would use it as the `about` text of every
+// binary that flattens this in.)
+#[derive(Debug, clap::Args)]
+pub struct FirstArgs {
+    /// Code of the first char in the sheet.
+    #[clap(long)]
+    first: Option<u8>,
+    /// The first char in the sheet, as a character, e.g. `--first-char ' '`.
+    #[clap(long, parse(try_from_str = parse_first_char), conflicts_with = "first")]
+    first_char: Option<u8>,
+}
+
+impl FirstArgs {
+    /// Returns the first char's code, if one was given.
+    pub fn first(&self) -> Option<u8> {
+        self.first.or(self.first_char)
+    }
+}
+
+/// Parses a single char in ISO8859-1 into its code.
+fn parse_first_char(s: &str) -> Result<u8, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => u8::try_from(c)
+            .map_err(|_| format!("{c:?} is not in ISO8859-1")),
+        _ => Err(format!("expected a single char, not {s:?}")),
+    }
+}
+
+/// Reports an error that stopped the program, and exits.
+pub fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("error: {e}");
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{ArgEnum, Parser};
+
+    #[derive(Debug, Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        first: FirstArgs,
+    }
+
+    fn first(args: &[&str]) -> Result<Option<u8>, clap::Error> {
+        let cli = Cli::try_parse_from(core::iter::once("test").chain(args.iter().copied()))?;
+        Ok(cli.first.first())
+    }
+
+    #[test]
+    fn glyph_order_args_parse_and_convert() {
+        let arg = GlyphOrderArg::from_str("cp437", false).unwrap();
+        assert_eq!(arg, GlyphOrderArg::Cp437);
+        assert!(matches!(GlyphOrder::from(arg), GlyphOrder::Cp437));
+        let arg = GlyphOrderArg::from_str("iso8859-1", false).unwrap();
+        assert!(matches!(GlyphOrder::from(arg), GlyphOrder::Iso8859_1));
+    }
+
+    #[test]
+    fn first_char_literal() {
+        assert_eq!(parse_first_char(" "), Ok(b' '));
+        assert_eq!(parse_first_char("A"), Ok(b'A'));
+        assert_eq!(parse_first_char("\u{e9}"), Ok(0xE9));
+        assert!(parse_first_char("").is_err());
+        assert!(parse_first_char("AB").is_err());
+        assert!(parse_first_char("\u{2603}").is_err());
+    }
+
+    #[test]
+    fn first_args() {
+        assert_eq!(first(&[]).unwrap(), None);
+        assert_eq!(first(&["--first", "32"]).unwrap(), Some(32));
+        assert_eq!(first(&["--first-char", " "]).unwrap(), Some(32));
+        assert!(first(&["--first-char", "ab"]).is_err());
+        assert!(first(&["--first", "32", "--first-char", " "]).is_err());
+    }
+
+}
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use clap::Parser;
-use image::Rgb;
+use image::{Rgb, RgbImage};
 
 #[derive(Debug, Parser)]
 struct Cvtc {
@@ -19,6 +19,11 @@ struct Cvtc {
     flip_x: bool,
     #[clap(long)]
     add_advance: Option<usize>,
+    /// RON file holding an array of `u8` advances, one per glyph, to make a
+    /// proportional font. Each glyph's separator is placed at its own
+    /// advance instead of at `width` plus `--add-advance`.
+    #[clap(long, conflicts_with = "add-advance")]
+    advances: Option<std::path::PathBuf>,
 
     input: std::path::PathBuf,
     output: std::path::PathBuf,
@@ -27,15 +32,47 @@ struct Cvtc {
 fn main() {
     let args = Cvtc::parse();
 
-    let mut font_data: Vec<u32> = ron::de::from_reader(
-        std::fs::File::open(args.input).unwrap()
+    let font_data: Vec<u64> = ron::de::from_reader(
+        std::fs::File::open(&args.input).unwrap()
     ).unwrap();
-    let bytes_per_row = (args.width + 7) / 8;
-    for row in &mut font_data {
+    let advances: Option<Vec<u8>> = args.advances.as_ref().map(|path| {
+        ron::de::from_reader(std::fs::File::open(path).unwrap()).unwrap()
+    });
+    let img = convert(&args, font_data, advances).unwrap_or_else(|e| fail(&e));
+    img.save(&args.output).unwrap();
+}
+
+/// Lays out `font_data`, the rows of each glyph in turn, as a glyph sheet,
+/// giving each glyph its entry in `advances` if provided.
+fn convert(
+    args: &Cvtc,
+    mut font_data: Vec<u64>,
+    advances: Option<Vec<u8>>,
+) -> Result<RgbImage, String> {
+    if args.width == 0 || args.width > 64 {
+        return Err("width must be between 1 and 64".into());
+    }
+    if args.height == 0 {
+        return Err("height must be at least 1".into());
+    }
+
+    let bytes_per_row = args.width.div_ceil(8);
+    // Normalize rows so that the leftmost pixel is in bit 63.
+    for (i, row) in font_data.iter_mut().enumerate() {
         if args.flip_x {
+            // The leftmost pixel is in bit 0, and the row is `width` bits.
+            if args.width < 64 && *row >> args.width != 0 {
+                return Err(format!("row {i} has pixels beyond width {}", args.width));
+            }
             *row = row.reverse_bits();
         } else {
-            *row <<= 8 * (4 - bytes_per_row);
+            // The row is right-aligned in `bytes_per_row` bytes, leftmost
+            // pixel first.
+            let bits = 8 * bytes_per_row;
+            if bits < 64 && *row >> bits != 0 {
+                return Err(format!("row {i} is wider than {bytes_per_row} bytes"));
+            }
+            *row <<= 64 - bits;
         }
     }
 
@@ -44,7 +81,7 @@ fn main() {
             if args.flip_y {
                 chunk.iter().rev().cloned().collect()
             } else {
-                chunk.iter().cloned().collect()
+                chunk.to_vec()
             }
         })
         .collect();
@@ -74,19 +111,46 @@ fn main() {
     };
 
     if ascent > args.height {
-        panic!("ascent must be <= height");
+        return Err("ascent must be <= height".into());
     }
 
     println!("Line height: {}; ascent = {}, descent = {}", args.height, ascent, args.height - ascent);
 
     let cell_width = args.width + args.add_advance.unwrap_or(0);
 
-    let img_width = u32::try_from((cell_width + 1) * args.per_band).unwrap();
-    let n_bands = (glyph_data.len() + (args.per_band - 1)) / args.per_band;
+    let advances: Vec<usize> = if let Some(advances) = advances {
+        if advances.len() != glyph_data.len() {
+            return Err(format!(
+                "found {} advances for {} glyphs",
+                advances.len(),
+                glyph_data.len(),
+            ));
+        }
+        for (i, (&advance, data)) in advances.iter().zip(&glyph_data).enumerate() {
+            if advance == 0 || advance > 64 {
+                return Err(format!("advance of glyph {i} must be between 1 and 64"));
+            }
+            // Pixels past the advance would land in the next glyph's cell.
+            if advance < 64 && data.iter().any(|row| row << advance != 0) {
+                return Err(format!("glyph {i} has pixels beyond its advance of {advance}"));
+            }
+        }
+        advances.into_iter().map(usize::from).collect()
+    } else {
+        vec![cell_width; glyph_data.len()]
+    };
+
+    // Each band holds up to `per_band` glyphs, each followed by a separator
+    // column, so it's as wide as the widest such run.
+    let band_widths: Vec<usize> = advances.chunks(args.per_band)
+        .map(|band| band.iter().map(|a| a + 1).sum())
+        .collect();
+    let img_width = u32::try_from(band_widths.iter().copied().max().unwrap_or(0)).unwrap();
+    let n_bands = band_widths.len();
     let band_height = args.height + 1;
     let img_height = u32::try_from(band_height * n_bands).unwrap();
 
-    let mut img = image::ImageBuffer::<Rgb<u8>, _>::new(img_width, img_height);
+    let mut img = RgbImage::new(img_width, img_height);
 
     // Fill the canvas with white, except for band boundaries and baselines.
     for (y, row) in img.enumerate_rows_mut() {
@@ -103,28 +167,110 @@ fn main() {
         }
     }
 
-    for (i, data) in glyph_data.iter().enumerate() {
+    let mut gx = 0;
+    for (i, (data, &advance)) in glyph_data.iter().zip(&advances).enumerate() {
         let band = i / args.per_band;
+        if i % args.per_band == 0 {
+            gx = 0;
+        }
         let gy = u32::try_from(band_height * band).unwrap();
-        let gx = u32::try_from((i % args.per_band) * (cell_width + 1)).unwrap();
+        let sep_x = u32::try_from(gx + advance).unwrap();
 
         // Baseline glyph separator
-        img.put_pixel(gx + cell_width as u32, gy + ascent as u32 - 1, Rgb([0xFF, 0, 0]));
-        img.put_pixel(gx + cell_width as u32, gy + ascent as u32 - 2, Rgb([0xFF, 0, 0]));
+        img.put_pixel(sep_x, gy + ascent as u32 - 1, Rgb([0xFF, 0, 0]));
+        img.put_pixel(sep_x, gy + ascent as u32 - 2, Rgb([0xFF, 0, 0]));
 
         // Draw glyph
+        let draw_width = args.width.min(advance);
+        let gx32 = u32::try_from(gx).unwrap();
         for (row_i, row) in data.iter().enumerate() {
             let mut row = *row;
             let py = gy + u32::try_from(row_i).unwrap();
-            for px in gx..gx + args.width as u32 {
-                if row & 1 << 31 != 0 {
+            for px in gx32..gx32 + draw_width as u32 {
+                if row & 1 << 63 != 0 {
                     img.put_pixel(px, py, Rgb([0, 0, 0]));
                 }
                 row <<= 1;
             }
         }
+
+        gx += advance + 1;
+    }
+
+    Ok(img)
+}
+
+/// Reports a problem with the arguments or input and exits.
+fn fail(message: &str) -> ! {
+    eprintln!("error: {message}");
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+    const RED: Rgb<u8> = Rgb([0xFF, 0, 0]);
+
+    fn args(extra: &[&str]) -> Cvtc {
+        let mut args = vec!["cvtc", "-w", "40", "-h", "2", "-a", "2"];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["in.ron", "out.png"]);
+        Cvtc::parse_from(args)
+    }
+
+    /// Checks a converted 40-pixel glyph with ink in columns 0 and 39 of its
+    /// top row, and column 32 of its bottom row.
+    fn check_wide_glyph(img: &RgbImage) {
+        let top: Vec<_> = (0..40).filter(|&x| *img.get_pixel(x, 0) == BLACK).collect();
+        assert_eq!(top, [0, 39]);
+        let bottom: Vec<_> = (0..40).filter(|&x| *img.get_pixel(x, 1) == BLACK).collect();
+        assert_eq!(bottom, [32]);
+        // The advance separator comes right after the glyph.
+        assert_eq!(*img.get_pixel(40, 0), RED);
+        assert_eq!(*img.get_pixel(40, 1), RED);
+    }
+
+    #[test]
+    fn wide_glyph() {
+        let img = convert(&args(&[]), vec![1 << 39 | 1, 1 << 7], None).unwrap();
+        check_wide_glyph(&img);
+    }
+
+    #[test]
+    fn wide_glyph_flipped_x() {
+        let img = convert(&args(&["--flip-x"]), vec![1 | 1 << 39, 1 << 32], None).unwrap();
+        check_wide_glyph(&img);
+    }
+
+    #[test]
+    fn row_wider_than_glyph_is_an_error() {
+        assert!(convert(&args(&[]), vec![1 << 40, 0], None).is_err());
+        assert!(convert(&args(&["--flip-x"]), vec![1 << 40, 0], None).is_err());
+    }
+
+    #[test]
+    fn per_glyph_advances() {
+        let rows = vec![1 << 39, 0, 1 << 39, 0];
+        let img = convert(&args(&[]), rows, Some(vec![3, 5])).unwrap();
+        assert_eq!(img.width(), 3 + 1 + 5 + 1);
+        let separators: Vec<_> = (0..img.width())
+            .filter(|&x| *img.get_pixel(x, 0) == RED)
+            .collect();
+        assert_eq!(separators, [3, 9]);
+        let ink: Vec<_> = (0..img.width())
+            .filter(|&x| *img.get_pixel(x, 0) == BLACK)
+            .collect();
+        assert_eq!(ink, [0, 4]);
+    }
+
+    #[test]
+    fn bad_advances_are_errors() {
+        // Wrong count.
+        assert!(convert(&args(&[]), vec![0; 4], Some(vec![3])).is_err());
+        // Ink past the advance.
+        assert!(convert(&args(&[]), vec![1 << 35, 0], Some(vec![3])).is_err());
     }
 
-    img.save(args.output).unwrap();
-    
 }
@@ -1,24 +1,257 @@
 use clap::Parser;
 
+mod common;
+use common::{FirstArgs, GlyphOrderArg};
+
 #[derive(Debug, Parser)]
 struct Img {
-    #[clap(long)]
-    first: Option<u8>,
+    #[clap(flatten)]
+    first: FirstArgs,
     #[clap(arg_enum, short, long)]
-    charset: Option<seff::load::GlyphOrderArg>,
-    input: std::path::PathBuf,
+    charset: Option<GlyphOrderArg>,
+    /// Output format: Rust or C source, RON, the binary format, or RON
+    /// holding only the font's metrics.
+    #[clap(arg_enum, long, alias = "lang", default_value = "rust")]
+    format: Format,
+    /// Where to write the output. Defaults to stdout.
+    #[clap(short, long)]
+    output: Option<std::path::PathBuf>,
+    /// Compress glyph images, if it makes the font smaller.
+    #[clap(long)]
+    compress: bool,
+    /// Share bitmap data between overlapping glyph images. Slower, but can
+    /// produce smaller fonts.
+    #[clap(long)]
+    overlap: bool,
+    /// Report how many glyphs have identical images, on stderr.
+    #[clap(long)]
+    sharing: bool,
+    /// Report how much ink each glyph holds, on stderr.
+    #[clap(long)]
+    stats: bool,
+    /// Name prefix for identifiers in generated Rust or C code. Rust has no
+    /// prefix by default, and C uses `font`.
+    #[clap(long)]
+    name: Option<String>,
+    /// Path to the `seff` crate, like `::seff`, for qualifying names in
+    /// generated Rust code instead of starting with `use seff::*`.
+    #[clap(long)]
+    crate_path: Option<String>,
+    /// Define the font and its tables as `const` items instead of statics, in
+    /// generated Rust code.
+    #[clap(long = "const")]
+    constants: bool,
+    /// Char whose glyph is drawn for chars missing from the font. Defaults to
+    /// the first glyph in the sheet.
+    #[clap(long)]
+    replacement: Option<char>,
+    /// The glyph sheet, or several pages of it, in order.
+    #[clap(required = true)]
+    input: Vec<std::path::PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, clap::ArgEnum)]
+enum Format {
+    Rust,
+    Ron,
+    Bin,
+    C,
+    Metrics,
 }
 
 fn main() {
     let args = Img::parse();
 
-    let input = std::fs::File::open(args.input).unwrap();
-    let input = std::io::BufReader::new(input);
+    let pages = args.input.iter()
+        .map(|path| std::fs::File::open(path).map(std::io::BufReader::new))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let order = args.charset.unwrap_or(GlyphOrderArg::Iso8859_1);
+
+    let options = load_options(&args);
+    let loaded = seff::load::load_owned_font_from_pngs(pages, order.into(), args.first.first(), &options)
+        .unwrap_or_else(|e| common::exit_with_error(e));
+    if let Err(e) = report_and_write(&args, &loaded) {
+        common::exit_with_error(e);
+    }
+}
+
+/// Works out the options for loading the glyph sheet from the command line.
+fn load_options(args: &Img) -> seff::load::LoadOptions<'static> {
+    seff::load::LoadOptions {
+        dedup: if args.overlap {
+            seff::load::DedupMode::Full
+        } else {
+            seff::load::DedupMode::ExactSuffix
+        },
+        replacement: args.replacement,
+        ..Default::default()
+    }
+}
+
+/// Reports on `loaded` and writes it out, as `args` say.
+fn report_and_write(args: &Img, loaded: &seff::OwnedFont) -> Result<(), Box<dyn std::error::Error>> {
+    let font = &loaded.as_font();
+    if args.sharing {
+        eprintln!("{}.", seff::gen::bitmap_sharing(font));
+    }
+    if args.stats {
+        let stats = seff::gen::glyph_stats(font);
+        eprintln!("char\tsize\tset\tink");
+        for stat in &stats {
+            eprintln!(
+                "{:?}\t{}x{}\t{}\t{:.0}%",
+                stat.ch,
+                stat.width,
+                stat.height,
+                stat.set_pixels,
+                stat.ink_fraction() * 100.,
+            );
+        }
+        eprintln!("total\t\t\t{:.0}%", seff::gen::ink_fraction(&stats) * 100.);
+    }
+    let compressed = if args.compress {
+        seff::gen::compress_bitmaps(font)
+    } else {
+        None
+    };
+    let font = compressed.as_ref().map_or(*font, seff::OwnedFont::as_font);
+
+    let mut out: Box<dyn std::io::Write> = match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let options = seff::gen::RustOptions {
+        prefix: args.name.as_deref(),
+        crate_path: args.crate_path.as_deref(),
+        sources: &loaded.sources,
+        constants: args.constants,
+    };
+    write_font(&font, args.format, &options, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes `font` to `out` in `format`. `options` are for Rust output, except
+/// that C output also takes its name from `options.prefix`.
+fn write_font(
+    font: &seff::Font<'_, '_, '_, '_>,
+    format: Format,
+    options: &seff::gen::RustOptions<'_>,
+    mut out: impl std::io::Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        Format::Rust => seff::gen::generate_rust_module(font, options, &mut out)?,
+        Format::Ron => seff::gen::write_ron(font, &mut out)?,
+        Format::Bin => seff::gen::write_binary(font, &mut out)?,
+        Format::C => {
+            seff::gen::generate_c_header(font, options.prefix.unwrap_or("font"), &mut out)?
+        }
+        Format::Metrics => seff::gen::write_metrics_ron(font, &mut out)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hello, world!";
+
+    /// Writes the futuroid font in `format`, and passes the output to `check`
+    /// along with the width of `TEXT` in the original.
+    fn output(format: Format, check: impl FnOnce(&[u8], usize)) {
+        let png = std::fs::File::open("fonts/futuroid.png").unwrap();
+        seff::load::load_font_from_png(
+            std::io::BufReader::new(png),
+            seff::load::GlyphOrder::Iso8859_1,
+            Some(b' '),
+            &Default::default(),
+            |font| {
+                let mut out = vec![];
+                let options = seff::gen::RustOptions { prefix: Some("futuroid"), ..Default::default() };
+                write_font(font, format, &options, &mut out)?;
+                assert!(!out.is_empty());
+                check(&out, font.width(TEXT));
+                Ok(())
+            },
+        ).unwrap();
+    }
+
+    /// Checks that source code has balanced braces.
+    fn assert_balanced(source: &[u8]) {
+        let mut depth = 0usize;
+        for &b in source {
+            match b {
+                b'{' => depth += 1,
+                b'}' => depth = depth.checked_sub(1).expect("unbalanced '}'"),
+                _ => (),
+            }
+        }
+        assert_eq!(depth, 0);
+    }
+
+    #[test]
+    fn rust_output() {
+        output(Format::Rust, |out, _| {
+            let source = std::str::from_utf8(out).unwrap();
+            assert!(source.contains("pub static FUTUROID_FONT: "));
+            assert_balanced(out);
+        });
+    }
+
+    #[test]
+    fn c_output() {
+        output(Format::C, |out, _| {
+            let source = std::str::from_utf8(out).unwrap();
+            assert!(source.contains("futuroid_glyphs"));
+            assert_balanced(out);
+        });
+    }
+
+    #[test]
+    fn ron_output_parses_back() {
+        output(Format::Ron, |out, width| {
+            let font: seff::OwnedFont = ron::de::from_bytes(out).unwrap();
+            assert_eq!(font.as_font().width(TEXT), width);
+        });
+    }
 
-    let order = args.charset.unwrap_or(seff::load::GlyphOrderArg::Iso8859_1);
+    #[test]
+    fn bin_output_parses_back() {
+        output(Format::Bin, |out, width| {
+            let mut words = vec![0u32; out.len().div_ceil(4)];
+            // Safety: `words` has room for `out.len()` bytes, and any bytes
+            // are valid `u32`s.
+            let bytes = unsafe {
+                let bytes = core::slice::from_raw_parts_mut(
+                    words.as_mut_ptr().cast::<u8>(),
+                    out.len(),
+                );
+                bytes.copy_from_slice(out);
+                &*bytes
+            };
+            let font = seff::Font::from_bytes(bytes).unwrap();
+            assert_eq!(font.width(TEXT), width);
+        });
+    }
 
-    seff::load::load_font_from_png(input, order.into(), args.first, |font| {
-        seff::gen::generate_rust_module(&font, std::io::stdout())?;
-        Ok(())
-    }).unwrap();
+    #[test]
+    fn replacement_flag_picks_the_glyph() {
+        let args = Img::parse_from(["img", "--first-char", " ", "--replacement", "?", "fonts/futuroid.png"]);
+        let png = std::fs::File::open(&args.input[0]).unwrap();
+        seff::load::load_font_from_png(
+            std::io::BufReader::new(png),
+            seff::load::GlyphOrder::Iso8859_1,
+            args.first.first(),
+            &load_options(&args),
+            |font| {
+                assert_eq!(usize::from(font.replacement), usize::from(b'?' - b' '));
+                // A char missing from the font is drawn as `'?'`.
+                assert_eq!(font.width("\u{3b1}"), font.width("?"));
+                Ok(())
+            },
+        ).unwrap();
+    }
 }
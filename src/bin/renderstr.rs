@@ -1,14 +1,20 @@
-use image::Luma;
 use clap::Parser;
 
+mod common;
+use common::{FirstArgs, GlyphOrderArg};
+
 #[derive(Debug, Parser)]
 struct RenderStr {
-    #[clap(long)]
-    first: Option<u8>,
+    #[clap(flatten)]
+    first: FirstArgs,
     #[clap(arg_enum, short, long)]
-    charset: Option<seff::load::GlyphOrderArg>,
+    charset: Option<GlyphOrderArg>,
     #[clap(short)]
     invert: bool,
+    /// Char whose glyph is drawn for chars missing from the font. Defaults to
+    /// the first glyph in the sheet.
+    #[clap(long)]
+    replacement: Option<char>,
 
     font: std::path::PathBuf,
     output: std::path::PathBuf,
@@ -21,37 +27,23 @@ fn main() {
     let font = std::fs::File::open(args.font).unwrap();
     let font = std::io::BufReader::new(font);
 
-    let order = args.charset.unwrap_or(seff::load::GlyphOrderArg::Iso8859_1);
+    let order = args.charset.unwrap_or(GlyphOrderArg::Iso8859_1);
 
-    seff::load::load_font_from_png(
+    let result = seff::load::load_font_from_png(
         font,
         order.into(),
-        args.first,
+        args.first.first(),
+        &seff::load::LoadOptions {
+            replacement: args.replacement,
+            ..Default::default()
+        },
         |font| {
-            let line_count = args.text.lines().count();
-            let img_width = args.text.lines()
-                .map(|line| font.width(line))
-                .max()
-                .unwrap();
-
-            let (bg, fg) = if args.invert {
-                (0, Luma([0xFF]))
-            } else {
-                (0xFF, Luma([0]))
-            };
-            let mut outimg = image::ImageBuffer::<Luma<u8>, _>::new(
-                img_width as u32,
-                (font.line_spacing_usize() * line_count) as u32,
-            );
-            outimg.fill(bg);
-
-            for (i, line) in args.text.lines().enumerate() {
-                font.render_direct(line, 0, i * font.line_spacing_usize(), &mut outimg, fg);
-            }
-
-            outimg.save(args.output)?;
+            let (fg, bg) = if args.invert { (0xFF, 0) } else { (0, 0xFF) };
+            font.render_to_image(&args.text, fg, bg, Default::default()).save(args.output)?;
             Ok(())
         }
-    ).unwrap();
-
+    );
+    if let Err(e) = result {
+        common::exit_with_error(e);
+    }
 }
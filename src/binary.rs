@@ -0,0 +1,504 @@
+//! Compact binary font format, for loading fonts at runtime.
+//!
+//! Fonts in this format can be parsed by `Font::from_bytes` without copying or
+//! allocation: the resulting `Font` borrows its glyph, bitmap, and kerning
+//! tables directly out of the buffer. Use `gen::write_binary` to produce them.
+//!
+//! # Layout
+//!
+//! All multi-byte integers are little-endian. The file begins with a 24-byte
+//! header:
+//!
+//! | Offset | Size | Contents                                        |
+//! |--------|------|-------------------------------------------------|
+//! | 0      | 4    | Magic, `b"SEFF"`                                |
+//! | 4      | 1    | Format version, currently 1                     |
+//! | 5      | 1    | Storage: 0 for `Dense`, 1 `Sparse`, 2 `Ranges`  |
+//! | 6      | 1    | `ascent`                                        |
+//! | 7      | 1    | `descent`                                       |
+//! | 8      | 1    | `line_spacing`                                  |
+//! | 9      | 1    | `replacement`                                   |
+//! | 10     | 1    | `first` for `Dense` storage, otherwise 0        |
+//! | 11     | 1    | Bitmap encoding: 0 for `Raw`, 1 for `PackBits`  |
+//! | 12     | 4    | Number of glyphs                                |
+//! | 16     | 4    | Length of bitmap data in bytes                  |
+//! | 20     | 4    | Number of kerning entries                       |
+//!
+//! This is followed immediately by the glyph table, then the bitmap data, then
+//! the kerning table.
+//!
+//! Each glyph is 8 bytes: `row_bytes`, `pixel_width`, `image_offset` (2
+//! bytes), `image_height`, `origin.0`, `origin.1`, and `advance`. A
+//! `pixel_width` of zero means the full width of `row_bytes`. In `Sparse`
+//! storage, each glyph is preceded by its `char` as a 4-byte codepoint, for 12
+//! bytes per entry.
+//!
+//! In `Ranges` storage, the glyph table is instead preceded by a 4-byte count
+//! of ranges, followed by the range table. Each range is 8 bytes: the `start`
+//! char as a 4-byte codepoint, then `len` and `offset` (2 bytes each).
+//!
+//! Each kerning entry is 3 bytes: `pair.0`, `pair.1`, and `adjust`.
+//!
+//! The format has no place for `replacement_char`, so `gen::write_binary`
+//! stores its glyph index as `replacement` instead, when it can.
+//!
+//! # Alignment
+//!
+//! Because the glyph table is borrowed in place, the buffer passed to
+//! `Font::from_bytes` must be aligned to at least 4 bytes, or parsing will fail
+//! with `ParseError::Misaligned`. For the same reason, this format can only be
+//! parsed on little-endian targets.
+//!
+//! Data from `include_bytes!` is only byte-aligned, so wrap it in `Aligned` to
+//! embed a font in a program:
+//!
+//! ```ignore
+//! static FONT_DATA: &seff::binary::Aligned<[u8]> =
+//!     &seff::binary::Aligned(*include_bytes!("font.seff"));
+//!
+//! let font = seff::Font::from_static(&FONT_DATA.0)?;
+//! ```
+
+use core::mem::{align_of, size_of};
+
+use crate::{
+    BitmapEncoding, Font, Glyph, GlyphStorage, Kerning, KerningEntry,
+    KerningError, KerningTable, LigatureTable,
+};
+
+/// Magic number at the start of every binary font.
+pub const MAGIC: [u8; 4] = *b"SEFF";
+/// Current format version.
+pub const VERSION: u8 = 1;
+/// Size of the header, in bytes.
+pub const HEADER_LEN: usize = 24;
+/// Glyph storage code for `GlyphStorage::Dense`.
+pub const STORAGE_DENSE: u8 = 0;
+/// Glyph storage code for `GlyphStorage::Sparse`.
+pub const STORAGE_SPARSE: u8 = 1;
+/// Glyph storage code for `GlyphStorage::Ranges`.
+pub const STORAGE_RANGES: u8 = 2;
+/// Bitmap encoding code for `BitmapEncoding::Raw`.
+pub const ENCODING_RAW: u8 = 0;
+/// Bitmap encoding code for `BitmapEncoding::PackBits`.
+pub const ENCODING_PACKBITS: u8 = 1;
+
+// The zero-copy parser below depends on the in-memory layout of these types
+// matching the file format exactly. `Glyph` and `KerningEntry` are `repr(C)`,
+// but the tuples inside them (and the tuples used for `Sparse` and `Ranges`
+// storage) are not, so check everything here.
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(size_of::<Glyph>() == 8);
+    assert!(align_of::<Glyph>() == 2);
+    assert!(offset_of!(Glyph, row_bytes) == 0);
+    assert!(offset_of!(Glyph, pixel_width) == 1);
+    assert!(offset_of!(Glyph, image_offset) == 2);
+    assert!(offset_of!(Glyph, image_height) == 4);
+    assert!(offset_of!(Glyph, origin) == 5);
+    assert!(offset_of!(Glyph, advance) == 7);
+    assert!(offset_of!((u8, u8), 0) == 0);
+    assert!(offset_of!((u8, u8), 1) == 1);
+
+    assert!(size_of::<(char, Glyph)>() == 12);
+    assert!(offset_of!((char, Glyph), 0) == 0);
+    assert!(offset_of!((char, Glyph), 1) == 4);
+
+    assert!(size_of::<(char, u16, u16)>() == 8);
+    assert!(align_of::<(char, u16, u16)>() == 4);
+    assert!(offset_of!((char, u16, u16), 0) == 0);
+    assert!(offset_of!((char, u16, u16), 1) == 4);
+    assert!(offset_of!((char, u16, u16), 2) == 6);
+
+    assert!(size_of::<KerningEntry>() == 3);
+    assert!(align_of::<KerningEntry>() == 1);
+    assert!(offset_of!(KerningEntry, pair) == 0);
+    assert!(offset_of!(KerningEntry, adjust) == 2);
+};
+
+/// Wrapper that aligns its contents suitably for `Font::from_bytes`.
+#[derive(Copy, Clone, Debug)]
+#[repr(C, align(4))]
+pub struct Aligned<T: ?Sized>(pub T);
+
+/// Ways that parsing a binary font can fail.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// The buffer doesn't start with `MAGIC`.
+    BadMagic,
+    /// The format version isn't one we understand.
+    UnsupportedVersion(u8),
+    /// The glyph storage code isn't one we understand.
+    BadStorage(u8),
+    /// The bitmap encoding code isn't one we understand.
+    BadEncoding(u8),
+    /// The buffer is shorter than its header says it should be.
+    Truncated,
+    /// The buffer isn't suitably aligned to borrow the glyph table out of it.
+    Misaligned,
+    /// The binary format can't be borrowed on big-endian targets.
+    WrongEndian,
+    /// A `Sparse` glyph table entry or `Ranges` range has an invalid `char`.
+    BadChar(u32),
+    /// The kerning table isn't sorted properly.
+    BadKerning(KerningError),
+    /// The glyph with this index has an image extending past the end of the
+    /// bitmap data.
+    BadGlyph(usize),
+    /// The replacement glyph index is out of range.
+    BadReplacement(u8),
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::BadMagic => f.write_str("not a binary seff font"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            Self::BadStorage(s) => write!(f, "unknown glyph storage {s}"),
+            Self::BadEncoding(e) => write!(f, "unknown bitmap encoding {e}"),
+            Self::Truncated => f.write_str("font data truncated"),
+            Self::Misaligned => f.write_str("font data misaligned"),
+            Self::WrongEndian => f.write_str("binary fonts require a little-endian target"),
+            Self::BadChar(c) => write!(f, "invalid char {c:#x} in glyph table"),
+            Self::BadKerning(e) => write!(f, "bad kerning table: {e}"),
+            Self::BadGlyph(i) => write!(f, "glyph {i} image out of range"),
+            Self::BadReplacement(r) => write!(f, "replacement glyph {r} out of range"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Reads a little-endian `u32` at `offset`, which the caller has checked is in
+/// bounds.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+/// Splits `len` bytes off the front of `bytes`.
+fn take(bytes: &[u8], len: usize) -> Result<(&[u8], &[u8]), ParseError> {
+    if bytes.len() < len {
+        Err(ParseError::Truncated)
+    } else {
+        Ok(bytes.split_at(len))
+    }
+}
+
+/// Checks that each `entry_size`-byte entry in `table` starts with a valid
+/// `char`.
+fn check_chars(table: &[u8], entry_size: usize) -> Result<(), ParseError> {
+    for entry in table.chunks_exact(entry_size) {
+        let c = read_u32(entry, 0);
+        if char::from_u32(c).is_none() {
+            return Err(ParseError::BadChar(c));
+        }
+    }
+    Ok(())
+}
+
+/// Reinterprets `bytes` as a slice of `count` `T`s.
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<T>()` bytes in `bytes` must be a valid `T`.
+unsafe fn cast_table<T>(bytes: &[u8], count: usize) -> Result<&[T], ParseError> {
+    debug_assert_eq!(bytes.len(), count * size_of::<T>());
+    let ptr = bytes.as_ptr() as *const T;
+    if !ptr.is_aligned() {
+        return Err(ParseError::Misaligned);
+    }
+    Ok(core::slice::from_raw_parts(ptr, count))
+}
+
+impl<'a> Font<'a, 'a, 'a, 'a> {
+    /// Parses a font in the binary format described in the `binary` module,
+    /// borrowing its tables directly from `bytes`.
+    ///
+    /// This does not allocate, and works in `no_std` environments. `bytes`
+    /// must be aligned to at least 4 bytes.
+    ///
+    /// Since `bytes` may come from an untrusted source, this checks that every
+    /// glyph's image lies within the bitmap data and that the replacement
+    /// glyph exists, so that rendering with the resulting font can't panic.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if cfg!(target_endian = "big") {
+            return Err(ParseError::WrongEndian);
+        }
+
+        let (header, rest) = take(bytes, HEADER_LEN)?;
+        if header[0..4] != MAGIC {
+            return Err(ParseError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(ParseError::UnsupportedVersion(header[4]));
+        }
+        let storage = header[5];
+        let bitmap_encoding = match header[11] {
+            ENCODING_RAW => BitmapEncoding::Raw,
+            ENCODING_PACKBITS => BitmapEncoding::PackBits,
+            e => return Err(ParseError::BadEncoding(e)),
+        };
+        let glyph_count = read_u32(header, 12) as usize;
+        let bitmap_len = read_u32(header, 16) as usize;
+        let kerning_count = read_u32(header, 20) as usize;
+
+        let glyph_entry_size = match storage {
+            STORAGE_DENSE | STORAGE_RANGES => size_of::<Glyph>(),
+            STORAGE_SPARSE => size_of::<(char, Glyph)>(),
+            _ => return Err(ParseError::BadStorage(storage)),
+        };
+        let (range_table, rest) = if storage == STORAGE_RANGES {
+            let (count, rest) = take(rest, 4)?;
+            let range_count = read_u32(count, 0) as usize;
+            let range_table_len = range_count
+                .checked_mul(size_of::<(char, u16, u16)>())
+                .ok_or(ParseError::Truncated)?;
+            take(rest, range_table_len)?
+        } else {
+            (&[][..], rest)
+        };
+        let glyph_table_len = glyph_count.checked_mul(glyph_entry_size)
+            .ok_or(ParseError::Truncated)?;
+        let (glyph_table, rest) = take(rest, glyph_table_len)?;
+        let (bitmaps, rest) = take(rest, bitmap_len)?;
+        let kerning_table_len = kerning_count
+            .checked_mul(size_of::<KerningEntry>())
+            .ok_or(ParseError::Truncated)?;
+        let (kerning_table, _) = take(rest, kerning_table_len)?;
+
+        let glyph_storage = match storage {
+            STORAGE_DENSE => {
+                // Safety: every field of `Glyph` is an integer, so any bit
+                // pattern is valid.
+                let glyphs = unsafe { cast_table::<Glyph>(glyph_table, glyph_count)? };
+                GlyphStorage::Dense { first: header[10], glyphs }
+            }
+            STORAGE_SPARSE => {
+                check_chars(glyph_table, glyph_entry_size)?;
+                // Safety: we've just checked that every `char` is valid, and
+                // the `Glyph`s are fine as above.
+                let sorted_glyphs = unsafe {
+                    cast_table::<(char, Glyph)>(glyph_table, glyph_count)?
+                };
+                GlyphStorage::Sparse { sorted_glyphs }
+            }
+            _ => {
+                let range_size = size_of::<(char, u16, u16)>();
+                check_chars(range_table, range_size)?;
+                // Safety: as above, the `char`s are checked and everything
+                // else is integers.
+                let ranges = unsafe {
+                    cast_table::<(char, u16, u16)>(
+                        range_table,
+                        range_table.len() / range_size,
+                    )?
+                };
+                let glyphs = unsafe { cast_table::<Glyph>(glyph_table, glyph_count)? };
+                GlyphStorage::Ranges { ranges, glyphs }
+            }
+        };
+
+        let replacement = header[9];
+        if glyph_storage.get_by_index(usize::from(replacement)).is_none() {
+            return Err(ParseError::BadReplacement(replacement));
+        }
+        // Compressed images are decoded defensively, but raw ones are sliced
+        // directly out of the bitmap data.
+        if bitmap_encoding == BitmapEncoding::Raw {
+            let mut index = 0;
+            while let Some(glyph) = glyph_storage.get_by_index(index) {
+                if glyph.try_slice_bitmap(bitmaps).is_none() {
+                    return Err(ParseError::BadGlyph(index));
+                }
+                index += 1;
+            }
+        }
+
+        // Safety: every field of `KerningEntry` is an integer.
+        let entries = unsafe {
+            cast_table::<KerningEntry>(kerning_table, kerning_count)?
+        };
+
+        Ok(Font {
+            ascent: header[6],
+            descent: header[7],
+            line_spacing: header[8],
+            glyph_storage,
+            replacement,
+            replacement_char: None,
+            bitmaps,
+            bitmap_encoding,
+            kerning: Kerning::Narrow(
+                KerningTable::new_checked(entries).map_err(ParseError::BadKerning)?,
+            ),
+            advance_bias: 0,
+            ligatures: LigatureTable::default(),
+        })
+    }
+}
+
+impl Font<'static, 'static, 'static, 'static> {
+    /// Parses a font from static data, such as an embedded font file, in the
+    /// binary format described in the `binary` module.
+    ///
+    /// This is `from_bytes` for the common case of a font that lives for the
+    /// whole program. Parsing only checks the header and borrows the tables in
+    /// place, so it's cheap enough to do at each use if that's convenient.
+    /// (It can't be a `const fn` yet, because checking alignment isn't
+    /// possible in `const` contexts.)
+    ///
+    /// As with `from_bytes`, `bytes` must be aligned to at least 4 bytes; see
+    /// `Aligned`.
+    pub fn from_static(bytes: &'static [u8]) -> Result<Self, ParseError> {
+        Self::from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::load::{self, GlyphOrder};
+
+    /// A font with a single 2x2 glyph for `'A'`, as it would be embedded with
+    /// `include_bytes!`.
+    static BLOB: &Aligned<[u8]> = &Aligned([
+        // Header: Dense storage, ascent 2, descent 0, line spacing 2,
+        // replacement 0, first 'A', Raw bitmaps, and 1 glyph, 2 bitmap bytes,
+        // no kerning.
+        b'S', b'E', b'F', b'F', VERSION, STORAGE_DENSE, 2, 0,
+        2, 0, b'A', ENCODING_RAW, 1, 0, 0, 0,
+        2, 0, 0, 0, 0, 0, 0, 0,
+        // Glyph: 1 row byte, offset 0, 2 rows, origin (0, 0), advance 3.
+        1, 0, 0, 0, 2, 0, 0, 3,
+        // Bitmap.
+        0xC0, 0xC0,
+    ]);
+
+    /// A 6x3 monochrome screen.
+    struct Screen([[bool; 6]; 3]);
+
+    impl crate::RenderTarget for Screen {
+        type Pixel = bool;
+
+        fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: bool) {
+            if let Some(p) = self.0.get_mut(y).and_then(|row| row.get_mut(x)) {
+                *p = pixel;
+            }
+        }
+    }
+
+    #[test]
+    fn from_static_blob() {
+        let font = Font::from_static(&BLOB.0).unwrap();
+        assert_eq!(font.width("AA"), 6);
+
+        let mut screen = Screen([[false; 6]; 3]);
+        font.render("AA", 0, 0, &mut screen, true);
+        let expected = [
+            [1, 1, 0, 1, 1, 0],
+            [1, 1, 0, 1, 1, 0],
+            [0, 0, 0, 0, 0, 0],
+        ];
+        assert_eq!(screen.0, expected.map(|row| row.map(|p| p == 1)));
+    }
+
+    #[test]
+    fn misaligned_blob_is_an_error() {
+        let len = BLOB.0.len();
+        let mut shifted = Aligned([0; 64]);
+        shifted.0[1..][..len].copy_from_slice(&BLOB.0);
+        assert!(matches!(Font::from_bytes(&shifted.0[1..][..len]), Err(ParseError::Misaligned)));
+    }
+
+    /// Parses `BLOB` after `patch` has changed it.
+    fn parse_patched(patch: impl FnOnce(&mut [u8])) -> Result<(), ParseError> {
+        let len = BLOB.0.len();
+        let mut blob = Aligned([0; 64]);
+        blob.0[..len].copy_from_slice(&BLOB.0);
+        patch(&mut blob.0[..len]);
+        Font::from_bytes(&blob.0[..len]).map(|_| ())
+    }
+
+    #[test]
+    fn glyph_images_are_checked() {
+        assert_eq!(parse_patched(|_| ()), Ok(()));
+        // The image starts inside the bitmap data, but runs off its end.
+        assert_eq!(parse_patched(|b| b[26] = 1), Err(ParseError::BadGlyph(0)));
+        // The image is too tall for the bitmap data.
+        assert_eq!(parse_patched(|b| b[28] = 3), Err(ParseError::BadGlyph(0)));
+        // The image starts past the end of the bitmap data.
+        assert_eq!(parse_patched(|b| b[27] = 1), Err(ParseError::BadGlyph(0)));
+    }
+
+    #[test]
+    fn replacement_is_checked() {
+        assert_eq!(parse_patched(|b| b[9] = 1), Err(ParseError::BadReplacement(1)));
+    }
+
+    /// Copies `bytes` into a buffer aligned for `Font::from_bytes`.
+    #[cfg(feature = "std")]
+    fn aligned(bytes: &[u8]) -> Vec<u32> {
+        let mut words = vec![0; bytes.len().div_ceil(4)];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+            let mut le = [0; 4];
+            le[..chunk.len()].copy_from_slice(chunk);
+            *word = u32::from_le_bytes(le);
+        }
+        words
+    }
+
+    #[cfg(feature = "std")]
+    fn render(font: &Font<'_, '_, '_, '_>, text: &str) -> image::GrayImage {
+        let mut img = image::GrayImage::new(font.width(text) as u32, u32::from(font.line_spacing));
+        font.render(text, 0, 0, &mut img, image::Luma([0xFF]));
+        img
+    }
+
+    #[cfg(feature = "std")]
+    fn check(path: &str, order: GlyphOrder<'_>, first: Option<u8>, text: &str) {
+        let png = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        load::load_font_from_png(png, order, first, &Default::default(), |font| {
+            let mut bytes = vec![];
+            crate::gen::write_binary(font, &mut bytes)?;
+            let words = aligned(&bytes);
+            // Safety: the words are initialized, and `bytes.len()` of them
+            // fit in `words`.
+            let bytes = unsafe {
+                core::slice::from_raw_parts(words.as_ptr().cast::<u8>(), bytes.len())
+            };
+            let parsed = Font::from_bytes(bytes).unwrap();
+
+            assert_eq!(
+                (parsed.ascent, parsed.descent, parsed.line_spacing, parsed.replacement),
+                (font.ascent, font.descent, font.line_spacing, font.replacement),
+            );
+            for i in 0.. {
+                let glyph = parsed.glyph_storage.get_by_index(i);
+                assert_eq!(glyph, font.glyph_storage.get_by_index(i));
+                if glyph.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(parsed.bitmaps, font.bitmaps);
+            assert_eq!(render(&parsed, text), render(font, text));
+            Ok(())
+        }).unwrap();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn round_trip_dense() {
+        check("fonts/futuroid.png", GlyphOrder::Iso8859_1, Some(b' '), "Hello, world!\n~{}~");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn round_trip_sparse() {
+        check("fonts/ibm-8x8.png", GlyphOrder::Cp437, Some(0), "\u{263A} caf\u{e9} \u{2591}\u{2592}");
+    }
+}
@@ -0,0 +1,219 @@
+//! Assembling fonts in code, rather than loading them from a file.
+
+use std::collections::BTreeMap;
+
+use crate::load::{check_glyph_size, choose_storage, pack_glyph, BitmapPacker, LoadError};
+use crate::{BitmapEncoding, Glyph, OwnedFont, OwnedGlyphStorage, OwnedKerning, WideKerningEntry};
+
+/// Builds an `OwnedFont` one glyph at a time, taking care of packing glyph
+/// images into the font's bitmap data.
+///
+/// Glyphs are given as boxes of pixels whose top edge is the top of the font's
+/// bounding box, so a glyph `height` of `ascent + descent` covers the whole
+/// line. Like glyphs loaded from a sheet, each glyph's image is trimmed to its
+/// ink, and identical image data is shared between glyphs.
+#[derive(Clone, Debug, Default)]
+pub struct FontBuilder {
+    ascent: u8,
+    descent: u8,
+    line_spacing: u8,
+    glyphs: BTreeMap<char, Glyph>,
+    bitmaps: BitmapPacker,
+    kerning: BTreeMap<(char, char), i8>,
+    replacement: Option<char>,
+    advance_bias: i8,
+}
+
+impl FontBuilder {
+    /// Creates an empty builder. All metrics start out at zero; see
+    /// `set_metrics`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the font's metrics. See the fields of `Font` for their meanings.
+    pub fn set_metrics(
+        &mut self,
+        ascent: u8,
+        descent: u8,
+        line_spacing: u8,
+    ) -> &mut Self {
+        self.ascent = ascent;
+        self.descent = descent;
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Adds a glyph for `c`, replacing any glyph previously added for it.
+    ///
+    /// `pixels` gives the glyph's `width` by `height` box in row-major order,
+    /// with `true` for ink. `width` is also used as the glyph's advance, and
+    /// can be at most 64.
+    ///
+    /// # Panics
+    ///
+    /// If `pixels` doesn't contain exactly `width * height` entries.
+    pub fn add_glyph(
+        &mut self,
+        c: char,
+        width: usize,
+        height: usize,
+        pixels: &[bool],
+    ) -> Result<&mut Self, LoadError> {
+        assert_eq!(pixels.len(), width * height, "wrong number of pixels for glyph");
+        if width > 64 {
+            return Err(LoadError::GlyphTooLarge {
+                index: self.glyphs.len(),
+                ch: Some(c),
+                width,
+                height,
+            });
+        }
+
+        let rows: Vec<u64> = if width == 0 {
+            vec![0; height]
+        } else {
+            pixels.chunks(width)
+                .map(|row| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|&(_, &ink)| ink)
+                        .fold(0, |bits, (x, _)| bits | 1 << (63 - x))
+                })
+                .collect()
+        };
+        check_glyph_size(&rows, width, self.glyphs.len(), Some(c))?;
+        let glyph = pack_glyph(&rows, width, &mut self.bitmaps)?;
+        self.glyphs.insert(c, glyph);
+        Ok(self)
+    }
+
+    /// Adds a kerning entry adjusting the advance from `before` to `after` by
+    /// `adjust`, replacing any previous entry for the same pair.
+    ///
+    /// If every kerned pair is in ISO8859-1, the finished font stores its
+    /// kerning compactly; otherwise it gets a `Kerning::Wide` table.
+    pub fn add_kern(&mut self, before: char, after: char, adjust: i8) -> &mut Self {
+        self.kerning.insert((before, after), adjust);
+        self
+    }
+
+    /// Sets the font's `replacement_char`, whose glyph is drawn in place of
+    /// chars missing from the font.
+    ///
+    /// If this isn't set, or `c` has no glyph in the finished font, the
+    /// replacement is whichever glyph has index 0 -- in practice, the glyph
+    /// for the lowest char.
+    pub fn set_replacement(&mut self, c: char) -> &mut Self {
+        self.replacement = Some(c);
+        self
+    }
+
+    /// Sets the font's `advance_bias`, which adjusts the advance of every
+    /// glyph.
+    pub fn set_advance_bias(&mut self, bias: i8) -> &mut Self {
+        self.advance_bias = bias;
+        self
+    }
+
+    /// Produces the finished font.
+    ///
+    /// The glyphs are stored as `Dense` if their chars are contiguous and start
+    /// within ISO8859-1, and as `Sparse` otherwise.
+    pub fn build(&self) -> OwnedFont {
+        let chars: Vec<char> = self.glyphs.keys().copied().collect();
+        let glyphs: Vec<Glyph> = self.glyphs.values().copied().collect();
+
+        // Glyph indices are in char order for both kinds of storage.
+        let replacement = self.replacement
+            .and_then(|r| chars.binary_search(&r).ok())
+            .and_then(|i| u8::try_from(i).ok())
+            .unwrap_or(0);
+
+        let (dense_first, sorted_glyphs) = choose_storage(&chars, &glyphs);
+        let glyph_storage = match dense_first {
+            Some(first) => OwnedGlyphStorage::Dense { first, glyphs },
+            None => OwnedGlyphStorage::Sparse { sorted_glyphs },
+        };
+
+        OwnedFont {
+            ascent: self.ascent,
+            descent: self.descent,
+            line_spacing: self.line_spacing,
+            glyph_storage,
+            replacement,
+            replacement_char: self.replacement,
+            bitmaps: self.bitmaps.bitmap.clone(),
+            bitmap_encoding: BitmapEncoding::Raw,
+            kerning: OwnedKerning::from_entries(
+                self.kerning.iter()
+                    .map(|(&pair, &adjust)| WideKerningEntry { pair, adjust })
+                    .collect(),
+            ),
+            advance_bias: self.advance_bias,
+            sources: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders `s` in `font` as ASCII art, one string per row.
+    fn art(font: &crate::Font<'_, '_, '_, '_>, s: &str) -> Vec<String> {
+        let mut img = image::GrayImage::new(font.width(s) as u32, u32::from(font.line_spacing));
+        font.render(s, 0, 0, &mut img, image::Luma([0xFF]));
+        img.rows()
+            .map(|row| row.map(|p| if p[0] != 0 { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn built_font_renders() {
+        let font = FontBuilder::new()
+            .set_metrics(2, 0, 2)
+            .add_glyph('a', 2, 2, &[true, true, true, true]).unwrap()
+            .add_glyph('b', 1, 2, &[true, false]).unwrap()
+            .add_kern('a', 'b', 1)
+            .build();
+        let font = font.as_font();
+        assert!(matches!(font.glyph_storage, crate::GlyphStorage::Dense { first: b'a', .. }));
+        assert_eq!(art(&font, "ab"), ["##.#", "##.."]);
+    }
+
+    #[test]
+    fn adding_a_glyph_again_replaces_it() {
+        let font = FontBuilder::new()
+            .set_metrics(1, 0, 1)
+            .add_glyph('a', 2, 1, &[true, false]).unwrap()
+            .add_glyph('a', 3, 1, &[false, false, true]).unwrap()
+            .build();
+        let font = font.as_font();
+        assert_eq!(art(&font, "a"), ["..#"]);
+    }
+
+    #[test]
+    fn too_wide_glyph_is_an_error() {
+        let mut builder = FontBuilder::new();
+        assert!(matches!(
+            builder.add_glyph('a', 65, 1, &[true; 65]),
+            Err(LoadError::GlyphTooLarge { index: 0, ch: Some('a'), width: 65, height: 1 }),
+        ));
+        assert!(builder.add_glyph('a', 64, 1, &[true; 64]).is_ok());
+    }
+
+    #[test]
+    fn oversized_glyphs_are_errors() {
+        let mut builder = FontBuilder::new();
+        builder.add_glyph('a', 2, 1, &[true, true]).unwrap();
+        let err = builder.add_glyph('b', 70, 1, &[true; 70]).unwrap_err();
+        assert!(matches!(
+            err,
+            LoadError::GlyphTooLarge { index: 1, ch: Some('b'), width: 70, height: 1 },
+        ));
+        assert_eq!(err.to_string(), "glyph 1 ('b') is too large (70x1 pixels)");
+        let err = builder.add_glyph('c', 1, 300, &[true; 300]).unwrap_err();
+        assert!(matches!(err, LoadError::GlyphTooLarge { width: 1, height: 300, .. }));
+    }
+}
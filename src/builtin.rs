@@ -0,0 +1,31 @@
+//! A small font built into the crate, for drawing text without any assets.
+
+// This is the output of the `img` tool for `fonts/tiny-5x7.png`. To regenerate
+// it after editing the PNG:
+//
+//     cargo run --features std --bin img -- --first-char ' ' --crate-path crate \
+//         fonts/tiny-5x7.png -o src/builtin/tiny_5x7.rs
+#[rustfmt::skip]
+mod tiny_5x7;
+
+/// A public domain 5x7 pixel font covering printable ASCII, `' '` through
+/// `'~'`. Glyphs sit on a 6x8 pixel cell, with one row of descent below the
+/// baseline.
+///
+/// This is meant for diagnostics and debug output, when you'd rather not ship
+/// a font of your own. It's available with the `builtin-font` feature.
+pub static DEFAULT_FONT: crate::Font<'static, 'static, 'static, 'static> = tiny_5x7::FONT;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn covers_printable_ascii() {
+        assert!(DEFAULT_FONT.width("Hello") > 0);
+        for c in ' '..='~' {
+            assert!(DEFAULT_FONT.glyph_storage.get(c).is_some(), "{c:?}");
+        }
+        assert!(DEFAULT_FONT.glyph_storage.get('\u{7f}').is_none());
+    }
+}
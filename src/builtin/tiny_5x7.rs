@@ -0,0 +1,1522 @@
+pub static FONT: crate::Font = crate::Font {
+    ascent: 7,
+    descent: 1,
+    line_spacing: 8,
+    glyph_storage: crate::GlyphStorage::Dense {
+        first: 32,
+        glyphs: &GLYPHS,
+    },
+    replacement: 0,
+    replacement_char: None,
+    bitmaps: &BITMAPS,
+    bitmap_encoding: crate::BitmapEncoding::Raw,
+    kerning: crate::Kerning::Narrow(crate::KerningTable { entries: &KERNING_ENTRIES }),
+    advance_bias: 0,
+    ligatures: crate::LigatureTable { entries: &LIGATURES },
+};
+pub static GLYPHS: [crate::Glyph; 95] = [
+    // index 0: ' ', from page 0 at (0, 0), 6x8
+    crate::Glyph {
+        row_bytes: 0,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 0,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 1: '!', from page 0 at (7, 0), 6x8
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    // | |
+    // |*|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 1,
+        image_offset: 0,
+        image_height: 7,
+        origin: (2, 0),
+        advance: 6,
+    },
+    // index 2: '"', from page 0 at (14, 0), 6x8
+    // |* *|
+    // |* *|
+    // |* *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 7,
+        image_height: 3,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 3: '#', from page 0 at (21, 0), 6x8
+    // | * * |
+    // | * * |
+    // |*****|
+    // | * * |
+    // |*****|
+    // | * * |
+    // | * * |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 10,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 4: '$', from page 0 at (28, 0), 6x8
+    // |  *  |
+    // | ****|
+    // |* *  |
+    // | *** |
+    // |  * *|
+    // |**** |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 17,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 5: '%', from page 0 at (35, 0), 6x8
+    // |**   |
+    // |**  *|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // |*  **|
+    // |   **|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 24,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 6: '&', from page 0 at (42, 0), 6x8
+    // | **  |
+    // |*  * |
+    // |* *  |
+    // | *   |
+    // |* * *|
+    // |*  * |
+    // | ** *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 31,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 7: '\'', from page 0 at (49, 0), 6x8
+    // | *|
+    // | *|
+    // |* |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 2,
+        image_offset: 38,
+        image_height: 3,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 8: '(', from page 0 at (56, 0), 6x8
+    // |  *|
+    // | * |
+    // |*  |
+    // |*  |
+    // |*  |
+    // | * |
+    // |  *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 41,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 9: ')', from page 0 at (63, 0), 6x8
+    // |*  |
+    // | * |
+    // |  *|
+    // |  *|
+    // |  *|
+    // | * |
+    // |*  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 48,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 10: '*', from page 0 at (70, 0), 6x8
+    // |  *  |
+    // |* * *|
+    // | *** |
+    // |* * *|
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 55,
+        image_height: 5,
+        origin: (0, 1),
+        advance: 6,
+    },
+    // index 11: '+', from page 0 at (77, 0), 6x8
+    // |  *  |
+    // |  *  |
+    // |*****|
+    // |  *  |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 60,
+        image_height: 5,
+        origin: (0, 1),
+        advance: 6,
+    },
+    // index 12: ',', from page 0 at (84, 0), 6x8
+    // |**|
+    // | *|
+    // |* |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 2,
+        image_offset: 65,
+        image_height: 3,
+        origin: (1, 5),
+        advance: 6,
+    },
+    // index 13: '-', from page 0 at (91, 0), 6x8
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 68,
+        image_height: 1,
+        origin: (0, 3),
+        advance: 6,
+    },
+    // index 14: '.', from page 0 at (98, 0), 6x8
+    // |**|
+    // |**|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 2,
+        image_offset: 69,
+        image_height: 2,
+        origin: (1, 5),
+        advance: 6,
+    },
+    // index 15: '/', from page 0 at (105, 0), 6x8
+    // |    *|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // |*    |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 71,
+        image_height: 5,
+        origin: (0, 1),
+        advance: 6,
+    },
+    // index 16: '0', from page 0 at (0, 9), 6x8
+    // | *** |
+    // |*   *|
+    // |*  **|
+    // |* * *|
+    // |**  *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 76,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 17: '1', from page 0 at (7, 9), 6x8
+    // | * |
+    // |** |
+    // | * |
+    // | * |
+    // | * |
+    // | * |
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 83,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 18: '2', from page 0 at (14, 9), 6x8
+    // | *** |
+    // |*   *|
+    // |    *|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 90,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 19: '3', from page 0 at (21, 9), 6x8
+    // |*****|
+    // |   * |
+    // |  *  |
+    // |   * |
+    // |    *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 97,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 20: '4', from page 0 at (28, 9), 6x8
+    // |   * |
+    // |  ** |
+    // | * * |
+    // |*  * |
+    // |*****|
+    // |   * |
+    // |   * |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 104,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 21: '5', from page 0 at (35, 9), 6x8
+    // |*****|
+    // |*    |
+    // |**** |
+    // |    *|
+    // |    *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 111,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 22: '6', from page 0 at (42, 9), 6x8
+    // |  ** |
+    // | *   |
+    // |*    |
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 118,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 23: '7', from page 0 at (49, 9), 6x8
+    // |*****|
+    // |    *|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // | *   |
+    // | *   |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 125,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 24: '8', from page 0 at (56, 9), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 132,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 25: '9', from page 0 at (63, 9), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // | ****|
+    // |    *|
+    // |   * |
+    // | **  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 139,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 26: ':', from page 0 at (70, 9), 6x8
+    // |**|
+    // |**|
+    // |  |
+    // |**|
+    // |**|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 2,
+        image_offset: 146,
+        image_height: 5,
+        origin: (1, 1),
+        advance: 6,
+    },
+    // index 27: ';', from page 0 at (77, 9), 6x8
+    // |**|
+    // |**|
+    // |  |
+    // |**|
+    // | *|
+    // |* |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 2,
+        image_offset: 151,
+        image_height: 6,
+        origin: (1, 1),
+        advance: 6,
+    },
+    // index 28: '<', from page 0 at (84, 9), 6x8
+    // |   *|
+    // |  * |
+    // | *  |
+    // |*   |
+    // | *  |
+    // |  * |
+    // |   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 4,
+        image_offset: 157,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 29: '=', from page 0 at (91, 9), 6x8
+    // |*****|
+    // |     |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 164,
+        image_height: 3,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 30: '>', from page 0 at (98, 9), 6x8
+    // |*   |
+    // | *  |
+    // |  * |
+    // |   *|
+    // |  * |
+    // | *  |
+    // |*   |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 4,
+        image_offset: 167,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 31: '?', from page 0 at (105, 9), 6x8
+    // | *** |
+    // |*   *|
+    // |    *|
+    // |   * |
+    // |  *  |
+    // |     |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 174,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 32: '@', from page 0 at (0, 18), 6x8
+    // | *** |
+    // |*   *|
+    // |    *|
+    // | ** *|
+    // |* * *|
+    // |* * *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 181,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 33: 'A', from page 0 at (7, 18), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // |*****|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 188,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 34: 'B', from page 0 at (14, 18), 6x8
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // |**** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 195,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 35: 'C', from page 0 at (21, 18), 6x8
+    // | *** |
+    // |*   *|
+    // |*    |
+    // |*    |
+    // |*    |
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 202,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 36: 'D', from page 0 at (28, 18), 6x8
+    // |***  |
+    // |*  * |
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*  * |
+    // |***  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 209,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 37: 'E', from page 0 at (35, 18), 6x8
+    // |*****|
+    // |*    |
+    // |*    |
+    // |**** |
+    // |*    |
+    // |*    |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 216,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 38: 'F', from page 0 at (42, 18), 6x8
+    // |*****|
+    // |*    |
+    // |*    |
+    // |**** |
+    // |*    |
+    // |*    |
+    // |*    |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 223,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 39: 'G', from page 0 at (49, 18), 6x8
+    // | *** |
+    // |*   *|
+    // |*    |
+    // |* ***|
+    // |*   *|
+    // |*   *|
+    // | ****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 230,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 40: 'H', from page 0 at (56, 18), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*****|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 237,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 41: 'I', from page 0 at (63, 18), 6x8
+    // |***|
+    // | * |
+    // | * |
+    // | * |
+    // | * |
+    // | * |
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 244,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 42: 'J', from page 0 at (70, 18), 6x8
+    // |  ***|
+    // |   * |
+    // |   * |
+    // |   * |
+    // |   * |
+    // |*  * |
+    // | **  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 251,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 43: 'K', from page 0 at (77, 18), 6x8
+    // |*   *|
+    // |*  * |
+    // |* *  |
+    // |**   |
+    // |* *  |
+    // |*  * |
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 258,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 44: 'L', from page 0 at (84, 18), 6x8
+    // |*    |
+    // |*    |
+    // |*    |
+    // |*    |
+    // |*    |
+    // |*    |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 265,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 45: 'M', from page 0 at (91, 18), 6x8
+    // |*   *|
+    // |** **|
+    // |* * *|
+    // |* * *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 272,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 46: 'N', from page 0 at (98, 18), 6x8
+    // |*   *|
+    // |*   *|
+    // |**  *|
+    // |* * *|
+    // |*  **|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 279,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 47: 'O', from page 0 at (105, 18), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 286,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 48: 'P', from page 0 at (0, 27), 6x8
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // |**** |
+    // |*    |
+    // |*    |
+    // |*    |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 293,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 49: 'Q', from page 0 at (7, 27), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |* * *|
+    // |*  * |
+    // | ** *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 300,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 50: 'R', from page 0 at (14, 27), 6x8
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // |**** |
+    // |* *  |
+    // |*  * |
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 307,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 51: 'S', from page 0 at (21, 27), 6x8
+    // | ****|
+    // |*    |
+    // |*    |
+    // | *** |
+    // |    *|
+    // |    *|
+    // |**** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 314,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 52: 'T', from page 0 at (28, 27), 6x8
+    // |*****|
+    // |  *  |
+    // |  *  |
+    // |  *  |
+    // |  *  |
+    // |  *  |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 321,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 53: 'U', from page 0 at (35, 27), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 328,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 54: 'V', from page 0 at (42, 27), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | * * |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 335,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 55: 'W', from page 0 at (49, 27), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |* * *|
+    // |* * *|
+    // |* * *|
+    // | * * |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 342,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 56: 'X', from page 0 at (56, 27), 6x8
+    // |*   *|
+    // |*   *|
+    // | * * |
+    // |  *  |
+    // | * * |
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 349,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 57: 'Y', from page 0 at (63, 27), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | * * |
+    // |  *  |
+    // |  *  |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 356,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 58: 'Z', from page 0 at (70, 27), 6x8
+    // |*****|
+    // |    *|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // |*    |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 363,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 59: '[', from page 0 at (77, 27), 6x8
+    // |***|
+    // |*  |
+    // |*  |
+    // |*  |
+    // |*  |
+    // |*  |
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 370,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 60: '\\', from page 0 at (84, 27), 6x8
+    // |*    |
+    // | *   |
+    // |  *  |
+    // |   * |
+    // |    *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 377,
+        image_height: 5,
+        origin: (0, 1),
+        advance: 6,
+    },
+    // index 61: ']', from page 0 at (91, 27), 6x8
+    // |***|
+    // |  *|
+    // |  *|
+    // |  *|
+    // |  *|
+    // |  *|
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 382,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 62: '^', from page 0 at (98, 27), 6x8
+    // |  *  |
+    // | * * |
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 389,
+        image_height: 3,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 63: '_', from page 0 at (105, 27), 6x8
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 68,
+        image_height: 1,
+        origin: (0, 6),
+        advance: 6,
+    },
+    // index 64: '`', from page 0 at (0, 36), 6x8
+    // |*  |
+    // | * |
+    // |  *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 45,
+        image_height: 3,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 65: 'a', from page 0 at (7, 36), 6x8
+    // | *** |
+    // |    *|
+    // | ****|
+    // |*   *|
+    // | ****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 392,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 66: 'b', from page 0 at (14, 36), 6x8
+    // |*    |
+    // |*    |
+    // |* ** |
+    // |**  *|
+    // |*   *|
+    // |*   *|
+    // |**** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 397,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 67: 'c', from page 0 at (21, 36), 6x8
+    // | *** |
+    // |*    |
+    // |*    |
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 404,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 68: 'd', from page 0 at (28, 36), 6x8
+    // |    *|
+    // |    *|
+    // | ** *|
+    // |*  **|
+    // |*   *|
+    // |*   *|
+    // | ****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 409,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 69: 'e', from page 0 at (35, 36), 6x8
+    // | *** |
+    // |*   *|
+    // |*****|
+    // |*    |
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 416,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 70: 'f', from page 0 at (42, 36), 6x8
+    // |  ** |
+    // | *  *|
+    // | *   |
+    // |***  |
+    // | *   |
+    // | *   |
+    // | *   |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 421,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 71: 'g', from page 0 at (49, 36), 6x8
+    // | ****|
+    // |*   *|
+    // |*   *|
+    // | ****|
+    // |    *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 428,
+        image_height: 6,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 72: 'h', from page 0 at (56, 36), 6x8
+    // |*    |
+    // |*    |
+    // |* ** |
+    // |**  *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 434,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 73: 'i', from page 0 at (63, 36), 6x8
+    // | * |
+    // |   |
+    // |** |
+    // | * |
+    // | * |
+    // | * |
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 441,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 74: 'j', from page 0 at (70, 36), 6x8
+    // |   *|
+    // |    |
+    // |  **|
+    // |   *|
+    // |   *|
+    // |   *|
+    // |*  *|
+    // | ** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 4,
+        image_offset: 448,
+        image_height: 8,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 75: 'k', from page 0 at (77, 36), 6x8
+    // |*   |
+    // |*   |
+    // |*  *|
+    // |* * |
+    // |**  |
+    // |* * |
+    // |*  *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 4,
+        image_offset: 456,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 76: 'l', from page 0 at (84, 36), 6x8
+    // |** |
+    // | * |
+    // | * |
+    // | * |
+    // | * |
+    // | * |
+    // |***|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 463,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 77: 'm', from page 0 at (91, 36), 6x8
+    // |** * |
+    // |* * *|
+    // |* * *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 470,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 78: 'n', from page 0 at (98, 36), 6x8
+    // |* ** |
+    // |**  *|
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 436,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 79: 'o', from page 0 at (105, 36), 6x8
+    // | *** |
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 475,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 80: 'p', from page 0 at (0, 45), 6x8
+    // |**** |
+    // |*   *|
+    // |*   *|
+    // |**** |
+    // |*    |
+    // |*    |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 480,
+        image_height: 6,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 81: 'q', from page 0 at (7, 45), 6x8
+    // | ****|
+    // |*   *|
+    // |*   *|
+    // | ****|
+    // |    *|
+    // |    *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 486,
+        image_height: 6,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 82: 'r', from page 0 at (14, 45), 6x8
+    // |* ** |
+    // |**  *|
+    // |*    |
+    // |*    |
+    // |*    |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 492,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 83: 's', from page 0 at (21, 45), 6x8
+    // | ****|
+    // |*    |
+    // | *** |
+    // |    *|
+    // |**** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 497,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 84: 't', from page 0 at (28, 45), 6x8
+    // | *   |
+    // | *   |
+    // |***  |
+    // | *   |
+    // | *   |
+    // | *  *|
+    // |  ** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 502,
+        image_height: 7,
+        origin: (0, 0),
+        advance: 6,
+    },
+    // index 85: 'u', from page 0 at (35, 45), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // |*  **|
+    // | ** *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 509,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 86: 'v', from page 0 at (42, 45), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | * * |
+    // |  *  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 337,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 87: 'w', from page 0 at (49, 45), 6x8
+    // |*   *|
+    // |*   *|
+    // |* * *|
+    // |* * *|
+    // | * * |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 514,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 88: 'x', from page 0 at (56, 45), 6x8
+    // |*   *|
+    // | * * |
+    // |  *  |
+    // | * * |
+    // |*   *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 519,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 89: 'y', from page 0 at (63, 45), 6x8
+    // |*   *|
+    // |*   *|
+    // |*   *|
+    // | ****|
+    // |    *|
+    // | *** |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 524,
+        image_height: 6,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 90: 'z', from page 0 at (70, 45), 6x8
+    // |*****|
+    // |   * |
+    // |  *  |
+    // | *   |
+    // |*****|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 530,
+        image_height: 5,
+        origin: (0, 2),
+        advance: 6,
+    },
+    // index 91: '{', from page 0 at (77, 45), 6x8
+    // |  *|
+    // | * |
+    // | * |
+    // |*  |
+    // | * |
+    // | * |
+    // |  *|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 535,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 92: '|', from page 0 at (84, 45), 6x8
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    // |*|
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 1,
+        image_offset: 542,
+        image_height: 7,
+        origin: (2, 0),
+        advance: 6,
+    },
+    // index 93: '}', from page 0 at (91, 45), 6x8
+    // |*  |
+    // | * |
+    // | * |
+    // |  *|
+    // | * |
+    // | * |
+    // |*  |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 3,
+        image_offset: 549,
+        image_height: 7,
+        origin: (1, 0),
+        advance: 6,
+    },
+    // index 94: '~', from page 0 at (98, 45), 6x8
+    // | *   |
+    // |* * *|
+    // |   * |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 5,
+        image_offset: 556,
+        image_height: 3,
+        origin: (0, 2),
+        advance: 6,
+    },
+];
+pub static KERNING_ENTRIES: [crate::KerningEntry; 0] = [
+];
+pub static LIGATURES: [(&str, u16); 0] = [
+];
+pub static BITMAPS: [u8; 559] = [
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0xa0,
+    0xa0, 0xa0, 0x50, 0x50, 0xf8, 0x50, 0xf8, 0x50,
+    0x50, 0x20, 0x78, 0xa0, 0x70, 0x28, 0xf0, 0x20,
+    0xc0, 0xc8, 0x10, 0x20, 0x40, 0x98, 0x18, 0x60,
+    0x90, 0xa0, 0x40, 0xa8, 0x90, 0x68, 0x40, 0x40,
+    0x80, 0x20, 0x40, 0x80, 0x80, 0x80, 0x40, 0x20,
+    0x80, 0x40, 0x20, 0x20, 0x20, 0x40, 0x80, 0x20,
+    0xa8, 0x70, 0xa8, 0x20, 0x20, 0x20, 0xf8, 0x20,
+    0x20, 0xc0, 0x40, 0x80, 0xf8, 0xc0, 0xc0, 0x08,
+    0x10, 0x20, 0x40, 0x80, 0x70, 0x88, 0x98, 0xa8,
+    0xc8, 0x88, 0x70, 0x40, 0xc0, 0x40, 0x40, 0x40,
+    0x40, 0xe0, 0x70, 0x88, 0x08, 0x10, 0x20, 0x40,
+    0xf8, 0xf8, 0x10, 0x20, 0x10, 0x08, 0x88, 0x70,
+    0x10, 0x30, 0x50, 0x90, 0xf8, 0x10, 0x10, 0xf8,
+    0x80, 0xf0, 0x08, 0x08, 0x88, 0x70, 0x30, 0x40,
+    0x80, 0xf0, 0x88, 0x88, 0x70, 0xf8, 0x08, 0x10,
+    0x20, 0x40, 0x40, 0x40, 0x70, 0x88, 0x88, 0x70,
+    0x88, 0x88, 0x70, 0x70, 0x88, 0x88, 0x78, 0x08,
+    0x10, 0x60, 0xc0, 0xc0, 0x00, 0xc0, 0xc0, 0xc0,
+    0xc0, 0x00, 0xc0, 0x40, 0x80, 0x10, 0x20, 0x40,
+    0x80, 0x40, 0x20, 0x10, 0xf8, 0x00, 0xf8, 0x80,
+    0x40, 0x20, 0x10, 0x20, 0x40, 0x80, 0x70, 0x88,
+    0x08, 0x10, 0x20, 0x00, 0x20, 0x70, 0x88, 0x08,
+    0x68, 0xa8, 0xa8, 0x70, 0x70, 0x88, 0x88, 0xf8,
+    0x88, 0x88, 0x88, 0xf0, 0x88, 0x88, 0xf0, 0x88,
+    0x88, 0xf0, 0x70, 0x88, 0x80, 0x80, 0x80, 0x88,
+    0x70, 0xe0, 0x90, 0x88, 0x88, 0x88, 0x90, 0xe0,
+    0xf8, 0x80, 0x80, 0xf0, 0x80, 0x80, 0xf8, 0xf8,
+    0x80, 0x80, 0xf0, 0x80, 0x80, 0x80, 0x70, 0x88,
+    0x80, 0xb8, 0x88, 0x88, 0x78, 0x88, 0x88, 0x88,
+    0xf8, 0x88, 0x88, 0x88, 0xe0, 0x40, 0x40, 0x40,
+    0x40, 0x40, 0xe0, 0x38, 0x10, 0x10, 0x10, 0x10,
+    0x90, 0x60, 0x88, 0x90, 0xa0, 0xc0, 0xa0, 0x90,
+    0x88, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xf8,
+    0x88, 0xd8, 0xa8, 0xa8, 0x88, 0x88, 0x88, 0x88,
+    0x88, 0xc8, 0xa8, 0x98, 0x88, 0x88, 0x70, 0x88,
+    0x88, 0x88, 0x88, 0x88, 0x70, 0xf0, 0x88, 0x88,
+    0xf0, 0x80, 0x80, 0x80, 0x70, 0x88, 0x88, 0x88,
+    0xa8, 0x90, 0x68, 0xf0, 0x88, 0x88, 0xf0, 0xa0,
+    0x90, 0x88, 0x78, 0x80, 0x80, 0x70, 0x08, 0x08,
+    0xf0, 0xf8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20,
+    0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x88,
+    0x88, 0x88, 0x88, 0x88, 0x50, 0x20, 0x88, 0x88,
+    0x88, 0xa8, 0xa8, 0xa8, 0x50, 0x88, 0x88, 0x50,
+    0x20, 0x50, 0x88, 0x88, 0x88, 0x88, 0x88, 0x50,
+    0x20, 0x20, 0x20, 0xf8, 0x08, 0x10, 0x20, 0x40,
+    0x80, 0xf8, 0xe0, 0x80, 0x80, 0x80, 0x80, 0x80,
+    0xe0, 0x80, 0x40, 0x20, 0x10, 0x08, 0xe0, 0x20,
+    0x20, 0x20, 0x20, 0x20, 0xe0, 0x20, 0x50, 0x88,
+    0x70, 0x08, 0x78, 0x88, 0x78, 0x80, 0x80, 0xb0,
+    0xc8, 0x88, 0x88, 0xf0, 0x70, 0x80, 0x80, 0x88,
+    0x70, 0x08, 0x08, 0x68, 0x98, 0x88, 0x88, 0x78,
+    0x70, 0x88, 0xf8, 0x80, 0x70, 0x30, 0x48, 0x40,
+    0xe0, 0x40, 0x40, 0x40, 0x78, 0x88, 0x88, 0x78,
+    0x08, 0x70, 0x80, 0x80, 0xb0, 0xc8, 0x88, 0x88,
+    0x88, 0x40, 0x00, 0xc0, 0x40, 0x40, 0x40, 0xe0,
+    0x10, 0x00, 0x30, 0x10, 0x10, 0x10, 0x90, 0x60,
+    0x80, 0x80, 0x90, 0xa0, 0xc0, 0xa0, 0x90, 0xc0,
+    0x40, 0x40, 0x40, 0x40, 0x40, 0xe0, 0xd0, 0xa8,
+    0xa8, 0x88, 0x88, 0x70, 0x88, 0x88, 0x88, 0x70,
+    0xf0, 0x88, 0x88, 0xf0, 0x80, 0x80, 0x78, 0x88,
+    0x88, 0x78, 0x08, 0x08, 0xb0, 0xc8, 0x80, 0x80,
+    0x80, 0x78, 0x80, 0x70, 0x08, 0xf0, 0x40, 0x40,
+    0xe0, 0x40, 0x40, 0x48, 0x30, 0x88, 0x88, 0x88,
+    0x98, 0x68, 0x88, 0x88, 0xa8, 0xa8, 0x50, 0x88,
+    0x50, 0x20, 0x50, 0x88, 0x88, 0x88, 0x88, 0x78,
+    0x08, 0x70, 0xf8, 0x10, 0x20, 0x40, 0xf8, 0x20,
+    0x40, 0x40, 0x80, 0x40, 0x40, 0x20, 0x80, 0x80,
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40, 0x40,
+    0x20, 0x40, 0x40, 0x80, 0x40, 0xa8, 0x10,
+];
@@ -0,0 +1,110 @@
+//! Remembering the widths of strings that get measured over and over.
+
+use std::collections::HashMap;
+
+use crate::Font;
+
+/// Widths of strings previously measured with `Font::cached_width`.
+///
+/// Because of kerning, a string's width depends on its exact contents, so
+/// entries are keyed on the whole string. Entries are also only valid for the
+/// font that measured them; use a separate cache for each font, or `clear` it
+/// when switching fonts.
+#[derive(Clone, Debug, Default)]
+pub struct WidthCache {
+    widths: HashMap<String, usize>,
+}
+
+impl WidthCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the width of `s`, so that it will be measured again next time.
+    pub fn invalidate(&mut self, s: &str) {
+        self.widths.remove(s);
+    }
+
+    /// Forgets the widths of all strings.
+    pub fn clear(&mut self) {
+        self.widths.clear();
+    }
+
+    /// Checks whether the width of `s` has been cached.
+    pub fn contains(&self, s: &str) -> bool {
+        self.widths.contains_key(s)
+    }
+
+    /// Returns the number of cached widths.
+    pub fn len(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.widths.is_empty()
+    }
+}
+
+impl Font<'_, '_, '_, '_> {
+    /// Computes the width of `s` like `width`, but reuses the result from
+    /// `cache` if `s` has been measured before, and records it there if not.
+    pub fn cached_width(&self, cache: &mut WidthCache, s: &str) -> usize {
+        if let Some(&w) = cache.widths.get(s) {
+            return w;
+        }
+        let w = self.width(s);
+        cache.widths.insert(s.to_owned(), w);
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Kerning, KerningEntry, KerningTable};
+    use crate::load::{load_font_from_png, GlyphOrder};
+
+    /// Calls `body` with the futuroid font, as is and with "AV" kerned.
+    fn with_fonts(body: impl FnOnce(&Font<'_, '_, '_, '_>, &Font<'_, '_, '_, '_>)) {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', b'V'), adjust: -2 }];
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        load_font_from_png(png, GlyphOrder::Iso8859_1, Some(b' '), &Default::default(), |font| {
+            body(font, &Font { kerning: Kerning::Narrow(KerningTable::new(&KERNING)), ..*font });
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn hits_match_width() {
+        with_fonts(|font, _| {
+            let mut cache = WidthCache::new();
+            let first = font.cached_width(&mut cache, "AVA");
+            assert_eq!(first, font.width("AVA"));
+            assert!(cache.contains("AVA"));
+            assert_eq!(font.cached_width(&mut cache, "AVA"), first);
+            assert_eq!(cache.len(), 1);
+        });
+    }
+
+    #[test]
+    fn clearing_forces_remeasuring() {
+        with_fonts(|font, kerned| {
+            assert_ne!(font.width("AV"), kerned.width("AV"));
+            let mut cache = WidthCache::new();
+            font.cached_width(&mut cache, "AV");
+            font.cached_width(&mut cache, "VA");
+
+            // The cache doesn't know the font changed...
+            assert_eq!(kerned.cached_width(&mut cache, "AV"), font.width("AV"));
+            // ...until the entry is dropped.
+            cache.invalidate("AV");
+            assert_eq!(kerned.cached_width(&mut cache, "AV"), kerned.width("AV"));
+
+            cache.clear();
+            assert!(cache.is_empty());
+            assert_eq!(kerned.cached_width(&mut cache, "VA"), kerned.width("VA"));
+        });
+    }
+}
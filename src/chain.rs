@@ -0,0 +1,256 @@
+//! Falling back across several fonts.
+
+use crate::{Cluster, DirectRenderTarget, Font, Glyph, KerningState, RenderTarget};
+
+/// An ordered list of fonts used together, so that chars missing in one font
+/// can be drawn from the next.
+///
+/// Each char in a string is drawn using the first font in the chain that has a
+/// glyph for it. Chars that none of the fonts have are drawn using the
+/// replacement glyph from the _last_ font in the chain. Kerning applies
+/// between consecutive chars drawn from the same font, and resets wherever the
+/// string switches fonts. Likewise, a font's ligatures are used if the font is
+/// the one chosen for the ligature's first char.
+///
+/// Fonts in the chain may differ in size. Glyphs are positioned so that all
+/// the fonts share a baseline, and the chain's bounding box is tall enough to
+/// fit the ascent and descent of any of its fonts.
+///
+/// An empty chain has no glyphs, and draws nothing.
+#[derive(Copy, Clone, Debug)]
+pub struct FontChain<'a> {
+    pub fonts: &'a [Font<'a, 'a, 'a, 'a>],
+}
+
+impl<'a> FontChain<'a> {
+    pub fn new(fonts: &'a [Font<'a, 'a, 'a, 'a>]) -> Self {
+        Self { fonts }
+    }
+
+    /// Displacement from the top of the chain's bounding box to the baseline,
+    /// in pixels: the largest ascent of any font in the chain.
+    pub fn ascent(&self) -> u8 {
+        self.fonts.iter().map(|f| f.ascent).max().unwrap_or(0)
+    }
+
+    /// Displacement from the baseline to the bottom of the chain's bounding
+    /// box, in pixels: the largest descent of any font in the chain.
+    pub fn descent(&self) -> u8 {
+        self.fonts.iter().map(|f| f.descent).max().unwrap_or(0)
+    }
+
+    /// See `Font::baseline_to_y`.
+    pub fn baseline_to_y(&self, baseline: usize) -> Option<usize> {
+        baseline.checked_sub(usize::from(self.ascent()))
+    }
+
+    /// Returns the largest line spacing of any font in the chain, as a `usize`
+    /// for convenience.
+    pub fn line_spacing_usize(&self) -> usize {
+        self.fonts.iter().map(|f| f.line_spacing_usize()).max().unwrap_or(0)
+    }
+
+    /// Finds the font that will be used to draw `c`, returning its index in
+    /// the chain along with the glyph.
+    ///
+    /// This returns `None` only if the chain is empty.
+    pub fn get_glyph_or_replacement(&self, c: char) -> Option<(usize, &Glyph)> {
+        self.fonts.iter()
+            .enumerate()
+            .find_map(|(i, f)| Some((i, f.glyph_storage.get(c)?)))
+            .or_else(|| {
+                let last = self.fonts.len().checked_sub(1)?;
+                Some((last, self.fonts[last].get_glyph_or_replacement(c)))
+            })
+    }
+
+    /// Computes the width, in pixels, of the string `s` rendered in this
+    /// chain. This handles kerning but not line breaks, just like
+    /// `Font::width`.
+    pub fn width(&self, s: &str) -> usize {
+        self.layout(s, 0, |_, _, _| ())
+    }
+
+    /// Renders text on a single line. See `Font::render` for details.
+    pub fn render<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            crate::paint_slow(target, fg, gx, gy, glyph, slice)
+        });
+    }
+
+    /// Renders text on a single line, slightly faster. See
+    /// `Font::render_direct` for details.
+    pub fn render_direct<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: DirectRenderTarget,
+    {
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            crate::paint_direct(target, fg, gx, gy, glyph, slice)
+        });
+    }
+
+    /// Implementation factor of both `render` and `render_direct`. This
+    /// behaves like `Font::render_core`, with each glyph's data coming from
+    /// whichever font in the chain it was found in.
+    pub fn render_core(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        mut action: impl FnMut(usize, usize, &Glyph, &[u8]),
+    ) {
+        let ascent = self.ascent();
+        self.layout(string, x, |pen_x, font, glyph| {
+            // Line up this font's baseline with the chain's.
+            let font_y = y.saturating_add(usize::from(ascent - font.ascent));
+            font.draw_glyph(glyph, pen_x, font_y, &mut action);
+        });
+    }
+
+    /// Walks the chars of `s` starting with the pen at `x`, calling `each` with
+    /// the pen position, font, and glyph for each char (or ligature) after
+    /// kerning, and returns the final pen position.
+    fn layout(
+        &self,
+        s: &str,
+        x: usize,
+        mut each: impl FnMut(usize, &Font<'a, 'a, 'a, 'a>, &Glyph),
+    ) -> usize {
+        let mut pen_x = x;
+        let mut run: Option<(usize, KerningState<'a>)> = None;
+
+        let mut rest = s.char_indices();
+        while let Some((start, c)) = rest.next() {
+            let Some((i, glyph)) = self.get_glyph_or_replacement(c) else {
+                break;
+            };
+            let font = &self.fonts[i];
+
+            let missing = font.glyph_storage.get(c).is_none();
+            let mut cluster = Cluster { start, chars: 1, first: c, last: c, glyph, missing };
+            font.apply_ligature(s, &mut cluster, &mut rest);
+
+            let kerning = match &mut run {
+                Some((ri, kerning)) if *ri == i => kerning,
+                _ => &mut run.insert((i, font.start_kerning())).1,
+            };
+            kerning.adjust_usize_for_cluster(&cluster, &mut pen_x);
+
+            each(pen_x, font, cluster.glyph);
+
+            pen_x = pen_x.saturating_add(font.advance_usize(cluster.glyph));
+        }
+        pen_x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitmapEncoding, GlyphStorage, Kerning, KerningEntry, KerningTable, LigatureTable};
+
+    const fn glyph(origin_y: u8, advance: u8) -> Glyph {
+        Glyph {
+        pixel_width: 0,
+            row_bytes: 1,
+            image_offset: 0,
+            image_height: 1,
+            origin: (0, origin_y),
+            advance,
+        }
+    }
+
+    static SMALL_GLYPHS: [Glyph; 1] = [glyph(2, 3)];
+    static BIG_GLYPHS: [Glyph; 1] = [glyph(4, 5)];
+    static SMALL_KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'a', b'a'), adjust: -1 }];
+
+    /// Two fonts, each drawing one bar on its baseline: a short one with an
+    /// ascent of 3 for `'a'`, which is kerned against itself, and a longer
+    /// one with an ascent of 5 for `'Z'`.
+    static FONTS: [Font<'static, 'static, 'static, 'static>; 2] = [
+        Font {
+            ascent: 3,
+            descent: 1,
+            line_spacing: 4,
+            glyph_storage: GlyphStorage::Dense { first: b'a', glyphs: &SMALL_GLYPHS },
+            replacement: 0,
+            replacement_char: None,
+            bitmaps: &[0xE0],
+            bitmap_encoding: BitmapEncoding::Raw,
+            kerning: Kerning::Narrow(KerningTable { entries: &SMALL_KERNING }),
+            advance_bias: 0,
+            ligatures: LigatureTable { entries: &[] },
+        },
+        Font {
+            ascent: 5,
+            descent: 0,
+            line_spacing: 6,
+            glyph_storage: GlyphStorage::Dense { first: b'Z', glyphs: &BIG_GLYPHS },
+            replacement: 0,
+            replacement_char: None,
+            bitmaps: &[0xF8],
+            bitmap_encoding: BitmapEncoding::Raw,
+            kerning: Kerning::Narrow(KerningTable { entries: &[] }),
+            advance_bias: 0,
+            ligatures: LigatureTable { entries: &[] },
+        },
+    ];
+
+    /// Returns the position and first bitmap byte of each glyph `chain`
+    /// draws for `s` at (10, 20).
+    fn drawn(chain: &FontChain<'_>, s: &str) -> [(usize, usize, u8); 3] {
+        let mut out = [(0, 0, 0); 3];
+        let mut n = 0;
+        chain.render_core(s, 10, 20, |gx, gy, _, slice| {
+            out[n] = (gx, gy, slice[0]);
+            n += 1;
+        });
+        out
+    }
+
+    #[test]
+    fn chain_metrics_fit_every_font() {
+        let chain = FontChain::new(&FONTS);
+        assert_eq!((chain.ascent(), chain.descent(), chain.line_spacing_usize()), (5, 1, 6));
+    }
+
+    #[test]
+    fn each_char_comes_from_the_first_font_with_it() {
+        let chain = FontChain::new(&FONTS);
+        assert_eq!(chain.get_glyph_or_replacement('a'), Some((0, &SMALL_GLYPHS[0])));
+        assert_eq!(chain.get_glyph_or_replacement('Z'), Some((1, &BIG_GLYPHS[0])));
+        // Missing from both, so replaced from the last font.
+        assert_eq!(chain.get_glyph_or_replacement('q'), Some((1, &BIG_GLYPHS[0])));
+        assert_eq!(FontChain::new(&[]).get_glyph_or_replacement('a'), None);
+    }
+
+    #[test]
+    fn fonts_share_a_baseline() {
+        let chain = FontChain::new(&FONTS);
+        // Every bar lands on row 24, the chain's baseline.
+        assert_eq!(drawn(&chain, "aZa"), [(10, 24, 0xE0), (13, 24, 0xF8), (18, 24, 0xE0)]);
+    }
+
+    #[test]
+    fn kerning_resets_at_font_switches() {
+        let chain = FontChain::new(&FONTS);
+        assert_eq!(chain.width("aa"), 5);
+        // The second 'a' follows a 'Z', not the first 'a'.
+        assert_eq!(chain.width("aZa"), 11);
+    }
+}
@@ -1,98 +1,127 @@
 use std::io::{self, Write};
 
-use crate::{Font, GlyphStorage, Glyph};
+use std::collections::HashMap;
 
+use crate::{
+    binary, BitmapEncoding, ClassKerningTable, Font, GlyphSource, GlyphStorage, Glyph, Kerning, KerningEntry, OwnedFont,
+};
+
+/// Options for `generate_rust_module`. The defaults produce a module that
+/// glob-imports `seff` and defines `FONT`, `GLYPHS`, and so on.
+#[derive(Clone, Debug, Default)]
+pub struct RustOptions<'a> {
+    /// Prefix for the names of the generated statics. A prefix of `"comic"`
+    /// produces `COMIC_FONT`, `COMIC_GLYPHS`, and so on, so that several fonts
+    /// can be generated into one module.
+    pub prefix: Option<&'a str>,
+    /// Path to the `seff` crate, such as `"::seff"`, used to qualify every
+    /// type the module names. If this is `None`, the module starts with
+    /// `use seff::*;` instead, which doesn't work if the dependency has been
+    /// renamed.
+    pub crate_path: Option<&'a str>,
+    /// Where each glyph came from in its glyph sheet, by glyph index, as in
+    /// `OwnedFont::sources`. Glyphs with a source get it noted in their
+    /// comments, to help track down glyphs that were sliced out wrong.
+    pub sources: &'a [GlyphSource],
+    /// Whether to define the font and its tables as `const` items rather than
+    /// `static`s, so that the font can be used in other constants, such as a
+    /// table of fonts. Each use of a constant can get its own copy of the
+    /// data, though identical copies are usually merged at link time.
+    pub constants: bool,
+}
+
+/// Writes Rust source defining `font` as a set of statics (or constants, with
+/// `options.constants`), with the `Font` itself named `FONT` unless `options`
+/// gives a prefix.
 pub fn generate_rust_module(
-    font: &Font<'_, '_, '_>,
+    font: &Font<'_, '_, '_, '_>,
+    options: &RustOptions<'_>,
     mut out: impl Write,
 ) -> io::Result<()> {
-    writeln!(out, "use seff::*;")?;
-    writeln!(out, "pub static FONT: Font = Font {{")?;
+    // `p` qualifies type names, and `n` prefixes static names.
+    let p = match options.crate_path {
+        Some(path) => format!("{path}::"),
+        None => {
+            writeln!(out, "use seff::*;")?;
+            String::new()
+        }
+    };
+    let n = match options.prefix {
+        Some(prefix) => format!("{}_", prefix.to_ascii_uppercase()),
+        None => String::new(),
+    };
+    let item = item_kind(options);
+    writeln!(out, "pub {item} {n}FONT: {p}Font = {p}Font {{")?;
     writeln!(out, "    ascent: {},", font.ascent)?;
     writeln!(out, "    descent: {},", font.descent)?;
     writeln!(out, "    line_spacing: {},", font.line_spacing)?;
     write!(out, "    glyph_storage: ")?;
     match font.glyph_storage {
         GlyphStorage::Dense { first, .. } => {
-            writeln!(out, "GlyphStorage::Dense {{")?;
+            writeln!(out, "{p}GlyphStorage::Dense {{")?;
             writeln!(out, "        first: {first},")?;
-            writeln!(out, "        glyphs: &GLYPHS,")?;
+            writeln!(out, "        glyphs: &{n}GLYPHS,")?;
             writeln!(out, "    }},")?;
         }
         GlyphStorage::Sparse { .. } => {
-            writeln!(out, "GlyphStorage::Sparse {{")?;
-            writeln!(out, "        sorted_glyphs: &SORTED_GLYPHS,")?;
+            writeln!(out, "{p}GlyphStorage::Sparse {{")?;
+            writeln!(out, "        sorted_glyphs: &{n}SORTED_GLYPHS,")?;
+            writeln!(out, "    }},")?;
+        }
+        GlyphStorage::Ranges { .. } => {
+            writeln!(out, "{p}GlyphStorage::Ranges {{")?;
+            writeln!(out, "        ranges: &{n}RANGES,")?;
+            writeln!(out, "        glyphs: &{n}GLYPHS,")?;
             writeln!(out, "    }},")?;
         }
     }
     writeln!(out, "    replacement: {},", font.replacement)?;
-    writeln!(out, "    bitmaps: &BITMAPS,")?;
-    writeln!(out, "    kerning: KerningTable {{ entries: &KERNING_ENTRIES }},")?;
+    writeln!(out, "    replacement_char: {:?},", font.replacement_char)?;
+    writeln!(out, "    bitmaps: &{n}BITMAPS,")?;
+    writeln!(out, "    bitmap_encoding: {p}BitmapEncoding::{:?},", font.bitmap_encoding)?;
+    match font.kerning {
+        Kerning::Narrow(_) => {
+            writeln!(out, "    kerning: {p}Kerning::Narrow({p}KerningTable {{ entries: &{n}KERNING_ENTRIES }}),")?;
+        }
+        Kerning::Wide(_) => {
+            writeln!(out, "    kerning: {p}Kerning::Wide({p}WideKerningTable {{ entries: &{n}KERNING_ENTRIES }}),")?;
+        }
+        Kerning::Class(table) => {
+            writeln!(out, "    kerning: {p}Kerning::Class({p}ClassKerningTable {{")?;
+            writeln!(out, "        pairs: {p}WideKerningTable {{ entries: &{n}KERNING_ENTRIES }},")?;
+            writeln!(out, "        classes: &{n}KERNING_CLASSES,")?;
+            writeln!(out, "        right_classes: {},", table.right_classes)?;
+            writeln!(out, "        matrix: &{n}KERNING_MATRIX,")?;
+            writeln!(out, "    }}),")?;
+        }
+    }
+    writeln!(out, "    advance_bias: {},", font.advance_bias)?;
+    writeln!(out, "    ligatures: {p}LigatureTable {{ entries: &{n}LIGATURES }},")?;
     writeln!(out, "}};")?;
 
     match font.glyph_storage {
         GlyphStorage::Dense { first, glyphs } => {
-            writeln!(out, "pub static GLYPHS: [Glyph; {}] = [", glyphs.len())?;
-            for (i, g) in glyphs.iter().enumerate() {
-                let Glyph {
-                    row_bytes,
-                    image_offset,
-                    image_height,
-                    origin,
-                    advance,
-                } = g;
-                writeln!(out, "    // index {}: {:?}", i, char::from_u32(u32::from(first) + i as u32).unwrap_or('?'))?;
-                if *row_bytes != 0 {
-                    let chunk = &font.bitmaps[usize::from(*image_offset)..usize::from(*image_offset) + usize::from(*row_bytes) * usize::from(*image_height)];
-                    for row in chunk.chunks(usize::from(*row_bytes)) {
-                        write!(out, "    // |")?;
-                        for byte in row {
-                            let mut byte = *byte;
-                            for _ in 0..8 {
-                                write!(out, "{}", if byte & 0x80 != 0 { '*' } else { ' ' })?;
-                                byte <<= 1;
-                            }
-                        }
-                        writeln!(out, "|")?;
-                    }
-                }
-                writeln!(out, "    Glyph {{")?;
-                writeln!(out, "        row_bytes: {row_bytes},")?;
-                writeln!(out, "        image_offset: {image_offset},")?;
-                writeln!(out, "        image_height: {image_height},")?;
-                writeln!(out, "        origin: {origin:?},")?;
-                writeln!(out, "        advance: {advance},")?;
-                writeln!(out, "    }},")?;
-            }
-            writeln!(out, "];")?;
+            let chars = (0..glyphs.len())
+                .map(|i| char::from_u32(u32::from(first) + i as u32));
+            write_rust_glyphs(&mut out, font, options, &p, &n, glyphs, chars)?;
         }
         GlyphStorage::Sparse { sorted_glyphs } => {
-            writeln!(out, "pub static SORTED_GLYPHS: [(char, Glyph); {}] = [", sorted_glyphs.len())?;
+            writeln!(out, "pub {item} {n}SORTED_GLYPHS: [(char, {p}Glyph); {}] = [", sorted_glyphs.len())?;
             for (i, (glyph_char, glyph)) in sorted_glyphs.iter().enumerate() {
                 let Glyph {
                     row_bytes,
+                    pixel_width,
                     image_offset,
                     image_height,
                     origin,
                     advance,
                 } = glyph;
-                writeln!(out, "    // index {}: {:?}", i, glyph_char)?;
-                if *row_bytes != 0 {
-                    let chunk = &font.bitmaps[usize::from(*image_offset)..usize::from(*image_offset) + usize::from(*row_bytes) * usize::from(*image_height)];
-                    for row in chunk.chunks(usize::from(*row_bytes)) {
-                        write!(out, "    // |")?;
-                        for byte in row {
-                            let mut byte = *byte;
-                            for _ in 0..8 {
-                                write!(out, "{}", if byte & 0x80 != 0 { '*' } else { ' ' })?;
-                                byte <<= 1;
-                            }
-                        }
-                        writeln!(out, "|")?;
-                    }
-                }
-                writeln!(out, "    ({:?}, Glyph {{", glyph_char)?;
+                let source = source_note(options.sources, i);
+                writeln!(out, "    // index {}: {:?}{}", i, glyph_char, source)?;
+                write_glyph_art(&mut out, "    // ", glyph, font.bitmaps, font.bitmap_encoding)?;
+                writeln!(out, "    ({:?}, {p}Glyph {{", glyph_char)?;
                 writeln!(out, "        row_bytes: {row_bytes},")?;
+                writeln!(out, "        pixel_width: {pixel_width},")?;
                 writeln!(out, "        image_offset: {image_offset},")?;
                 writeln!(out, "        image_height: {image_height},")?;
                 writeln!(out, "        origin: {origin:?},")?;
@@ -101,19 +130,69 @@ pub fn generate_rust_module(
             }
             writeln!(out, "];")?;
         }
+        GlyphStorage::Ranges { ranges, glyphs } => {
+            writeln!(out, "pub {item} {n}RANGES: [(char, u16, u16); {}] = [", ranges.len())?;
+            for (start, len, offset) in ranges {
+                writeln!(out, "    ({start:?}, {len}, {offset}),")?;
+            }
+            writeln!(out, "];")?;
+            let chars = range_chars(ranges, glyphs);
+            write_rust_glyphs(&mut out, font, options, &p, &n, glyphs, chars)?;
+        }
     }
 
-    writeln!(out, "pub static KERNING_ENTRIES: [KerningEntry; {}] = [",
-        font.kerning.entries.len())?;
-    for e in font.kerning.entries {
-        writeln!(out, "    KerningEntry {{")?;
-        writeln!(out, "        pair: {:?},", e.pair)?;
-        writeln!(out, "        adjust: {},", e.adjust)?;
-        writeln!(out, "    }},")?;
+    match font.kerning {
+        Kerning::Narrow(table) => {
+            writeln!(out, "pub {item} {n}KERNING_ENTRIES: [{p}KerningEntry; {}] = [",
+                table.entries.len())?;
+            for e in table.entries {
+                writeln!(out, "    {p}KerningEntry {{")?;
+                writeln!(out, "        pair: {:?},", e.pair)?;
+                writeln!(out, "        adjust: {},", e.adjust)?;
+                writeln!(out, "    }},")?;
+            }
+        }
+        Kerning::Wide(table) | Kerning::Class(ClassKerningTable { pairs: table, .. }) => {
+            writeln!(out, "pub {item} {n}KERNING_ENTRIES: [{p}WideKerningEntry; {}] = [",
+                table.entries.len())?;
+            for e in table.entries {
+                writeln!(out, "    {p}WideKerningEntry {{")?;
+                writeln!(out, "        pair: {:?},", e.pair)?;
+                writeln!(out, "        adjust: {},", e.adjust)?;
+                writeln!(out, "    }},")?;
+            }
+        }
     }
     writeln!(out, "];")?;
 
-    writeln!(out, "pub static BITMAPS: [u8; {}] = [", font.bitmaps.len())?;
+    if let Kerning::Class(table) = font.kerning {
+        writeln!(out, "pub {item} {n}KERNING_CLASSES: [(char, u8, u8); {}] = [",
+            table.classes.len())?;
+        for (c, left, right) in table.classes {
+            writeln!(out, "    ({c:?}, {left}, {right}),")?;
+        }
+        writeln!(out, "];")?;
+        writeln!(out, "pub {item} {n}KERNING_MATRIX: [i8; {}] = [", table.matrix.len())?;
+        // One line per left class, when the matrix is the right shape for it.
+        let row_len = usize::from(table.right_classes).max(1);
+        for row in table.matrix.chunks(row_len) {
+            write!(out, "   ")?;
+            for adjust in row {
+                write!(out, " {adjust},")?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out, "];")?;
+    }
+
+    writeln!(out, "pub {item} {n}LIGATURES: [(&str, u16); {}] = [",
+        font.ligatures.entries.len())?;
+    for (seq, index) in font.ligatures.entries {
+        writeln!(out, "    ({seq:?}, {index}),")?;
+    }
+    writeln!(out, "];")?;
+
+    writeln!(out, "pub {item} {n}BITMAPS: [u8; {}] = [", font.bitmaps.len())?;
     for line in font.bitmaps.chunks(8) {
         for (i, byte) in line.iter().enumerate() {
             write!(out, "{}0x{:02x},",
@@ -128,3 +207,1214 @@ pub fn generate_rust_module(
 
     Ok(())
 }
+
+/// Returns the keyword for the items `generate_rust_module` defines.
+fn item_kind(options: &RustOptions<'_>) -> &'static str {
+    if options.constants { "const" } else { "static" }
+}
+
+/// Writes a `GLYPHS` array for `Dense` or `Ranges` storage. `chars` gives the
+/// char for each glyph, for the comments. `p` and `n` are the type path and
+/// name prefixes from `generate_rust_module`.
+fn write_rust_glyphs(
+    out: &mut impl Write,
+    font: &Font<'_, '_, '_, '_>,
+    options: &RustOptions<'_>,
+    p: &str,
+    n: &str,
+    glyphs: &[Glyph],
+    chars: impl Iterator<Item = Option<char>>,
+) -> io::Result<()> {
+    let item = item_kind(options);
+    writeln!(out, "pub {item} {n}GLYPHS: [{p}Glyph; {}] = [", glyphs.len())?;
+    for ((i, g), c) in glyphs.iter().enumerate().zip(chars) {
+        let Glyph {
+            row_bytes,
+            pixel_width,
+            image_offset,
+            image_height,
+            origin,
+            advance,
+        } = g;
+        let source = source_note(options.sources, i);
+        writeln!(out, "    // index {}: {:?}{}", i, c.unwrap_or('?'), source)?;
+        write_glyph_art(out, "    // ", g, font.bitmaps, font.bitmap_encoding)?;
+        writeln!(out, "    {p}Glyph {{")?;
+        writeln!(out, "        row_bytes: {row_bytes},")?;
+        writeln!(out, "        pixel_width: {pixel_width},")?;
+        writeln!(out, "        image_offset: {image_offset},")?;
+        writeln!(out, "        image_height: {image_height},")?;
+        writeln!(out, "        origin: {origin:?},")?;
+        writeln!(out, "        advance: {advance},")?;
+        writeln!(out, "    }},")?;
+    }
+    writeln!(out, "];")?;
+    Ok(())
+}
+
+/// Describes where glyph `index` came from, if `sources` says, for the end of
+/// its comment.
+fn source_note(sources: &[GlyphSource], index: usize) -> String {
+    match sources.get(index) {
+        Some(GlyphSource { page, rect: (x, y, w, h) }) => {
+            format!(", from page {page} at ({x}, {y}), {w}x{h}")
+        }
+        None => String::new(),
+    }
+}
+
+/// Works out which char each glyph in `Ranges` storage is used for, if any.
+fn range_chars(
+    ranges: &[(char, u16, u16)],
+    glyphs: &[Glyph],
+) -> impl Iterator<Item = Option<char>> {
+    let mut chars = vec![None; glyphs.len()];
+    for &(start, len, offset) in ranges {
+        for i in 0..len {
+            if let Some(slot) = chars.get_mut(usize::from(offset) + usize::from(i)) {
+                *slot = char::from_u32(u32::from(start) + u32::from(i));
+            }
+        }
+    }
+    chars.into_iter()
+}
+
+/// Lists the glyphs in `storage` along with the chars they stand for, in glyph
+/// index order. Glyphs that no char maps to are left out.
+fn char_glyphs(storage: GlyphStorage<'_>) -> Vec<(char, &Glyph)> {
+    match storage {
+        GlyphStorage::Dense { first, glyphs } => glyphs.iter()
+            .enumerate()
+            .filter_map(|(i, g)| Some((char::from_u32(u32::from(first) + i as u32)?, g)))
+            .collect(),
+        GlyphStorage::Sparse { sorted_glyphs } => sorted_glyphs.iter()
+            .map(|(c, g)| (*c, g))
+            .collect(),
+        GlyphStorage::Ranges { ranges, glyphs } => glyphs.iter()
+            .zip(range_chars(ranges, glyphs))
+            .filter_map(|(g, c)| Some((c?, g)))
+            .collect(),
+    }
+}
+
+/// Generates a C header defining `font`.
+///
+/// Every identifier in the header is prefixed with `name` (for arrays) or its
+/// uppercase equivalent (for macros), so several fonts can be used in one
+/// program. As well as the metrics `#define`s (including `NAME_ADVANCE_BIAS`,
+/// to be added to every glyph's advance), the header declares:
+///
+/// - `name_bitmaps`, the `uint8_t` bitmap data. If `NAME_BITMAP_ENCODING` is 1,
+///   each glyph's image is PackBits-compressed (see `BitmapEncoding`).
+/// - `name_glyphs`, the glyph table. For `Dense` storage this is an array of
+///   `struct seff_glyph`, indexed by codepoint minus `NAME_FIRST`; for `Sparse`
+///   storage it's an array of `struct seff_sparse_glyph` sorted by codepoint.
+///   For `Ranges` storage it's an array of `struct seff_glyph`, indexed through
+///   `name_ranges`, an array of `struct seff_glyph_range` sorted by `start`.
+/// - `name_kerning`, an array of `struct seff_kerning_entry` sorted by pair.
+///   This is omitted if the font has no kerning.
+///
+/// Ligatures are not included.
+///
+/// The structs are defined in the header (once, even if several font headers
+/// are included) and mirror the Rust types:
+///
+/// ```c
+/// struct seff_glyph {
+///     uint8_t row_bytes;
+///     uint8_t pixel_width;
+///     uint16_t image_offset;
+///     uint8_t image_height;
+///     uint8_t origin_x;
+///     uint8_t origin_y;
+///     uint8_t advance;
+/// };
+///
+/// struct seff_sparse_glyph {
+///     uint32_t codepoint;
+///     struct seff_glyph glyph;
+/// };
+///
+/// struct seff_glyph_range {
+///     uint32_t start;
+///     uint16_t len;
+///     uint16_t offset;
+/// };
+///
+/// struct seff_kerning_entry {
+///     uint8_t before;
+///     uint8_t after;
+///     int8_t adjust;
+/// };
+/// ```
+pub fn generate_c_header(
+    font: &Font<'_, '_, '_, '_>,
+    name: &str,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let upper = name.to_ascii_uppercase();
+
+    writeln!(out, "#ifndef {upper}_H")?;
+    writeln!(out, "#define {upper}_H")?;
+    writeln!(out)?;
+    writeln!(out, "#include <stdint.h>")?;
+    writeln!(out)?;
+    writeln!(out, "#ifndef SEFF_TYPES_DEFINED")?;
+    writeln!(out, "#define SEFF_TYPES_DEFINED")?;
+    writeln!(out, "struct seff_glyph {{")?;
+    writeln!(out, "    uint8_t row_bytes;")?;
+    writeln!(out, "    uint8_t pixel_width;")?;
+    writeln!(out, "    uint16_t image_offset;")?;
+    writeln!(out, "    uint8_t image_height;")?;
+    writeln!(out, "    uint8_t origin_x;")?;
+    writeln!(out, "    uint8_t origin_y;")?;
+    writeln!(out, "    uint8_t advance;")?;
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "struct seff_sparse_glyph {{")?;
+    writeln!(out, "    uint32_t codepoint;")?;
+    writeln!(out, "    struct seff_glyph glyph;")?;
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "struct seff_glyph_range {{")?;
+    writeln!(out, "    uint32_t start;")?;
+    writeln!(out, "    uint16_t len;")?;
+    writeln!(out, "    uint16_t offset;")?;
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "struct seff_kerning_entry {{")?;
+    writeln!(out, "    uint8_t before;")?;
+    writeln!(out, "    uint8_t after;")?;
+    writeln!(out, "    int8_t adjust;")?;
+    writeln!(out, "}};")?;
+    writeln!(out, "#endif")?;
+    writeln!(out)?;
+
+    writeln!(out, "#define {upper}_ASCENT {}", font.ascent)?;
+    writeln!(out, "#define {upper}_DESCENT {}", font.descent)?;
+    writeln!(out, "#define {upper}_LINE_SPACING {}", font.line_spacing)?;
+    writeln!(out, "#define {upper}_ADVANCE_BIAS {}", font.advance_bias)?;
+    writeln!(out, "#define {upper}_REPLACEMENT {}", replacement_index(font))?;
+    writeln!(out, "#define {upper}_BITMAP_ENCODING {}", encoding_code(font.bitmap_encoding))?;
+
+    let c_glyph = |out: &mut dyn Write, g: &Glyph| -> io::Result<()> {
+        write!(out, "{{ {}, {}, {}, {}, {}, {}, {} }}",
+            g.row_bytes,
+            g.pixel_width,
+            g.image_offset,
+            g.image_height,
+            g.origin.0,
+            g.origin.1,
+            g.advance,
+        )
+    };
+
+    // C doesn't allow empty arrays, so a font with no glyphs (or no bitmap
+    // data) gets a single zeroed entry; the counts still say how many are
+    // real.
+    match font.glyph_storage {
+        GlyphStorage::Dense { first, glyphs } => {
+            writeln!(out, "#define {upper}_FIRST {first}")?;
+            writeln!(out, "#define {upper}_GLYPH_COUNT {}", glyphs.len())?;
+            writeln!(out)?;
+            writeln!(out, "static const struct seff_glyph {name}_glyphs[{}] = {{", glyphs.len().max(1))?;
+            if glyphs.is_empty() {
+                writeln!(out, "    {{ 0, 0, 0, 0, 0, 0 }},")?;
+            }
+            for (i, g) in glyphs.iter().enumerate() {
+                let c = char::from_u32(u32::from(first) + i as u32).unwrap_or('?');
+                writeln!(out, "    // index {}: {:?}", i, c)?;
+                write_glyph_art(&mut out, "    // ", g, font.bitmaps, font.bitmap_encoding)?;
+                write!(out, "    ")?;
+                c_glyph(&mut out, g)?;
+                writeln!(out, ",")?;
+            }
+            writeln!(out, "}};")?;
+        }
+        GlyphStorage::Sparse { sorted_glyphs } => {
+            writeln!(out, "#define {upper}_GLYPH_COUNT {}", sorted_glyphs.len())?;
+            writeln!(out)?;
+            writeln!(out, "static const struct seff_sparse_glyph {name}_glyphs[{}] = {{", sorted_glyphs.len().max(1))?;
+            if sorted_glyphs.is_empty() {
+                writeln!(out, "    {{ 0x0, {{ 0, 0, 0, 0, 0, 0 }} }},")?;
+            }
+            for (i, (c, g)) in sorted_glyphs.iter().enumerate() {
+                writeln!(out, "    // index {}: {:?}", i, c)?;
+                write_glyph_art(&mut out, "    // ", g, font.bitmaps, font.bitmap_encoding)?;
+                write!(out, "    {{ 0x{:x}, ", u32::from(*c))?;
+                c_glyph(&mut out, g)?;
+                writeln!(out, " }},")?;
+            }
+            writeln!(out, "}};")?;
+        }
+        GlyphStorage::Ranges { ranges, glyphs } => {
+            writeln!(out, "#define {upper}_RANGE_COUNT {}", ranges.len())?;
+            writeln!(out, "#define {upper}_GLYPH_COUNT {}", glyphs.len())?;
+            writeln!(out)?;
+            writeln!(out, "static const struct seff_glyph_range {name}_ranges[{}] = {{", ranges.len().max(1))?;
+            if ranges.is_empty() {
+                writeln!(out, "    {{ 0, 0, 0 }},")?;
+            }
+            for (start, len, offset) in ranges {
+                writeln!(out, "    {{ 0x{:x}, {len}, {offset} }},", u32::from(*start))?;
+            }
+            writeln!(out, "}};")?;
+            writeln!(out)?;
+            writeln!(out, "static const struct seff_glyph {name}_glyphs[{}] = {{", glyphs.len())?;
+            for ((i, g), c) in glyphs.iter().enumerate().zip(range_chars(ranges, glyphs)) {
+                writeln!(out, "    // index {}: {:?}", i, c.unwrap_or('?'))?;
+                write_glyph_art(&mut out, "    // ", g, font.bitmaps, font.bitmap_encoding)?;
+                write!(out, "    ")?;
+                c_glyph(&mut out, g)?;
+                writeln!(out, ",")?;
+            }
+            writeln!(out, "}};")?;
+        }
+    }
+    writeln!(out)?;
+
+    // C doesn't allow empty arrays, so leave the kerning table out entirely
+    // if there's nothing in it.
+    let entries = narrow_kerning(font)?;
+    writeln!(out, "#define {upper}_KERNING_COUNT {}", entries.len())?;
+    if !entries.is_empty() {
+        writeln!(out, "static const struct seff_kerning_entry {name}_kerning[{}] = {{", entries.len())?;
+        for e in &entries {
+            writeln!(out, "    {{ {}, {}, {} }},", e.pair.0, e.pair.1, e.adjust)?;
+        }
+        writeln!(out, "}};")?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "static const uint8_t {name}_bitmaps[{}] = {{", font.bitmaps.len().max(1))?;
+    if font.bitmaps.is_empty() {
+        writeln!(out, "    0")?;
+    }
+    for line in font.bitmaps.chunks(8) {
+        for (i, byte) in line.iter().enumerate() {
+            write!(out, "{}0x{:02x},",
+                if i == 0 { "    " } else { " " },
+                byte
+            )?;
+        }
+
+        writeln!(out)?;
+    }
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "#endif")?;
+
+    Ok(())
+}
+
+/// Writes an ASCII-art picture of `glyph`'s image, decoded from `bitmap`
+/// according to `encoding`, for looking at glyphs in tools or tests.
+///
+/// Each row of the image becomes one line, with `*` for set pixels and a space
+/// for clear ones, between `|` borders:
+///
+/// ```text
+/// | ** |
+/// |****|
+/// ```
+///
+/// This covers only the glyph's image, not its whole bounding box, so a blank
+/// glyph produces no output. The comments in `generate_rust_module` and
+/// `generate_c_header` output use the same format.
+pub fn glyph_preview(
+    glyph: &Glyph,
+    bitmap: &[u8],
+    encoding: BitmapEncoding,
+    mut out: impl Write,
+) -> io::Result<()> {
+    write_glyph_art(&mut out, "", glyph, bitmap, encoding)
+}
+
+/// Writes `glyph_preview` output with each line starting with `prefix`.
+fn write_glyph_art(
+    out: &mut impl Write,
+    prefix: &str,
+    glyph: &Glyph,
+    bitmap: &[u8],
+    encoding: BitmapEncoding,
+) -> io::Result<()> {
+    let row_width = glyph.width_in_pixels();
+    for (x, _, set) in glyph.pixels(bitmap, encoding) {
+        if x == 0 {
+            write!(out, "{prefix}|")?;
+        }
+        write!(out, "{}", if set { '*' } else { ' ' })?;
+        if x == row_width - 1 {
+            writeln!(out, "|")?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `font` in the binary format described in the `binary` module, for
+/// loading at runtime with `Font::from_bytes`.
+///
+/// The binary format can't represent ligatures, so any in `font` are left out.
+/// It also has no place for `advance_bias`, which is instead added to each
+/// glyph's advance (limited to 0 through 255).
+pub fn write_binary(
+    font: &Font<'_, '_, '_, '_>,
+    mut out: impl Write,
+) -> io::Result<()> {
+    fn len_u32(len: usize) -> io::Result<[u8; 4]> {
+        u32::try_from(len)
+            .map(u32::to_le_bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "table too large"))
+    }
+    // The format has no place for `advance_bias`, so it's applied to each
+    // glyph's advance instead.
+    let glyph_bytes = |g: &Glyph| -> [u8; 8] {
+        let [off_lo, off_hi] = g.image_offset.to_le_bytes();
+        [
+            g.row_bytes,
+            g.pixel_width,
+            off_lo,
+            off_hi,
+            g.image_height,
+            g.origin.0,
+            g.origin.1,
+            u8::try_from(font.advance_usize(g)).unwrap_or(u8::MAX),
+        ]
+    };
+
+    let (storage, first, glyph_count) = match font.glyph_storage {
+        GlyphStorage::Dense { first, glyphs } => {
+            (binary::STORAGE_DENSE, first, glyphs.len())
+        }
+        GlyphStorage::Sparse { sorted_glyphs } => {
+            (binary::STORAGE_SPARSE, 0, sorted_glyphs.len())
+        }
+        GlyphStorage::Ranges { glyphs, .. } => {
+            (binary::STORAGE_RANGES, 0, glyphs.len())
+        }
+    };
+
+    out.write_all(&binary::MAGIC)?;
+    out.write_all(&[
+        binary::VERSION,
+        storage,
+        font.ascent,
+        font.descent,
+        font.line_spacing,
+        replacement_index(font),
+        first,
+        encoding_code(font.bitmap_encoding),
+    ])?;
+    out.write_all(&len_u32(glyph_count)?)?;
+    out.write_all(&len_u32(font.bitmaps.len())?)?;
+    let kerning = narrow_kerning(font)?;
+    out.write_all(&len_u32(kerning.len())?)?;
+
+    match font.glyph_storage {
+        GlyphStorage::Dense { glyphs, .. } => {
+            for g in glyphs {
+                out.write_all(&glyph_bytes(g))?;
+            }
+        }
+        GlyphStorage::Sparse { sorted_glyphs } => {
+            for (c, g) in sorted_glyphs {
+                out.write_all(&u32::from(*c).to_le_bytes())?;
+                out.write_all(&glyph_bytes(g))?;
+            }
+        }
+        GlyphStorage::Ranges { ranges, glyphs } => {
+            out.write_all(&len_u32(ranges.len())?)?;
+            for (start, len, offset) in ranges {
+                out.write_all(&u32::from(*start).to_le_bytes())?;
+                out.write_all(&len.to_le_bytes())?;
+                out.write_all(&offset.to_le_bytes())?;
+            }
+            for g in glyphs {
+                out.write_all(&glyph_bytes(g))?;
+            }
+        }
+    }
+
+    out.write_all(font.bitmaps)?;
+
+    for e in &kerning {
+        out.write_all(&[e.pair.0, e.pair.1, e.adjust as u8])?;
+    }
+
+    Ok(())
+}
+
+/// Writes `font` as RON, for loading at runtime by deserializing an
+/// `OwnedFont`.
+pub fn write_ron(
+    font: &Font<'_, '_, '_, '_>,
+    out: impl Write,
+) -> Result<(), ron::Error> {
+    ron::ser::to_writer_pretty(out, font, ron::ser::PrettyConfig::new())
+}
+
+/// Font-wide metrics, and the metrics of each glyph, as written by
+/// `write_metrics_ron`.
+#[derive(serde::Serialize)]
+struct FontMetrics {
+    ascent: u8,
+    descent: u8,
+    line_spacing: u8,
+    advance_bias: i8,
+    glyphs: Vec<GlyphMetrics>,
+}
+
+#[derive(serde::Serialize)]
+struct GlyphMetrics {
+    char: char,
+    advance: u8,
+    origin: (u8, u8),
+    width: usize,
+    image_height: u8,
+    row_bytes: u8,
+}
+
+/// Writes the metrics of `font` and its glyphs, but not their images, as RON.
+///
+/// This is meant for layout code elsewhere, perhaps in another language, that
+/// needs to measure text the way seff does. Each glyph is listed with the
+/// char it stands for, in glyph index order. Glyphs that no char maps to are
+/// left out.
+pub fn write_metrics_ron(
+    font: &Font<'_, '_, '_, '_>,
+    out: impl Write,
+) -> Result<(), ron::Error> {
+    let metrics = |c: char, g: &Glyph| GlyphMetrics {
+        char: c,
+        advance: g.advance,
+        origin: g.origin,
+        width: g.width_in_pixels(),
+        image_height: g.image_height,
+        row_bytes: g.row_bytes,
+    };
+    let glyphs = char_glyphs(font.glyph_storage).into_iter()
+        .map(|(c, g)| metrics(c, g))
+        .collect();
+    let font_metrics = FontMetrics {
+        ascent: font.ascent,
+        descent: font.descent,
+        line_spacing: font.line_spacing,
+        advance_bias: font.advance_bias,
+        glyphs,
+    };
+    ron::ser::to_writer_pretty(out, &font_metrics, ron::ser::PrettyConfig::new())
+}
+
+/// Where one glyph's image lies in an atlas from `generate_atlas`, and how to
+/// place it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AtlasEntry {
+    /// The char the glyph stands for.
+    pub ch: char,
+    /// The image's rectangle in the atlas, in pixels, as `(x, y, width,
+    /// height)`. Blank glyphs have an empty rectangle at `(0, 0)`.
+    pub rect: (u32, u32, u32, u32),
+    /// See `Glyph::origin`.
+    pub origin: (u8, u8),
+    /// See `Glyph::advance`.
+    pub advance: u8,
+}
+
+impl AtlasEntry {
+    /// Returns `rect` as texture coordinates in an atlas `size` pixels wide
+    /// and tall, as `(left, top, right, bottom)`, each from 0 to 1.
+    pub fn uv(&self, size: (u32, u32)) -> (f32, f32, f32, f32) {
+        let (x, y, w, h) = self.rect;
+        let (aw, ah) = (size.0 as f32, size.1 as f32);
+        (x as f32 / aw, y as f32 / ah, (x + w) as f32 / aw, (y + h) as f32 / ah)
+    }
+}
+
+/// Draws every glyph image in `font` into a single image, for use as a
+/// texture, and returns it along with where each glyph went.
+///
+/// Ink is 255 and everything else is 0. Glyphs are packed onto shelves,
+/// tallest first, in an image roughly as wide as it is tall, with a pixel of
+/// space between neighbors so that filtering doesn't bleed one glyph into
+/// another. Every glyph that a char maps to gets an entry, in glyph index
+/// order, including blank ones.
+pub fn generate_atlas(font: &Font<'_, '_, '_, '_>) -> (image::GrayImage, Vec<AtlasEntry>) {
+    const GAP: u32 = 1;
+
+    let glyphs = char_glyphs(font.glyph_storage);
+    let size = |g: &Glyph| (g.width_in_pixels() as u32, u32::from(g.image_height));
+    let area: u32 = glyphs.iter()
+        .map(|(_, g)| size(g))
+        .map(|(w, h)| (w + GAP) * (h + GAP))
+        .sum();
+    let widest = glyphs.iter().map(|(_, g)| size(g).0 + GAP).max().unwrap_or(0);
+    let atlas_width = widest.max((area as f64).sqrt().ceil() as u32);
+
+    // Lay out the shelves, tallest glyphs first.
+    let mut order: Vec<usize> = (0..glyphs.len())
+        .filter(|&i| glyphs[i].1.has_image())
+        .collect();
+    order.sort_by_key(|&i| core::cmp::Reverse(size(glyphs[i].1).1));
+    let mut places = vec![(0, 0); glyphs.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for &i in &order {
+        let (w, h) = size(glyphs[i].1);
+        if x + w > atlas_width {
+            (x, y) = (0, y + shelf_height + GAP);
+            shelf_height = 0;
+        }
+        places[i] = (x, y);
+        x += w + GAP;
+        shelf_height = shelf_height.max(h);
+    }
+    let atlas_height = y + shelf_height;
+
+    let mut atlas = image::GrayImage::new(atlas_width.max(1), atlas_height.max(1));
+    let entries = glyphs.iter().zip(&places).map(|(&(ch, g), &(x, y))| {
+        let (w, h) = if g.has_image() { size(g) } else { (0, 0) };
+        for (px, py, set) in g.pixels(font.bitmaps, font.bitmap_encoding) {
+            if set {
+                atlas.put_pixel(x + px as u32, y + py as u32, image::Luma([0xFF]));
+            }
+        }
+        AtlasEntry {
+            ch,
+            rect: (x, y, w, h),
+            origin: g.origin,
+            advance: g.advance,
+        }
+    });
+    let entries = entries.collect();
+    (atlas, entries)
+}
+
+/// Returns the index of `font`'s replacement glyph, resolving
+/// `replacement_char` if possible, for formats that can only store an index.
+fn replacement_index(font: &Font<'_, '_, '_, '_>) -> u8 {
+    font.replacement_char
+        .and_then(|c| font.glyph_storage.index_of(c))
+        .and_then(|i| u8::try_from(i).ok())
+        .unwrap_or(font.replacement)
+}
+
+/// Returns `font`'s kerning entries in compact form, for formats that only
+/// have room for ISO8859-1 pairs. This fails if the font has a wide or class
+/// table with pairs that don't fit.
+fn narrow_kerning(font: &Font<'_, '_, '_, '_>) -> io::Result<Vec<KerningEntry>> {
+    match font.kerning {
+        Kerning::Narrow(table) => Ok(table.entries.to_vec()),
+        Kerning::Wide(_) | Kerning::Class(_) => font.kerning.pairs()
+            .map(|((before, after), adjust)| {
+                match (u8::try_from(before), u8::try_from(after)) {
+                    (Ok(before), Ok(after)) => Ok(KerningEntry {
+                        pair: (before, after),
+                        adjust,
+                    }),
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "kerning pair outside ISO8859-1",
+                    )),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Returns the code used for `encoding` in the binary format and C headers.
+fn encoding_code(encoding: BitmapEncoding) -> u8 {
+    match encoding {
+        BitmapEncoding::Raw => binary::ENCODING_RAW,
+        BitmapEncoding::PackBits => binary::ENCODING_PACKBITS,
+    }
+}
+
+/// Produces a copy of `font` with its glyph images compressed using
+/// `BitmapEncoding::PackBits`.
+///
+/// Returns `None` if this wouldn't make the font's bitmap data any smaller, or
+/// if `font` is already compressed.
+pub fn compress_bitmaps(font: &Font<'_, '_, '_, '_>) -> Option<OwnedFont> {
+    if font.bitmap_encoding != BitmapEncoding::Raw {
+        return None;
+    }
+
+    let mut owned = OwnedFont::from(font);
+    let mut bitmaps = vec![];
+    // Glyphs with identical images can share compressed data too.
+    let mut seen: HashMap<&[u8], u16> = HashMap::new();
+    let mut compress = |g: &mut Glyph| -> Option<()> {
+        if !g.has_image() {
+            return Some(());
+        }
+        let raw = g.slice_bitmap(font.bitmaps);
+        g.image_offset = if let Some(&offset) = seen.get(raw) {
+            offset
+        } else {
+            let offset = u16::try_from(bitmaps.len()).ok()?;
+            packbits_encode(raw, &mut bitmaps);
+            seen.insert(raw, offset);
+            offset
+        };
+        Some(())
+    };
+    match &mut owned.glyph_storage {
+        crate::OwnedGlyphStorage::Dense { glyphs, .. }
+        | crate::OwnedGlyphStorage::Ranges { glyphs, .. } => {
+            glyphs.iter_mut().try_for_each(&mut compress)?;
+        }
+        crate::OwnedGlyphStorage::Sparse { sorted_glyphs } => {
+            sorted_glyphs.iter_mut().try_for_each(|(_, g)| compress(g))?;
+        }
+    }
+
+    if bitmaps.len() >= font.bitmaps.len() {
+        return None;
+    }
+    owned.bitmaps = bitmaps;
+    owned.bitmap_encoding = BitmapEncoding::PackBits;
+    Some(owned)
+}
+
+/// How a font's glyphs share bitmap data, as reported by `bitmap_sharing`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BitmapSharing {
+    /// Number of glyphs that have an image.
+    pub glyphs: usize,
+    /// Number of distinct images among those glyphs.
+    pub unique: usize,
+    /// Number of copies of those images stored in the font, counting glyphs
+    /// that point at the same data once. If this is more than `unique`,
+    /// `share_bitmaps` can make the font smaller.
+    pub stored: usize,
+}
+
+impl std::fmt::Display for BitmapSharing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} glyphs share {} unique bitmaps", self.glyphs, self.unique)?;
+        if self.stored > self.unique {
+            write!(f, " ({} stored copies)", self.stored)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the glyphs in `font` whose images are identical, whether or not
+/// they already share bitmap data.
+///
+/// Two images are identical if they have the same bytes per row and the same
+/// decoded bytes; the glyphs' other metrics don't matter.
+pub fn bitmap_sharing(font: &Font<'_, '_, '_, '_>) -> BitmapSharing {
+    let mut images = std::collections::HashSet::new();
+    let mut slices = std::collections::HashSet::new();
+    let mut sharing = BitmapSharing::default();
+    let mut count = |g: &Glyph| {
+        if !g.has_image() {
+            return;
+        }
+        sharing.glyphs += 1;
+        let bytes: Vec<u8> = g.bitmap_bytes(font.bitmaps, font.bitmap_encoding)
+            .collect();
+        images.insert((g.row_bytes, bytes));
+        slices.insert((g.image_offset, g.row_bytes, g.image_height));
+    };
+    match font.glyph_storage {
+        GlyphStorage::Dense { glyphs, .. }
+        | GlyphStorage::Ranges { glyphs, .. } => glyphs.iter().for_each(&mut count),
+        GlyphStorage::Sparse { sorted_glyphs } => {
+            sorted_glyphs.iter().for_each(|(_, g)| count(g));
+        }
+    }
+    sharing.unique = images.len();
+    sharing.stored = slices.len();
+    sharing
+}
+
+/// How much ink one glyph's image holds, as reported by `glyph_stats`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GlyphStat {
+    /// The char the glyph stands for.
+    pub ch: char,
+    /// Width of the glyph's image, in pixels.
+    pub width: usize,
+    /// Height of the glyph's image, in pixels.
+    pub height: usize,
+    /// Number of pixels set in the image.
+    pub set_pixels: usize,
+}
+
+impl GlyphStat {
+    /// Returns the fraction of the image's pixels that are set, from 0 to 1.
+    /// A glyph with no image counts as 0.
+    pub fn ink_fraction(&self) -> f64 {
+        fraction(self.set_pixels, self.width * self.height)
+    }
+}
+
+/// Measures how much ink each glyph in `font` holds, for catching glyphs that
+/// are accidentally blank or solid. Every glyph that a char maps to gets an
+/// entry, in glyph index order.
+pub fn glyph_stats(font: &Font<'_, '_, '_, '_>) -> Vec<GlyphStat> {
+    char_glyphs(font.glyph_storage).into_iter()
+        .map(|(ch, g)| GlyphStat {
+            ch,
+            width: g.width_in_pixels(),
+            height: usize::from(g.image_height),
+            set_pixels: g.pixels(font.bitmaps, font.bitmap_encoding)
+                .filter(|&(_, _, set)| set)
+                .count(),
+        })
+        .collect()
+}
+
+/// Returns the fraction of the pixels in all of the images in `stats` that
+/// are set, from 0 to 1, weighting each glyph by its image's area.
+pub fn ink_fraction(stats: &[GlyphStat]) -> f64 {
+    let set = stats.iter().map(|s| s.set_pixels).sum();
+    let area = stats.iter().map(|s| s.width * s.height).sum();
+    fraction(set, area)
+}
+
+fn fraction(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Produces a copy of `font` in which glyphs with identical images all point
+/// at a single copy of the image, with any bitmap data that's no longer used
+/// removed.
+///
+/// Fonts from `load` and `FontBuilder` already share identical images, but
+/// fonts from elsewhere may not. Bitmap data that glyphs already share, in
+/// whole or in part, stays shared.
+///
+/// Returns `None` if this wouldn't make the font's bitmap data any smaller, or
+/// if `font` is compressed. (`compress_bitmaps` shares identical images as it
+/// compresses them.)
+pub fn share_bitmaps(font: &Font<'_, '_, '_, '_>) -> Option<OwnedFont> {
+    if font.bitmap_encoding != BitmapEncoding::Raw {
+        return None;
+    }
+
+    let mut owned = OwnedFont::from(font);
+    let mut glyphs: Vec<&mut Glyph> = match &mut owned.glyph_storage {
+        crate::OwnedGlyphStorage::Dense { glyphs, .. }
+        | crate::OwnedGlyphStorage::Ranges { glyphs, .. } => {
+            glyphs.iter_mut().collect()
+        }
+        crate::OwnedGlyphStorage::Sparse { sorted_glyphs } => {
+            sorted_glyphs.iter_mut().map(|(_, g)| g).collect()
+        }
+    };
+    glyphs.retain(|g| g.has_image());
+
+    // Point each glyph at the earliest copy of its image, and note which
+    // bytes are still in use.
+    let mut first: HashMap<(u8, &[u8]), u16> = HashMap::new();
+    let mut used = vec![false; font.bitmaps.len()];
+    for g in &mut glyphs {
+        let raw = g.try_slice_bitmap(font.bitmaps)?;
+        let offset = first.entry((g.row_bytes, raw)).or_insert(g.image_offset);
+        *offset = u16::min(*offset, g.image_offset);
+    }
+    for g in &mut glyphs {
+        let raw = g.slice_bitmap(font.bitmaps);
+        g.image_offset = first[&(g.row_bytes, raw)];
+        let start = usize::from(g.image_offset);
+        used[start..start + raw.len()].fill(true);
+    }
+
+    // Squeeze out the unused bytes. Each glyph's image is made of used bytes,
+    // so it stays contiguous, and moves down by the number of unused bytes
+    // before it.
+    let mut new_offsets = Vec::with_capacity(font.bitmaps.len());
+    let mut bitmaps = vec![];
+    for (&byte, &used) in font.bitmaps.iter().zip(&used) {
+        new_offsets.push(bitmaps.len());
+        if used {
+            bitmaps.push(byte);
+        }
+    }
+    if bitmaps.len() >= font.bitmaps.len() {
+        return None;
+    }
+    for g in glyphs {
+        // This can only shrink, so it still fits.
+        g.image_offset = new_offsets[usize::from(g.image_offset)] as u16;
+    }
+    owned.bitmaps = bitmaps;
+    Some(owned)
+}
+
+/// Appends the PackBits encoding of `data` to `out`.
+fn packbits_encode(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..].iter().take(128).take_while(|&&b| b == data[i]).count();
+        if run >= 2 {
+            out.push((257 - run) as u8);
+            out.push(data[i]);
+            i += run;
+        } else {
+            // Gather literal bytes until a run of at least three starts, where
+            // switching to a repeat would save space.
+            let start = i;
+            while i < data.len() && i - start < 128 {
+                if data[i..].len() >= 3 && data[i] == data[i + 1] && data[i] == data[i + 2] {
+                    break;
+                }
+                i += 1;
+            }
+            out.push((i - start - 1) as u8);
+            out.extend_from_slice(&data[start..i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KerningTable, LigatureTable};
+    use crate::load::GlyphOrder;
+
+    static GLYPHS: [Glyph; 2] = [
+        Glyph {
+            row_bytes: 0,
+            pixel_width: 0,
+            image_offset: 0,
+            image_height: 0,
+            origin: (0, 0),
+            advance: 3,
+        },
+        Glyph {
+            row_bytes: 1,
+            pixel_width: 0,
+            image_offset: 0,
+            image_height: 2,
+            origin: (0, 0),
+            advance: 3,
+        },
+    ];
+
+    /// A two-glyph font, a space and a 2x2 block for `'!'`.
+    static FONT: Font<'static, 'static, 'static, 'static> = Font {
+        ascent: 2,
+        descent: 0,
+        line_spacing: 3,
+        glyph_storage: GlyphStorage::Dense { first: b' ', glyphs: &GLYPHS },
+        replacement: 0,
+        replacement_char: None,
+        bitmaps: &[0xC0, 0xC0],
+        bitmap_encoding: BitmapEncoding::Raw,
+        kerning: Kerning::Narrow(KerningTable { entries: &[] }),
+        advance_bias: 0,
+        ligatures: LigatureTable { entries: &[] },
+    };
+
+    /// `FONT` as `generate_rust_module` makes it with `constants`.
+    const OPTIONS: RustOptions<'static> = RustOptions {
+        prefix: Some("block"),
+        crate_path: Some("crate"),
+        sources: &[],
+        constants: true,
+    };
+
+    // The module `generate_rust_module` makes of `FONT` with `OPTIONS`,
+    // checked in so that building the tests shows that it compiles.
+    // `const_module_is_current` checks that it's up to date.
+    #[rustfmt::skip]
+    mod block {
+        include!("gen/block_const.rs");
+    }
+
+    // The generated items, used in const contexts.
+    const FONTS: [Font<'static, 'static, 'static, 'static>; 2] = [block::BLOCK_FONT; 2];
+    const BLOCK_ADVANCE: u8 = block::BLOCK_GLYPHS[1].advance;
+    const _: () = assert!(block::BLOCK_BITMAPS.len() == 2);
+    const _: () = assert!(matches!(
+        block::BLOCK_FONT.glyph_storage,
+        GlyphStorage::Dense { first: b' ', .. },
+    ));
+
+    /// Generates a C header for `font`, and returns its code with the `//`
+    /// comments (which draw the glyphs, and name their chars) removed.
+    fn c_code(font: &Font<'_, '_, '_, '_>, name: &str) -> String {
+        let mut out = vec![];
+        generate_c_header(font, name, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| line.split("//").next().unwrap().trim_end())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn c_header_is_well_formed() {
+        let code = c_code(&FONT, "block");
+        assert_eq!(code.matches('{').count(), code.matches('}').count());
+        assert!(code.contains("#define BLOCK_GLYPH_COUNT 2\n"));
+        let (_, glyphs) = code.split_once("block_glyphs[2] = {\n").unwrap();
+        let (glyphs, _) = glyphs.split_once("};").unwrap();
+        assert_eq!(glyphs.lines().count(), 2);
+    }
+
+    #[test]
+    fn c_header_has_no_empty_arrays() {
+        let storages = [
+            GlyphStorage::Dense { first: b' ', glyphs: &[] },
+            GlyphStorage::Sparse { sorted_glyphs: &[] },
+        ];
+        for glyph_storage in storages {
+            let font = Font { glyph_storage, bitmaps: &[], ..FONT };
+            let code = c_code(&font, "empty");
+            assert!(!code.contains("[0]"), "{code}");
+            assert!(code.contains("#define EMPTY_GLYPH_COUNT 0\n"));
+            assert_eq!(code.matches('{').count(), code.matches('}').count());
+        }
+    }
+
+    fn render(font: &Font<'_, '_, '_, '_>, text: &str) -> image::GrayImage {
+        let mut img = image::GrayImage::new(font.width(text) as u32, u32::from(font.line_spacing));
+        font.render(text, 0, 0, &mut img, image::Luma([0xFF]));
+        img
+    }
+
+    #[test]
+    fn compressed_font_renders_like_raw() {
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        let options = Default::default();
+        crate::load::load_font_from_png(png, GlyphOrder::Iso8859_1, Some(b' '), &options, |font| {
+            let packed = compress_bitmaps(font).unwrap();
+            let packed = packed.as_font();
+            assert_eq!(packed.bitmap_encoding, BitmapEncoding::PackBits);
+            assert!(packed.bitmaps.len() < font.bitmaps.len());
+            let text = "Hello, world! {~}";
+            assert_eq!(render(&packed, text), render(font, text));
+            // Each glyph is still decoded in one piece.
+            let calls = |font: &Font<'_, '_, '_, '_>| {
+                let mut calls = 0;
+                font.render_core(text, 0, 0, |_, _, _, _| calls += 1);
+                calls
+            };
+            assert_eq!(calls(&packed), calls(font));
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn compressed_big_glyph_renders_like_raw() {
+        // Too big to decompress in one go, so it's drawn in bands.
+        let glyphs = [Glyph {
+            row_bytes: 9,
+            pixel_width: 0,
+            image_offset: 0,
+            image_height: 255,
+            origin: (0, 0),
+            advance: 72,
+        }];
+        let bitmaps: Vec<u8> = (0..9 * 255)
+            .map(|i| if (i / 9) % 3 == 0 { 0xFF } else { 0xAA })
+            .collect();
+        let font = Font {
+            line_spacing: 255,
+            glyph_storage: GlyphStorage::Dense { first: b'!', glyphs: &glyphs },
+            bitmaps: &bitmaps,
+            ..FONT
+        };
+        let packed = compress_bitmaps(&font).unwrap();
+        assert_eq!(render(&packed.as_font(), "!!"), render(&font, "!!"));
+    }
+
+    #[test]
+    fn packbits_round_trip() {
+        let zeros = [0; 300];
+        let alternating: Vec<u8> = (0..40).map(|i| if i % 2 == 0 { 0xAA } else { 0x55 }).collect();
+        let mixed = [1, 2, 2, 3, 3, 3, 0, 0, 0, 0, 9];
+        for data in [&zeros[..], &alternating, &mixed] {
+            let mut packed = vec![];
+            packbits_encode(data, &mut packed);
+            let unpacked: Vec<u8> = crate::PackBits::new(&packed).collect();
+            assert_eq!(unpacked, data);
+        }
+    }
+
+    #[test]
+    fn glyph_preview_of_8x8_glyph() {
+        let glyph = Glyph {
+            row_bytes: 1,
+            pixel_width: 0,
+            image_offset: 0,
+            image_height: 8,
+            origin: (0, 0),
+            advance: 8,
+        };
+        let bitmap = [0x18, 0x3C, 0x66, 0xC3, 0xFF, 0xC3, 0xC3, 0x00];
+        let mut out = vec![];
+        glyph_preview(&glyph, &bitmap, BitmapEncoding::Raw, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+|   **   |
+|  ****  |
+| **  ** |
+|**    **|
+|********|
+|**    **|
+|**    **|
+|        |
+");
+    }
+
+
+    /// Generates `FONT` twice, under two prefixes, into one module.
+    fn prefixed_modules() -> String {
+        let mut out = vec![];
+        for prefix in ["small", "big"] {
+            let options = RustOptions { prefix: Some(prefix), crate_path: Some("crate"), ..Default::default() };
+            generate_rust_module(&FONT, &options, &mut out).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    // Both prefixes in one module, generated by `prefixed_modules`. Being
+    // compiled here is what proves the names don't collide.
+    #[rustfmt::skip]
+    mod prefixed {
+        include!("gen/prefixed.rs");
+    }
+
+    #[test]
+    fn prefixed_modules_are_current() {
+        assert_eq!(
+            prefixed_modules(),
+            include_str!("gen/prefixed.rs"),
+            "src/gen/prefixed.rs needs regenerating",
+        );
+        assert_eq!(prefixed::SMALL_FONT.width("!!"), FONT.width("!!"));
+        assert_eq!(prefixed::BIG_FONT.width("!!"), FONT.width("!!"));
+    }
+
+    #[test]
+    fn identical_images_are_shared() {
+        // `'"'` has its own copy of `'!'`'s image.
+        static COPIED: [Glyph; 3] = [GLYPHS[0], GLYPHS[1], Glyph { image_offset: 2, ..GLYPHS[1] }];
+        let font = Font {
+            glyph_storage: GlyphStorage::Dense { first: b' ', glyphs: &COPIED },
+            bitmaps: &[0xC0, 0xC0, 0xC0, 0xC0],
+            ..FONT
+        };
+        let sharing = bitmap_sharing(&font);
+        assert_eq!(sharing, BitmapSharing { glyphs: 2, unique: 1, stored: 2 });
+        assert_eq!(sharing.to_string(), "2 glyphs share 1 unique bitmaps (2 stored copies)");
+
+        let shared = share_bitmaps(&font).unwrap();
+        let shared = shared.as_font();
+        let offset = |c| shared.glyph_storage.get(c).unwrap().image_offset;
+        assert_eq!(offset('!'), offset('"'));
+        assert_eq!(shared.bitmaps, [0xC0, 0xC0]);
+        assert_eq!(bitmap_sharing(&shared).stored, 1);
+        // Nothing is left to share.
+        assert!(share_bitmaps(&shared).is_none());
+    }
+
+    #[test]
+    fn metrics_name_each_glyph() {
+        #[derive(serde::Deserialize)]
+        struct Metrics {
+            line_spacing: u8,
+            glyphs: Vec<Entry>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Entry {
+            char: char,
+            width: usize,
+            image_height: u8,
+        }
+
+        let mut out = vec![];
+        write_metrics_ron(&FONT, &mut out).unwrap();
+        let metrics: Metrics = ron::de::from_bytes(&out).unwrap();
+        assert_eq!(metrics.line_spacing, 3);
+        let glyphs: Vec<_> = metrics.glyphs.iter()
+            .map(|g| (g.char, g.width, g.image_height))
+            .collect();
+        assert_eq!(glyphs, [(' ', 0, 0), ('!', 8, 2)]);
+    }
+
+    #[test]
+    fn atlas_rects_hold_their_glyphs() {
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        let order = crate::load::GlyphOrder::Iso8859_1;
+        crate::load::load_font_from_png(png, order, Some(b' '), &Default::default(), |font| {
+            let (atlas, entries) = generate_atlas(font);
+            let inked: Vec<_> = entries.iter().filter(|e| e.rect.2 * e.rect.3 != 0).collect();
+            assert!(inked.len() > 90);
+            for (n, e) in inked.iter().enumerate() {
+                let (x, y, w, h) = e.rect;
+                assert!(x + w <= atlas.width() && y + h <= atlas.height(), "{:?}", e.ch);
+                for f in &inked[n + 1..] {
+                    let (fx, fy, fw, fh) = f.rect;
+                    let apart = x + w <= fx || fx + fw <= x || y + h <= fy || fy + fh <= y;
+                    assert!(apart, "{:?} overlaps {:?}", e.ch, f.ch);
+                }
+
+                let glyph = font.glyph_storage.get(e.ch).unwrap();
+                assert_eq!((e.origin, e.advance), (glyph.origin, glyph.advance));
+                for (gx, gy, set) in glyph.pixels(font.bitmaps, font.bitmap_encoding) {
+                    let p = atlas.get_pixel(x + gx as u32, y + gy as u32).0[0];
+                    assert_eq!(p, if set { 0xFF } else { 0 }, "{:?} at ({gx}, {gy})", e.ch);
+                }
+            }
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn comments_note_glyph_sources() {
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        let order = crate::load::GlyphOrder::Iso8859_1;
+        let loaded = crate::load::load_owned_font_from_png(png, order, Some(b' '), &Default::default())
+            .unwrap();
+        let module = |options: &RustOptions<'_>| {
+            let mut out = vec![];
+            generate_rust_module(&loaded.as_font(), options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let code = module(&RustOptions { sources: &loaded.sources, ..Default::default() });
+        // 'A' starts the second band, below the first's separator row.
+        assert!(code.contains("    // index 33: 'A', from page 0 at (0, 29), 18x23\n"));
+        assert_eq!(code.matches(", from page 0 at (").count(), loaded.sources.len());
+
+        let code = module(&RustOptions::default());
+        assert!(code.contains("    // index 33: 'A'\n"));
+    }
+
+    #[test]
+    fn block_glyph_ink() {
+        let stats = glyph_stats(&FONT);
+        assert_eq!(stats[0], GlyphStat { ch: ' ', width: 0, height: 0, set_pixels: 0 });
+        assert_eq!(stats[1], GlyphStat { ch: '!', width: 8, height: 2, set_pixels: 4 });
+        assert_eq!(stats[0].ink_fraction(), 0.);
+        assert_eq!(stats[1].ink_fraction(), 0.25);
+        assert_eq!(ink_fraction(&stats), 0.25);
+    }
+
+    #[test]
+    fn const_module_is_current() {
+        let mut out = vec![];
+        generate_rust_module(&FONT, &OPTIONS, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("gen/block_const.rs"),
+            "src/gen/block_const.rs needs regenerating",
+        );
+    }
+
+    #[test]
+    fn const_font_is_usable() {
+        assert_eq!(FONTS[1], FONT);
+        assert_eq!(BLOCK_ADVANCE, 3);
+        assert_eq!(FONTS[0].width("! !"), 9);
+    }
+}
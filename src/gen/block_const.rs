@@ -0,0 +1,45 @@
+pub const BLOCK_FONT: crate::Font = crate::Font {
+    ascent: 2,
+    descent: 0,
+    line_spacing: 3,
+    glyph_storage: crate::GlyphStorage::Dense {
+        first: 32,
+        glyphs: &BLOCK_GLYPHS,
+    },
+    replacement: 0,
+    replacement_char: None,
+    bitmaps: &BLOCK_BITMAPS,
+    bitmap_encoding: crate::BitmapEncoding::Raw,
+    kerning: crate::Kerning::Narrow(crate::KerningTable { entries: &BLOCK_KERNING_ENTRIES }),
+    advance_bias: 0,
+    ligatures: crate::LigatureTable { entries: &BLOCK_LIGATURES },
+};
+pub const BLOCK_GLYPHS: [crate::Glyph; 2] = [
+    // index 0: ' '
+    crate::Glyph {
+        row_bytes: 0,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 0,
+        origin: (0, 0),
+        advance: 3,
+    },
+    // index 1: '!'
+    // |**      |
+    // |**      |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 2,
+        origin: (0, 0),
+        advance: 3,
+    },
+];
+pub const BLOCK_KERNING_ENTRIES: [crate::KerningEntry; 0] = [
+];
+pub const BLOCK_LIGATURES: [(&str, u16); 0] = [
+];
+pub const BLOCK_BITMAPS: [u8; 2] = [
+    0xc0, 0xc0,
+];
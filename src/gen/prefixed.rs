@@ -0,0 +1,90 @@
+pub static SMALL_FONT: crate::Font = crate::Font {
+    ascent: 2,
+    descent: 0,
+    line_spacing: 3,
+    glyph_storage: crate::GlyphStorage::Dense {
+        first: 32,
+        glyphs: &SMALL_GLYPHS,
+    },
+    replacement: 0,
+    replacement_char: None,
+    bitmaps: &SMALL_BITMAPS,
+    bitmap_encoding: crate::BitmapEncoding::Raw,
+    kerning: crate::Kerning::Narrow(crate::KerningTable { entries: &SMALL_KERNING_ENTRIES }),
+    advance_bias: 0,
+    ligatures: crate::LigatureTable { entries: &SMALL_LIGATURES },
+};
+pub static SMALL_GLYPHS: [crate::Glyph; 2] = [
+    // index 0: ' '
+    crate::Glyph {
+        row_bytes: 0,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 0,
+        origin: (0, 0),
+        advance: 3,
+    },
+    // index 1: '!'
+    // |**      |
+    // |**      |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 2,
+        origin: (0, 0),
+        advance: 3,
+    },
+];
+pub static SMALL_KERNING_ENTRIES: [crate::KerningEntry; 0] = [
+];
+pub static SMALL_LIGATURES: [(&str, u16); 0] = [
+];
+pub static SMALL_BITMAPS: [u8; 2] = [
+    0xc0, 0xc0,
+];
+pub static BIG_FONT: crate::Font = crate::Font {
+    ascent: 2,
+    descent: 0,
+    line_spacing: 3,
+    glyph_storage: crate::GlyphStorage::Dense {
+        first: 32,
+        glyphs: &BIG_GLYPHS,
+    },
+    replacement: 0,
+    replacement_char: None,
+    bitmaps: &BIG_BITMAPS,
+    bitmap_encoding: crate::BitmapEncoding::Raw,
+    kerning: crate::Kerning::Narrow(crate::KerningTable { entries: &BIG_KERNING_ENTRIES }),
+    advance_bias: 0,
+    ligatures: crate::LigatureTable { entries: &BIG_LIGATURES },
+};
+pub static BIG_GLYPHS: [crate::Glyph; 2] = [
+    // index 0: ' '
+    crate::Glyph {
+        row_bytes: 0,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 0,
+        origin: (0, 0),
+        advance: 3,
+    },
+    // index 1: '!'
+    // |**      |
+    // |**      |
+    crate::Glyph {
+        row_bytes: 1,
+        pixel_width: 0,
+        image_offset: 0,
+        image_height: 2,
+        origin: (0, 0),
+        advance: 3,
+    },
+];
+pub static BIG_KERNING_ENTRIES: [crate::KerningEntry; 0] = [
+];
+pub static BIG_LIGATURES: [(&str, u16); 0] = [
+];
+pub static BIG_BITMAPS: [u8; 2] = [
+    0xc0, 0xc0,
+];
@@ -1,14 +1,48 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod binary;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "builtin-font")]
+mod builtin;
+#[cfg(feature = "std")]
+mod cache;
+mod chain;
 #[cfg(feature = "std")]
 pub mod gen;
+mod gamma;
 #[cfg(feature = "std")]
 pub mod load;
+#[cfg(feature = "std")]
+mod owned;
+#[cfg(feature = "std")]
+mod validate;
+
+#[cfg(feature = "std")]
+pub use builder::FontBuilder;
+#[cfg(feature = "builtin-font")]
+pub use builtin::DEFAULT_FONT;
+#[cfg(feature = "std")]
+pub use cache::WidthCache;
+pub use chain::FontChain;
+#[cfg(feature = "std")]
+pub use owned::{GlyphSource, OwnedFont, OwnedGlyphStorage, OwnedKerning};
+#[cfg(feature = "std")]
+pub use validate::FontError;
 
 /// In-memory representation of a font, which is a typeface realized at a
 /// particular size, weight, and other parameters.
-#[derive(Copy, Clone, Debug)]
-pub struct Font<'g, 'i, 'k> {
+///
+/// With the `serde` feature, this can be serialized, but since it borrows its
+/// tables it can't be deserialized; deserialize an `OwnedFont` instead.
+///
+/// Fonts compare and hash by the contents of their tables, not by where the
+/// tables are, so two copies of the same font loaded separately are equal.
+/// Tables must also be in the same form to match, though: a `Narrow` kerning
+/// table doesn't equal a `Wide` one holding the same pairs.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Font<'g, 'i, 'k, 'l> {
     /// Displacement from the top of the bounding box to the baseline, in
     /// pixels.
     pub ascent: u8,
@@ -24,16 +58,31 @@ pub struct Font<'g, 'i, 'k> {
     /// Index of glyph to use as a replacement for rendering characters whose
     /// glyphs are missing in this font.
     ///
-    /// Note that glyph indexes are glyph-storage specific.
+    /// Note that glyph indexes are glyph-storage specific, so
+    /// `replacement_char` is usually more convenient. This is used only if
+    /// `replacement_char` is `None` or missing from the font.
     pub replacement: u8,
+    /// Char whose glyph is used as a replacement for rendering characters
+    /// whose glyphs are missing in this font, such as `'?'` or U+FFFD. If this
+    /// is `None`, or the font has no glyph for it, `replacement` is used.
+    pub replacement_char: Option<char>,
     /// Bitmap storage for all glyphs. Individual glyphs reference ranges in
     /// this slice.
     pub bitmaps: &'i [u8],
+    /// How glyph images are encoded in `bitmaps`.
+    pub bitmap_encoding: BitmapEncoding,
     /// Kerning table for adjusting glyph-to-glyph spacing.
-    pub kerning: KerningTable<'k>,
+    pub kerning: Kerning<'k>,
+    /// Adjustment to the advance of every glyph, in pixels, for spacing out
+    /// (or tightening) a whole font without editing its glyphs. This applies
+    /// on top of each glyph's own `advance`, before kerning, and can't make an
+    /// advance less than zero. Usually zero.
+    pub advance_bias: i8,
+    /// Ligature table, for drawing sequences of chars as a single glyph.
+    pub ligatures: LigatureTable<'l>,
 }
 
-impl<'k> Font<'_, '_, 'k> {
+impl<'k> Font<'_, '_, 'k, '_> {
     /// Given the Y coordinate of the desired text baseline, this computes the Y
     /// of the top of its bounding box, for use with `render`.
     ///
@@ -53,17 +102,70 @@ impl<'k> Font<'_, '_, 'k> {
     /// Looks up the glyph for `c`, or the replacement glyph if `c` is not
     /// present in this font.
     pub fn get_glyph_or_replacement(&self, c: char) -> &Glyph {
-        self.glyph_storage.get(c).unwrap_or_else(|| {
-            self.glyph_storage.get_by_index(usize::from(self.replacement))
-                .unwrap()
-        })
+        self.glyph_storage.get(c)
+            .or_else(|| self.glyph_storage.get(self.replacement_char?))
+            .unwrap_or_else(|| {
+                self.glyph_storage.get_by_index(usize::from(self.replacement))
+                    .unwrap()
+            })
+    }
+
+    /// Returns the chars in `s` that this font has no glyph for, and that
+    /// would be drawn using the replacement glyph.
+    ///
+    /// Chars are produced in the order they appear in `s`, and are not
+    /// deduplicated: a missing char that appears twice is produced twice.
+    pub fn missing_chars<'s>(&'s self, s: &'s str) -> impl Iterator<Item = char> + 's {
+        s.chars().filter(|&c| self.glyph_storage.get(c).is_none())
+    }
+
+    /// Checks whether this font has a glyph for every char in `s`, so that it
+    /// can be drawn without replacement glyphs.
+    pub fn has_all_glyphs(&self, s: &str) -> bool {
+        self.missing_chars(s).next().is_none()
+    }
+
+    /// Checks whether this font has a glyph of its own for `c`, rather than
+    /// drawing it with the replacement glyph.
+    pub fn covers(&self, c: char) -> bool {
+        self.glyph_storage.get(c).is_some()
+    }
+
+    /// Returns the number of glyphs in this font, including any that no char
+    /// maps to.
+    pub fn glyph_count(&self) -> usize {
+        match self.glyph_storage {
+            GlyphStorage::Dense { glyphs, .. }
+            | GlyphStorage::Ranges { glyphs, .. } => glyphs.len(),
+            GlyphStorage::Sparse { sorted_glyphs } => sorted_glyphs.len(),
+        }
+    }
+
+    /// For a font with `Dense` storage, returns the first and last chars it
+    /// covers, inclusive; every char between them has a glyph. Returns `None`
+    /// for other kinds of storage, which can have gaps, and for a font with no
+    /// glyphs.
+    pub fn char_range(&self) -> Option<(char, char)> {
+        match self.glyph_storage {
+            GlyphStorage::Dense { first, glyphs } => {
+                let last = u32::from(first) + u32::try_from(glyphs.len()).ok()?.checked_sub(1)?;
+                Some((char::from(first), char::from_u32(last)?))
+            }
+            _ => None,
+        }
     }
 
     /// Computes the width, in pixels, of the char `c` rendered in this font.
     /// Note that this ignores kerning, so looping over the chars in a string
     /// will not get you the correct result (see `width`).
     pub fn char_width(&self, c: char) -> usize {
-        usize::from(self.get_glyph_or_replacement(c).advance)
+        self.advance_usize(self.get_glyph_or_replacement(c))
+    }
+
+    /// Returns the advance of `glyph` in this font, in pixels: its own
+    /// advance adjusted by `advance_bias`.
+    pub fn advance_usize(&self, glyph: &Glyph) -> usize {
+        kern_usize(self.advance_bias, glyph.default_advance_usize())
     }
 
     /// Computes the width, in pixels, of the string `s` rendered in this font.
@@ -71,21 +173,543 @@ impl<'k> Font<'_, '_, 'k> {
     /// using whatever glyph is given in the font for `'\n'`.
     ///
     /// This happens to be exactly the same logic used by `render`, so you can
-    /// use `width` to work out the dimensions needed for `render`.
+    /// use `width` to work out the dimensions needed for `render`. An empty
+    /// string has width zero.
     pub fn width(&self, s: &str) -> usize {
         let mut x = 0_usize;
         let mut kerning = self.start_kerning();
 
-        for c in s.chars() {
-            kerning.adjust_usize_for_char(c, &mut x);
+        for cluster in self.clusters(s) {
+            kerning.adjust_usize_for_cluster(&cluster, &mut x);
 
             // Add the default advance; if kerning applies we'll handle it next
             // iteration.
-            x = x.saturating_add(self.char_width(c));
+            x = x.saturating_add(self.advance_usize(cluster.glyph));
         }
         x
     }
 
+    /// Computes the width of the part of `s` starting at the char at index
+    /// `from_char_index` (counting chars, not bytes), for measuring
+    /// right-aligned text as chars are added to its start.
+    ///
+    /// The suffix is measured in isolation, exactly as `width` would measure
+    /// it on its own: there's no kerning between the char before the suffix
+    /// and the suffix's first char, and a ligature cut by the start of the
+    /// suffix isn't formed. So this can differ from the width that the suffix
+    /// takes up within all of `s`. An index at or past the end of `s` gives
+    /// zero.
+    pub fn width_suffix(&self, s: &str, from_char_index: usize) -> usize {
+        match s.char_indices().nth(from_char_index) {
+            Some((i, _)) => self.width(&s[i..]),
+            None => 0,
+        }
+    }
+
+    /// Computes the width of `s`, like `width`, but in units of
+    /// `1 / SUBPIXELS` of a pixel, for use with `render_offset`.
+    ///
+    /// Advances are whole pixels, so this is always a multiple of
+    /// `SUBPIXELS`; it exists so that scrolling code can do all its arithmetic
+    /// in one unit.
+    pub fn width_subpixel(&self, s: &str) -> usize {
+        self.width(s).saturating_mul(SUBPIXELS)
+    }
+
+    /// Computes the X position, relative to the start of `s`, of a text
+    /// cursor placed before the char at index `char_index` (counting chars,
+    /// not bytes). This follows the same layout as `width`, so kerning is
+    /// taken into account.
+    ///
+    /// An index at or past the end of `s` puts the cursor at the end, so
+    /// `caret_x(s, s.chars().count())` is `width(s)`. Positions within a
+    /// ligature are spaced evenly across its glyph.
+    pub fn caret_x(&self, s: &str, char_index: usize) -> usize {
+        let mut caret = None;
+        self.for_each_caret(s, |i, x| {
+            if i == char_index {
+                caret = Some(x);
+            }
+        });
+        caret.unwrap_or_else(|| self.width(s))
+    }
+
+    /// Finds the char index of the cursor position in `s` nearest to
+    /// `target_x`, for turning a click back into a position in the text. This
+    /// is the inverse of `caret_x`.
+    ///
+    /// A point exactly halfway between two cursor positions, such as the
+    /// middle of an evenly sized glyph, goes to the later position.
+    pub fn char_at_x(&self, s: &str, target_x: usize) -> usize {
+        let mut best = (0, usize::MAX);
+        self.for_each_caret(s, |i, x| {
+            let distance = x.abs_diff(target_x);
+            if distance <= best.1 {
+                best = (i, distance);
+            }
+        });
+        best.0
+    }
+
+    /// Calls `each` with the char index and X position of every cursor
+    /// position in `s`, from before the first char through after the last.
+    fn for_each_caret(&self, s: &str, mut each: impl FnMut(usize, usize)) {
+        let mut index = 0;
+        let end = self.layout_clusters(s, 0, |cluster, pen_x| {
+            let advance = self.advance_usize(cluster.glyph);
+            for i in 0..cluster.chars {
+                each(index + i, pen_x.saturating_add(advance * i / cluster.chars));
+            }
+            index += cluster.chars;
+        });
+        each(index, end);
+    }
+
+    /// Splits `s` into the glyphs used to draw it, substituting ligatures.
+    fn clusters<'s>(&'s self, s: &'s str) -> impl Iterator<Item = Cluster<'s>> + 's {
+        let mut rest = s.char_indices();
+        core::iter::from_fn(move || {
+            let (start, first) = rest.next()?;
+            let glyph = self.get_glyph_or_replacement(first);
+            let missing = self.glyph_storage.get(first).is_none();
+            let mut cluster = Cluster { start, chars: 1, first, last: first, glyph, missing };
+            self.apply_ligature(s, &mut cluster, &mut rest);
+            Some(cluster)
+        })
+    }
+
+    /// If a ligature starts at `cluster`'s position in `s`, extends `cluster`
+    /// to cover it, consuming the rest of its chars from `rest`.
+    fn apply_ligature<'f>(
+        &'f self,
+        s: &str,
+        cluster: &mut Cluster<'f>,
+        rest: &mut core::str::CharIndices<'_>,
+    ) {
+        let s = &s[cluster.start..];
+        let found = self.ligatures.entries.iter()
+            .filter(|(seq, _)| !seq.is_empty() && s.starts_with(seq))
+            .filter_map(|&(seq, index)| {
+                Some((seq, self.glyph_storage.get_by_index(usize::from(index))?))
+            })
+            .max_by_key(|(seq, _)| seq.len());
+        let Some((seq, glyph)) = found else {
+            return;
+        };
+        for _ in seq.chars().skip(1) {
+            if let Some((_, c)) = rest.next() {
+                cluster.chars += 1;
+                cluster.last = c;
+            }
+        }
+        cluster.glyph = glyph;
+        cluster.missing = false;
+    }
+
+    /// Lays out `text` as lines, returning the text, width, and Y offset of
+    /// each.
+    ///
+    /// Lines are split on `\n` or `\r\n`. With `TrailingNewline::Ends`, this
+    /// works the same way as `str::lines`, with no final empty line if `text`
+    /// ends in a newline, and no lines at all for an empty `text`. With
+    /// `TrailingNewline::StartsLine`, every newline starts another line, so
+    /// there's always one more line than there are newlines. Each line's `y`
+    /// is its index times the line spacing, suitable for passing to `render`
+    /// after adding the Y coordinate of the first line.
+    ///
+    /// The line spacing is the font's `line_spacing`, unless `layout`
+    /// overrides it. An override smaller than the font's `ascent + descent`
+    /// is allowed, but makes the lines' bounding boxes overlap, so that
+    /// descenders on one line can run into the next.
+    pub fn lines_layout<'s>(
+        &'s self,
+        text: &'s str,
+        layout: LayoutOptions,
+    ) -> impl Iterator<Item = LineInfo<'s>> + 's {
+        let spacing = self.spacing_or_default(layout.line_spacing);
+        let last_empty = layout.newline == TrailingNewline::StartsLine
+            && (text.is_empty() || text.ends_with('\n'));
+        text.split_inclusive('\n')
+            .map(|line| match line.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => line,
+            })
+            .chain(last_empty.then_some(""))
+            .enumerate()
+            .map(move |(i, line)| LineInfo {
+                text: line,
+                width: self.width(line),
+                y: i.saturating_mul(spacing),
+            })
+    }
+
+    /// Computes the `(width, height)` in pixels of the box holding `text` laid
+    /// out as lines by `lines_layout` with the same `layout`. It's as wide as
+    /// the widest line, and tall enough to reach the bottom of the last line:
+    /// one line spacing per line, except that the last line is at least
+    /// `ascent + descent` tall, in case the spacing is tighter than that.
+    pub fn measure(&self, text: &str, layout: LayoutOptions) -> (usize, usize) {
+        let spacing = self.spacing_or_default(layout.line_spacing);
+        let (width, lines) = self.lines_layout(text, layout)
+            .fold((0, 0_usize), |(width, lines), line| (width.max(line.width), lines + 1));
+        (width, self.block_height(lines, spacing))
+    }
+
+    /// Returns the height of `lines` lines spaced `spacing` apart, as
+    /// described for `measure`.
+    fn block_height(&self, lines: usize, spacing: usize) -> usize {
+        let last_height = spacing.max(usize::from(self.ascent) + usize::from(self.descent));
+        match lines {
+            0 => 0,
+            n => (n - 1).saturating_mul(spacing).saturating_add(last_height),
+        }
+    }
+
+    /// Returns `line_spacing` if it's given, or the font's line spacing
+    /// otherwise.
+    fn spacing_or_default(&self, line_spacing: Option<usize>) -> usize {
+        line_spacing.unwrap_or_else(|| self.line_spacing_usize())
+    }
+
+    /// Renders `text` as lines, as laid out by `lines_layout` with the given
+    /// `layout`, into a new image sized to fit it by `measure`. Pixels that
+    /// are set in the font are `fg`, and the rest are `bg`.
+    ///
+    /// An image can't be empty, so for text with no size (such as an empty
+    /// string), this returns a single `bg` pixel.
+    ///
+    /// # Panics
+    ///
+    /// If the text is too big for an image, i.e., more than `u32::MAX` pixels
+    /// in either direction.
+    #[cfg(feature = "std")]
+    pub fn render_to_image(
+        &self,
+        text: &str,
+        fg: u8,
+        bg: u8,
+        layout: LayoutOptions,
+    ) -> image::GrayImage {
+        let (width, height) = self.measure(text, layout);
+        let mut img = image::GrayImage::from_pixel(
+            u32::try_from(width.max(1)).expect("text too wide for an image"),
+            u32::try_from(height.max(1)).expect("text too tall for an image"),
+            image::Luma([bg]),
+        );
+        for line in self.lines_layout(text, layout) {
+            self.render_direct(line.text, 0, line.y, &mut img, image::Luma([fg]));
+        }
+        img
+    }
+
+    /// Renders `text` like `render_to_image`, but crops the image to the
+    /// bounding box of the ink, plus `margin` pixels of `bg` on every side.
+    ///
+    /// This leaves out the blank rows above and below short glyphs, and any
+    /// space around the text, which suits icons and sprites. Pixels count as
+    /// ink if they differ from `bg`, so if `fg` and `bg` are the same, or the
+    /// text has no ink at all, this returns a single `bg` pixel.
+    ///
+    /// # Panics
+    ///
+    /// Under the same conditions as `render_to_image`.
+    #[cfg(feature = "std")]
+    pub fn render_to_image_tight(
+        &self,
+        text: &str,
+        fg: u8,
+        bg: u8,
+        layout: LayoutOptions,
+        margin: u32,
+    ) -> image::GrayImage {
+        let full = self.render_to_image(text, fg, bg, layout);
+        let ink = full.enumerate_pixels()
+            .filter(|(_, _, p)| p.0[0] != bg)
+            .fold(None, |bounds: Option<(u32, u32, u32, u32)>, (x, y, _)| Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            }));
+        let Some((x0, y0, x1, y1)) = ink else {
+            return image::GrayImage::from_pixel(1, 1, image::Luma([bg]));
+        };
+        let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+        let grow = |n: u32| n.checked_add(margin.saturating_mul(2))
+            .expect("text too big for an image");
+        let mut img = image::GrayImage::from_pixel(grow(width), grow(height), image::Luma([bg]));
+        let ink = image::imageops::crop_imm(&full, x0, y0, width, height);
+        image::imageops::replace(&mut img, &*ink, i64::from(margin), i64::from(margin));
+        img
+    }
+
+    /// Renders `text` as lines, as laid out by `lines_layout`, into ASCII art,
+    /// for eyeballing layout or comparing against expected output in tests.
+    ///
+    /// The result covers the box `measure` gives for the text, in the same
+    /// format as `gen::glyph_preview`: each row becomes a line ending in `\n`,
+    /// with `*` for set pixels and a space for clear ones, between `|`
+    /// borders. Text with no size produces an empty string.
+    #[cfg(feature = "std")]
+    pub fn debug_render(&self, text: &str) -> String {
+        let layout = LayoutOptions::default();
+        let (width, height) = self.measure(text, layout);
+        if width == 0 {
+            return String::new();
+        }
+        let mut pixels = vec![false; width.saturating_mul(height)];
+        let mut target = SliceTarget::new(&mut pixels, width, width);
+        for line in self.lines_layout(text, layout) {
+            self.render(line.text, 0, line.y, &mut target, true);
+        }
+        let mut art = String::with_capacity((width + 3) * height);
+        for row in pixels.chunks(width) {
+            art.push('|');
+            art.extend(row.iter().map(|&set| if set { '*' } else { ' ' }));
+            art.push_str("|\n");
+        }
+        art
+    }
+
+    /// Renders text on a single line into an RGBA image, for compositing
+    /// over something else later.
+    ///
+    /// This works like `render`: every pixel that's set in the font becomes
+    /// `fg` with an alpha of 255, fully opaque, and every other pixel is left
+    /// exactly as it was, alpha included. So in an image that starts out
+    /// fully transparent (all zeros, as from `RgbaImage::new`), the text's
+    /// pixels are opaque and the rest stay transparent, ready to be blended
+    /// over a background.
+    #[cfg(feature = "std")]
+    pub fn render_rgba(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut image::RgbaImage,
+        fg: image::Rgb<u8>,
+    ) {
+        let [r, g, b] = fg.0;
+        self.render(string, x, y, target, image::Rgba([r, g, b, 0xFF]));
+    }
+
+    /// Renders `text` as lines, like `render_to_image`, but mirrored left to
+    /// right as a whole, for printing on the back of a transparency or with
+    /// printers that feed the wrong way.
+    ///
+    /// Unlike `render_flipped`, which mirrors a single line in place, this
+    /// mirrors the entire block of lines within the box `measure` gives for
+    /// it, with its top left corner at `(x, y)`. Short lines end up
+    /// right-aligned, and ink hanging past the box is lost.
+    ///
+    /// This draws into a scratch buffer the size of the box first, so it's
+    /// only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn render_mirrored_block<T>(
+        &self,
+        text: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        line_spacing: Option<usize>,
+    )
+        where T: RenderTarget,
+    {
+        let layout = LayoutOptions { line_spacing, ..LayoutOptions::default() };
+        let (width, height) = self.measure(text, layout);
+        if width == 0 {
+            return;
+        }
+        let mut scratch = vec![false; width.saturating_mul(height)];
+        let mut block = SliceTarget::new(&mut scratch, width, width);
+        for line in self.lines_layout(text, layout) {
+            self.render(line.text, 0, line.y, &mut block, true);
+        }
+        for (v, row) in scratch.chunks(width).enumerate() {
+            for (u, _) in row.iter().enumerate().filter(|(_, &set)| set) {
+                target.put_pixel_slow(
+                    x.saturating_add(width - 1 - u),
+                    y.saturating_add(v),
+                    fg,
+                );
+            }
+        }
+    }
+
+    /// Breaks `text` into lines that fit within `max_width` pixels, returning
+    /// the text of each line.
+    ///
+    /// `text` is first split into paragraphs at newlines, the same way as
+    /// `lines_layout`, and each paragraph is then wrapped greedily: each line
+    /// holds as many words as fit, breaking at ASCII whitespace. The
+    /// whitespace at a break is dropped, but whitespace at the start of a
+    /// paragraph is kept. A word too wide to fit on a line by itself is broken
+    /// between chars, and a line always holds at least one char, even if that
+    /// char is wider than `max_width`. An empty paragraph produces one empty
+    /// line.
+    pub fn wrap<'s>(
+        &'s self,
+        text: &'s str,
+        max_width: usize,
+    ) -> impl Iterator<Item = &'s str> + 's {
+        self.wrap_paragraphs(text, max_width).map(|(line, _)| line)
+    }
+
+    /// Breaks `text` into lines that fit within `max_width` pixels, like
+    /// `wrap`, but with each run of ASCII whitespace within a paragraph
+    /// collapsed into a single space first, the way HTML and Markdown treat
+    /// spaces.
+    ///
+    /// So doubled spaces and tabs take up no more room than a single space,
+    /// and every break between words is one space wide. Newlines still end
+    /// paragraphs, as in `wrap`, and aren't collapsed, so blank lines are
+    /// kept. Since the lines no longer match `text` exactly, they're returned
+    /// as new strings.
+    #[cfg(feature = "std")]
+    pub fn wrap_collapsed(&self, text: &str, max_width: usize) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in text.lines() {
+            let paragraph = collapse_whitespace(paragraph);
+            if paragraph.is_empty() {
+                lines.push(paragraph);
+            } else {
+                lines.extend(self.wrap(&paragraph, max_width).map(String::from));
+            }
+        }
+        lines
+    }
+
+    /// Implementation of `wrap`, which also reports whether each line is the
+    /// last in its paragraph.
+    fn wrap_paragraphs<'s>(
+        &'s self,
+        text: &'s str,
+        max_width: usize,
+    ) -> impl Iterator<Item = (&'s str, bool)> + 's {
+        text.lines().flat_map(move |paragraph| {
+            let mut rest = Some(paragraph);
+            core::iter::from_fn(move || {
+                let (line, after) = self.break_line(rest?, max_width);
+                let after = after.trim_start_matches(|c: char| c.is_ascii_whitespace());
+                rest = (!after.is_empty()).then_some(after);
+                Some((line, rest.is_none()))
+            })
+        })
+    }
+
+    /// Splits the longest line that fits in `max_width` off the front of `s`,
+    /// returning it and the remainder, as described for `wrap`.
+    fn break_line<'s>(&self, s: &'s str, max_width: usize) -> (&'s str, &'s str) {
+        if self.width(s) <= max_width {
+            return (s, "");
+        }
+
+        // Try breaking at each whitespace in turn, until the line gets too
+        // long.
+        let mut best = None;
+        for (i, c) in s.char_indices() {
+            if c.is_ascii_whitespace() {
+                let line = s[..i].trim_end_matches(|c: char| c.is_ascii_whitespace());
+                if line.is_empty() {
+                    continue;
+                }
+                if self.width(line) > max_width {
+                    break;
+                }
+                best = Some((line, &s[i..]));
+            }
+        }
+        if let Some(split) = best {
+            return split;
+        }
+
+        // The first word doesn't fit, so break it at the last char that does,
+        // but take at least one char.
+        let mut end = s.chars().next().map_or(0, char::len_utf8);
+        for (i, c) in s.char_indices().skip(1) {
+            if self.width(&s[..i + c.len_utf8()]) > max_width {
+                break;
+            }
+            end = i + c.len_utf8();
+        }
+        s.split_at(end)
+    }
+
+    /// Shortens `s` to fit within `max_width` pixels, marking the cut with
+    /// `ellipsis`.
+    ///
+    /// If `s` already fits, it's returned unchanged. Otherwise, this returns
+    /// the longest prefix of `s` that still fits when followed by `ellipsis`.
+    /// Widths are computed the same way as `width`, so kerning is taken into
+    /// account -- including kerning between the last kept char and the
+    /// ellipsis.
+    ///
+    /// If `max_width` is too small to hold even the ellipsis, this returns as
+    /// much of `s` as fits, with no ellipsis at all.
+    #[cfg(feature = "std")]
+    pub fn truncate_with_ellipsis<'a>(
+        &self,
+        s: &'a str,
+        max_width: usize,
+        ellipsis: char,
+    ) -> std::borrow::Cow<'a, str> {
+        self.truncate_with_ellipsis_str(s, max_width, ellipsis.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Like `truncate_with_ellipsis`, but marks the cut with a string, such
+    /// as `"..."` for fonts without a `'…'` glyph. The last kept char is
+    /// kerned against the first char of `ellipsis`.
+    #[cfg(feature = "std")]
+    pub fn truncate_with_ellipsis_str<'a>(
+        &self,
+        s: &'a str,
+        max_width: usize,
+        ellipsis: &str,
+    ) -> std::borrow::Cow<'a, str> {
+        use std::borrow::Cow;
+
+        if self.width(s) <= max_width {
+            return Cow::Borrowed(s);
+        }
+
+        let ellipsis_width = self.width(ellipsis);
+        let ellipsis_first = ellipsis.chars().next();
+        let mut x = 0_usize;
+        let mut kerning = self.start_kerning();
+        let mut prev = None;
+        // Longest prefix that fits with the ellipsis appended, and without.
+        let mut with_ellipsis = None;
+        let mut bare = 0;
+
+        for cluster in self.clusters(s) {
+            let i = cluster.start;
+            // At this point, `x` is the width of `s[..i]`.
+            if x <= max_width {
+                bare = i;
+            }
+            let mut end = x;
+            if let Some(adjust) = prev.zip(ellipsis_first).and_then(|(p, e)| self.kerning.get(p, e)) {
+                end = kern_usize(adjust, end);
+            }
+            if end.saturating_add(ellipsis_width) <= max_width {
+                with_ellipsis = Some(i);
+            }
+
+            kerning.adjust_usize_for_cluster(&cluster, &mut x);
+            x = x.saturating_add(self.advance_usize(cluster.glyph));
+            prev = Some(cluster.last);
+        }
+
+        match with_ellipsis {
+            Some(i) => {
+                let mut out = String::with_capacity(i + ellipsis.len());
+                out.push_str(&s[..i]);
+                out.push_str(ellipsis);
+                Cow::Owned(out)
+            }
+            None => Cow::Borrowed(&s[..bare]),
+        }
+    }
+
     /// Renders text on a single line.
     ///
     /// The text in `string` will be drawn with its _upper left_ coordinate at
@@ -113,119 +737,1105 @@ impl<'k> Font<'_, '_, 'k> {
     )
         where T: RenderTarget,
     {
-        self.render_core(string, x, y, |gx, gy, glyph, slice| {
-            let height = usize::from(glyph.image_height);
-            let row_bytes = glyph.row_bytes_usize();
-
-            for (y, data) in (gy..gy + height).zip(slice.chunks(row_bytes)) {
-                let mut x = gx;
-                for byte in data {
-                    let mut byte = *byte;
-                    for _ in 0..8 {
-                        if byte & 0x80 != 0 {
-                            target.put_pixel_slow(x, y, fg);
-                        }
-                        byte <<= 1;
-                        x += 1;
-                    }
-                }
+        self.render_styled(string, x, y, target, |_, _| fg);
+    }
+
+    /// Renders text on a single line, like `render`, but with a color chosen
+    /// separately for each glyph.
+    ///
+    /// `fg` is called once per glyph, in order, with the char being drawn and
+    /// its byte offset in `string` (as from `str::char_indices`), and returns
+    /// the color for that glyph. For a ligature, it's called once, with the
+    /// ligature's first char.
+    pub fn render_styled<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        mut fg: impl FnMut(char, usize) -> T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            let color = fg(cluster.first, cluster.start);
+            self.draw_glyph(cluster.glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                paint_slow(target, color, gx, gy, glyph, slice)
+            });
+        });
+    }
+
+    /// Renders part of a line of text, exactly where it would land if the
+    /// whole line were rendered with `render`, for redrawing only the part of
+    /// a line that changed.
+    ///
+    /// `full` is the whole line, starting at `(x, y)`, and only the glyphs
+    /// for chars starting within `range`, a range of byte offsets into `full`,
+    /// are drawn. The chars before `range` still count for positioning,
+    /// kerning included, so the first glyph drawn is kerned against the char
+    /// before it. A ligature is drawn if its first char is in `range`.
+    pub fn render_substring<T>(
+        &self,
+        full: &str,
+        range: core::ops::Range<usize>,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        self.layout_clusters(full, x, |cluster, pen_x| {
+            if range.contains(&cluster.start) {
+                self.draw_glyph(cluster.glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                    paint_slow(target, fg, gx, gy, glyph, slice)
+                });
             }
         });
     }
 
-    /// Renders text on a single line, slightly faster.
+    /// Draws a text cursor for the line `s`, rendered at `(x, y)`, before the
+    /// char at index `char_index`: a 1-pixel-wide bar at `caret_x` from the
+    /// line, covering the font's `ascent + descent` from `y` down.
     ///
-    /// This behaves just like `render` but uses the `DirectRenderTarget` API,
-    /// which lets us make more assumptions about memory layout and winds up
-    /// being slightly cheaper.
+    /// The bar spans the glyphs' box rather than the line spacing, so it's
+    /// as tall as the tallest glyph can reach. In fonts whose line spacing is
+    /// `ascent + descent`, which is most of them, that's the same thing; in
+    /// fonts with extra space between lines, it keeps the cursors on
+    /// neighboring lines from running together.
     ///
-    /// See `render` for more details.
-    pub fn render_direct<T>(
+    /// As with `caret_x`, an index at or past the end of `s` puts the cursor
+    /// after the last char.
+    pub fn render_caret<T>(
         &self,
-        string: &str,
+        s: &str,
+        char_index: usize,
         x: usize,
         y: usize,
         target: &mut T,
         fg: T::Pixel,
     )
-        where T: DirectRenderTarget,
+        where T: RenderTarget,
     {
-        self.render_core(string, x, y, |gx, gy, glyph, slice| {
-            let height = usize::from(glyph.image_height);
-            let row_bytes = glyph.row_bytes_usize();
-
-            for (y, data) in (gy..gy + height).zip(slice.chunks(row_bytes)) {
-                let dest =
-                    target.subrow_mut(y, gx..gx + row_bytes * 8);
-                let mut data = data.iter().cloned();
-                let mut byte = 0;
-                let mut bits_left = 0_usize;
-                for pel in dest {
-                    if let Some(n) = bits_left.checked_sub(1) {
-                        bits_left = n;
-                    } else if let Some(b) = data.next() {
-                        byte = b;
-                        bits_left = 7;
-                    } else {
-                        break;
-                    }
+        let caret_x = x.saturating_add(self.caret_x(s, char_index));
+        let height = usize::from(self.ascent) + usize::from(self.descent);
+        for v in 0..height {
+            target.put_pixel_slow(caret_x, y.saturating_add(v), fg);
+        }
+    }
 
-                    if byte & 0x80 != 0 {
-                        *pel = fg;
-                    }
-                    byte <<= 1;
+    /// Renders text on a single line, like `render`, on an opaque background:
+    /// each glyph's whole cell is filled with `bg` before any ink is drawn.
+    ///
+    /// A glyph's cell is as wide as its advance and as tall as the font's line
+    /// spacing, starting at the glyph's pen position and at `y`, so the cells
+    /// of consecutive chars, and of consecutive lines drawn `line_spacing`
+    /// apart, tile without gaps, as in a terminal. Ink outside a glyph's cell
+    /// is still drawn, over its neighbors' backgrounds.
+    pub fn render_cells<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        bg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        let bottom = y.saturating_add(self.line_spacing_usize());
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            let right = pen_x.saturating_add(self.advance_usize(cluster.glyph));
+            for py in y..bottom {
+                for px in pen_x..right {
+                    target.put_pixel_slow(px, py, bg);
                 }
             }
         });
+        self.render(string, x, y, target, fg);
     }
 
-    /// Implementation factor of both `render` and `render_direct`, exposed here
-    /// in case you're doing something unexpected.
+    /// Renders text on a single line, shrunk by `aa.scale` and anti-aliased,
+    /// blending in linear light for gamma-correct edges.
     ///
-    /// This will call `action` with the X, Y coordinates for each non-empty
-    /// glyph rendered from `string`, starting at the given location, as well as
-    /// the font's `Glyph` for the character and the actual slice of bitmap
-    /// data.
-    pub fn render_core(
+    /// Each pixel drawn stands for an `aa.scale` by `aa.scale` square of the
+    /// font's pixels, and is shaded between `aa.bg` and `fg` by how many of
+    /// them are set. Since `fg` and `aa.bg` are sRGB levels, they're blended
+    /// as linear light rather than averaged directly: a pixel that's half
+    /// covered by white on black comes out at level 188, not a muddy 128.
+    /// This turns a font drawn at two or three times the size it's shown at
+    /// into one with smooth edges.
+    ///
+    /// `(x, y)` is the upper left corner of the shrunk text's line. Pixels
+    /// with no coverage aren't drawn, so `aa.bg` should be what's already in
+    /// `target` behind the text. A `scale` of 0 draws nothing.
+    pub fn render_aa_gamma<T>(
         &self,
         string: &str,
         x: usize,
         y: usize,
-        mut action: impl FnMut(usize, usize, &Glyph, &[u8]),
-    ) {
-        let mut pen_x = x;
-        let mut kerning = self.start_kerning();
-        for c in string.chars() {
-            kerning.adjust_usize_for_char(c, &mut pen_x);
-
-            let glyph = self.get_glyph_or_replacement(c);
+        target: &mut T,
+        fg: u8,
+        aa: Antialias,
+    )
+        where T: RenderTarget<Pixel = u8>,
+    {
+        // Coverage is counted a square tile of target pixels at a time, so
+        // that the counts fit on the stack.
+        const TILE: usize = 16;
+        let scale = usize::from(aa.scale);
+        if scale == 0 {
+            return;
+        }
+        let area = u32::from(aa.scale) * u32::from(aa.scale);
+        let span = TILE * scale;
 
-            if glyph.has_image() {
-                let (gx, gy) = glyph.displace_usize(pen_x, y);
-                action(
-                    gx,
-                    gy,
-                    glyph,
-                    glyph.slice_bitmap(&self.bitmaps),
-                );
+        // The box around every glyph image, in the font's pixels.
+        let mut bounds = None;
+        self.layout_clusters(string, 0, |cluster, pen_x| {
+            let glyph = cluster.glyph;
+            if !glyph.has_image() {
+                return;
             }
+            let (left, top) = glyph.displace_usize(pen_x, 0);
+            let right = left.saturating_add(glyph.width_in_pixels());
+            let bottom = top.saturating_add(usize::from(glyph.image_height));
+            bounds = Some(match bounds {
+                None => (left, top, right, bottom),
+                Some((x0, y0, x1, y1)) => (
+                    usize::min(x0, left),
+                    usize::min(y0, top),
+                    usize::max(x1, right),
+                    usize::max(y1, bottom),
+                ),
+            });
+        });
+        let Some((x0, y0, x1, y1)) = bounds else {
+            return;
+        };
 
-            pen_x += glyph.default_advance_usize();
+        for tile_y in (y0 / scale..y1.div_ceil(scale)).step_by(TILE) {
+            for tile_x in (x0 / scale..x1.div_ceil(scale)).step_by(TILE) {
+                let (left, top) = (tile_x.saturating_mul(scale), tile_y.saturating_mul(scale));
+                let mut counts = [[0u32; TILE]; TILE];
+                self.layout_clusters(string, 0, |cluster, pen_x| {
+                    let glyph = cluster.glyph;
+                    let (gx, gy) = glyph.displace_usize(pen_x, 0);
+                    let right = gx.saturating_add(glyph.width_in_pixels());
+                    let bottom = gy.saturating_add(usize::from(glyph.image_height));
+                    if right <= left || bottom <= top
+                        || gx >= left.saturating_add(span) || gy >= top.saturating_add(span)
+                    {
+                        return;
+                    }
+                    for (px, py, set) in glyph.pixels(self.bitmaps, self.bitmap_encoding) {
+                        let cx = gx.saturating_add(px).checked_sub(left).map(|d| d / scale);
+                        let cy = gy.saturating_add(py).checked_sub(top).map(|d| d / scale);
+                        if let (true, Some(cx @ 0..TILE), Some(cy @ 0..TILE)) = (set, cx, cy) {
+                            counts[cy][cx] += 1;
+                        }
+                    }
+                });
+                for (cy, row) in counts.iter().enumerate() {
+                    for (cx, &n) in row.iter().enumerate().filter(|&(_, &n)| n != 0) {
+                        // Kerning can overlap glyphs, and so count pixels twice.
+                        let level = gamma::blend(aa.bg, fg, n.min(area), area);
+                        target.put_pixel_slow(
+                            x.saturating_add(tile_x + cx),
+                            y.saturating_add(tile_y + cy),
+                            level,
+                        );
+                    }
+                }
+            }
         }
     }
 
-    /// Returns a `KerningState` ready to being kerning characters. This is
-    /// appropriate for use at the beginning of a line.
-    pub fn start_kerning(&self) -> KerningState<'k> {
-        KerningState {
-            table: self.kerning,
-            last_char: None,
-        }
+    /// Renders text on a single line, like `render`, and counts the glyphs
+    /// that needed drawing, for finding out how much work is going to waste.
+    ///
+    /// `size` is the `(width, height)` of `target`, in pixels. Glyphs whose
+    /// images fall entirely outside it aren't drawn at all, and are counted as
+    /// `clipped`; glyphs that are only partly inside are drawn, and counted as
+    /// `drawn`.
+    pub fn render_counted<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        size: (usize, usize),
+    ) -> RenderStats
+        where T: RenderTarget,
+    {
+        let mut stats = RenderStats::default();
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            let glyph = cluster.glyph;
+            if !glyph.has_image() {
+                stats.blank += 1;
+                return;
+            }
+            let (gx, gy) = glyph.displace_usize(pen_x, y);
+            if gx >= size.0 || gy >= size.1 {
+                stats.clipped += 1;
+                return;
+            }
+            stats.drawn += 1;
+            self.draw_glyph(glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                paint_slow(target, fg, gx, gy, glyph, slice)
+            });
+        });
+        stats
     }
-}
 
-/// Storage for the set of glyphs that make up a font.
-#[derive(Copy, Clone, Debug)]
+    /// Renders text on a single line, like `render`, but combining `fg` with
+    /// the pixels already in `target` according to `mode`.
+    ///
+    /// Drawing the same text twice in `BlendMode::Xor` puts `target` back the
+    /// way it was, which is handy for cursors and selection highlights. Where
+    /// kerning makes two glyphs' ink overlap, though, each of them is blended
+    /// in turn, so in `Xor` mode the overlap cancels out.
+    ///
+    /// Targets that can't read back their pixels replace them instead; see
+    /// `RenderTarget::put_pixel_blended`.
+    pub fn render_blended<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        mode: BlendMode,
+    )
+        where T: RenderTarget,
+              T::Pixel: BlendPixel,
+    {
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            self.draw_glyph(cluster.glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                let width = glyph.width_in_pixels();
+                let rows = (gy..).zip(slice.chunks(glyph.row_bytes_usize()));
+                for (py, row) in rows {
+                    for (px, set) in (gx..).zip(row_pixels(row, width)) {
+                        if set {
+                            target.put_pixel_blended(px, py, fg, mode);
+                        }
+                    }
+                }
+            });
+        });
+    }
+
+    /// Renders text on a single line, like `render`, with a one pixel outline
+    /// of color `outline` around each glyph.
+    ///
+    /// The outline makes the text's bounding box one pixel larger on every
+    /// side: it's `width(string) + 2` pixels wide and `line_spacing + 2` tall,
+    /// with its upper left at `(x, y)`. The glyphs themselves are drawn at
+    /// `(x + 1, y + 1)`.
+    ///
+    /// Where one glyph's outline overlaps a neighboring glyph, the glyph wins,
+    /// since all outlines are drawn before any glyphs.
+    pub fn render_outlined<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        outline: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        // Stamp each glyph at all eight one-pixel offsets from where it'll be
+        // drawn, which is (1, 1) from here.
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    if (dx, dy) != (1, 1) {
+                        let (ox, oy) = (gx.saturating_add(dx), gy.saturating_add(dy));
+                        paint_slow(target, outline, ox, oy, glyph, slice);
+                    }
+                }
+            }
+        });
+        let (x, y) = (x.saturating_add(1), y.saturating_add(1));
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            paint_slow(target, fg, gx, gy, glyph, slice)
+        });
+    }
+
+    /// Renders text on a single line, like `render`, over a drop shadow
+    /// described by `shadow`.
+    ///
+    /// Coordinates can't be negative, so if the shadow would start left of or
+    /// above `(x, y)` by more than `x` or `y`, the text and shadow are both
+    /// shifted right or down just enough for the shadow to start at zero.
+    pub fn render_shadow<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        shadow: Shadow<T::Pixel>,
+    )
+        where T: RenderTarget,
+    {
+        let Shadow { color: shadow, offset } = shadow;
+        // Returns the positions of the text and shadow along one axis.
+        fn place(pos: usize, offset: i8) -> (usize, usize) {
+            let distance = usize::from(offset.unsigned_abs());
+            if offset < 0 {
+                let pos = pos.max(distance);
+                (pos, pos - distance)
+            } else {
+                (pos, pos.saturating_add(distance))
+            }
+        }
+        let (x, shadow_x) = place(x, offset.0);
+        let (y, shadow_y) = place(y, offset.1);
+        self.render_core(string, shadow_x, shadow_y, |gx, gy, glyph, slice| {
+            paint_slow(target, shadow, gx, gy, glyph, slice)
+        });
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            paint_slow(target, fg, gx, gy, glyph, slice)
+        });
+    }
+
+    /// Computes the width of `s`, like `width`, but handling chars that have
+    /// no glyph in this font according to `missing` instead of always using
+    /// the replacement glyph.
+    pub fn width_with_missing(&self, s: &str, missing: MissingPolicy) -> usize {
+        self.layout_clusters_with(s, 0, missing, |_, _| ())
+    }
+
+    /// Renders text on a single line, like `render`, but handling chars that
+    /// have no glyph in this font according to `missing` instead of always
+    /// drawing the replacement glyph. The result is `width_with_missing` wide.
+    pub fn render_with_missing<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        missing: MissingPolicy,
+    )
+        where T: RenderTarget,
+    {
+        self.layout_clusters_with(string, x, missing, |cluster, pen_x| {
+            self.draw_glyph(cluster.glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                paint_slow(target, fg, gx, gy, glyph, slice)
+            });
+        });
+    }
+
+    /// Renders text on a single line, like `render`, but with its starting
+    /// X position `x_subpixel` given in units of `1 / SUBPIXELS` of a pixel.
+    ///
+    /// Each glyph is drawn at the whole pixel nearest to its exact position,
+    /// which is kept in subpixel units throughout, so stepping `x_subpixel`
+    /// one unit at a time moves the text smoothly without the glyphs drifting
+    /// apart or together. Halfway positions round to the right.
+    pub fn render_offset<T>(
+        &self,
+        string: &str,
+        x_subpixel: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        self.layout_clusters(string, 0, |cluster, pen_x| {
+            let exact = x_subpixel.saturating_add(pen_x.saturating_mul(SUBPIXELS));
+            let x = exact.saturating_add(SUBPIXELS / 2) / SUBPIXELS;
+            self.draw_glyph(cluster.glyph, x, y, &mut |gx, gy, glyph, slice| {
+                paint_slow(target, fg, gx, gy, glyph, slice)
+            });
+        });
+    }
+
+    /// Renders text on a single line, like `render`, up to pen position
+    /// `stop_x`, for revealing text a bit at a time. Returns the number of
+    /// chars drawn.
+    ///
+    /// Glyphs are drawn in order until one would start at or past `stop_x`;
+    /// that glyph and all the ones after it are left out. Any ink at or past
+    /// `stop_x`, such as from a glyph straddling it, is clipped off, so
+    /// nothing is drawn there. The chars of a ligature are drawn, and counted,
+    /// together.
+    pub fn render_until<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        stop_x: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    ) -> usize
+        where T: RenderTarget,
+    {
+        let mut target = ClipRight { target, stop_x };
+        let mut drawn = 0;
+        let mut stopped = false;
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            // Negative kerning could bring the pen back before `stop_x`, but
+            // once we've stopped, we stay stopped.
+            stopped |= pen_x >= stop_x;
+            if stopped {
+                return;
+            }
+            drawn += cluster.chars;
+            self.draw_glyph(cluster.glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                paint_slow(&mut target, fg, gx, gy, glyph, slice)
+            });
+        });
+        drawn
+    }
+
+    /// Renders text on a single line with its baseline at `baseline`, rather
+    /// than with the top of its bounding box at a given `y` as in `render`.
+    ///
+    /// If `baseline` is less than the font's `ascent`, the bounding box would
+    /// start above the top of the target. Rather than failing like
+    /// `baseline_to_y`, this clips off the parts of glyphs that would fall
+    /// above row 0.
+    pub fn render_baseline<T>(
+        &self,
+        string: &str,
+        x: usize,
+        baseline: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        let ascent = usize::from(self.ascent);
+        // `clip` is how far above row 0 the bounding box starts.
+        let (y, clip) = match baseline.checked_sub(ascent) {
+            Some(y) => (y, 0),
+            None => (0, ascent - baseline),
+        };
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            let skip = clip.saturating_sub(gy);
+            let slice = slice.get(skip * glyph.row_bytes_usize()..).unwrap_or(&[]);
+            paint_slow(target, fg, gx, gy + skip - clip, glyph, slice)
+        });
+    }
+
+    /// Renders `text` as a block of lines justified to exactly `justify.width`
+    /// pixels, with the top of the first line's bounding box at `y`.
+    ///
+    /// `text` is broken into lines as by `wrap`. Except for the last line of
+    /// each paragraph, and lines holding a single word, each line is stretched
+    /// to fill the width by spreading the leftover space across the gaps between
+    /// its words. Space that doesn't divide evenly goes to the leftmost gaps,
+    /// one extra pixel each. The lines that aren't stretched are drawn flush
+    /// left.
+    ///
+    /// Lines are spaced as in `lines_layout`: by `justify.line_spacing` if
+    /// it's given, and by the font's line spacing otherwise.
+    pub fn render_justified<T>(
+        &self,
+        text: &str,
+        x: usize,
+        y: usize,
+        justify: Justify,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        let Justify { width, line_spacing } = justify;
+        let spacing = self.spacing_or_default(line_spacing);
+        let lines = self.wrap_paragraphs(text, width).enumerate();
+        for (i, (line, last)) in lines {
+            let line_y = y.saturating_add(i.saturating_mul(spacing));
+            let gaps = words(line).count().saturating_sub(1);
+            if last || gaps == 0 {
+                self.render(line, x, line_y, target, fg);
+                continue;
+            }
+
+            // Each word goes where it would naturally fall, plus the extra
+            // space given to the gaps before it.
+            let slack = width.saturating_sub(self.width(line));
+            for (gap, (start, word)) in words(line).enumerate() {
+                let extra = gap * (slack / gaps) + usize::min(gap, slack % gaps);
+                let word_x = x
+                    .saturating_add(self.width(&line[..start]))
+                    .saturating_add(extra);
+                self.render(word, word_x, line_y, target, fg);
+            }
+        }
+    }
+
+    /// Wraps `text` to `paragraph.max_width` pixels as `wrap` does, and
+    /// renders the lines one below the other, with the upper left of the first
+    /// at `(x, y)`. Lines are `paragraph.line_spacing` apart, or the font's
+    /// line spacing if that's `None`.
+    ///
+    /// Returns the height of the rendered block, as `measure` would compute it
+    /// for the wrapped lines, or 0 if there were none.
+    ///
+    /// As in `wrap`, every line holds at least one char, so a `max_width`
+    /// narrower than any glyph puts each char on a line of its own.
+    pub fn render_paragraph<T>(
+        &self,
+        text: &str,
+        x: usize,
+        y: usize,
+        paragraph: Paragraph,
+        target: &mut T,
+        fg: T::Pixel,
+    ) -> usize
+        where T: RenderTarget,
+    {
+        let spacing = self.spacing_or_default(paragraph.line_spacing);
+        let mut lines = 0_usize;
+        for line in self.wrap(text, paragraph.max_width) {
+            let line_y = y.saturating_add(lines.saturating_mul(spacing));
+            self.render(line, x, line_y, target, fg);
+            lines += 1;
+        }
+        self.block_height(lines, spacing)
+    }
+
+    /// Renders text on a single line, like `render`, but mirrored.
+    ///
+    /// With `flip.x`, the whole string is mirrored horizontally across the
+    /// span it would normally cover, from `x` to `x + self.width(string)`:
+    /// glyphs run right to left, and each is drawn reversed. Ink that would
+    /// normally hang past the end of the string lands before `x` instead, and
+    /// is clipped if that would put it at a negative coordinate.
+    ///
+    /// With `flip.y`, each glyph is drawn upside down within its own image, so
+    /// glyphs stay at the same height on the line.
+    pub fn render_flipped<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+        flip: Flip,
+    )
+        where T: RenderTarget,
+    {
+        // When flipping horizontally, column `px` is drawn at `mirror - px`.
+        let mirror = x.saturating_add(x)
+            .saturating_add(self.width(string))
+            .saturating_sub(1);
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            let glyph = cluster.glyph;
+            let (_, top) = glyph.displace_usize(pen_x, y);
+            let bottom = top.saturating_add(usize::from(glyph.image_height));
+            self.draw_glyph(glyph, pen_x, y, &mut |gx, gy, glyph, slice| {
+                let width = glyph.width_in_pixels();
+                let rows = (gy..).zip(slice.chunks(glyph.row_bytes_usize()));
+                for (py, row) in rows {
+                    let py = if flip.y { bottom - 1 - (py - top) } else { py };
+                    for (px, set) in (gx..).zip(row_pixels(row, width)) {
+                        if !set {
+                            continue;
+                        }
+                        let px = if flip.x {
+                            let Some(px) = mirror.checked_sub(px) else {
+                                continue;
+                            };
+                            px
+                        } else {
+                            px
+                        };
+                        target.put_pixel_slow(px, py, fg);
+                    }
+                }
+            });
+        });
+    }
+
+    /// Renders text on a single line, rotated by a multiple of 90 degrees.
+    ///
+    /// `(x, y)` is the top left corner of the rotated text's bounding box,
+    /// whatever the rotation, since coordinates can't be negative; see
+    /// `rotated_extent` for its size. Unrotated, that box is `self.width(s)`
+    /// wide and `ascent + descent` tall. So, for instance, text rotated
+    /// `Cw90` reads downward with the tops of the glyphs facing right, and
+    /// its first glyph is at the top right of the box.
+    ///
+    /// Ink that falls outside the bounding box, such as a glyph that overhangs
+    /// the end of the string, is clipped if it would land at a negative
+    /// coordinate.
+    pub fn render_rotated<T>(
+        &self,
+        s: &str,
+        x: usize,
+        y: usize,
+        rotation: Rotation,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        let width = self.width(s);
+        let height = usize::from(self.ascent) + usize::from(self.descent);
+        // Maps a pixel of the unrotated text, drawn at the origin, to its
+        // rotated position relative to `(x, y)`.
+        let flip_u = |u: usize| width.checked_sub(1)?.checked_sub(u);
+        let flip_v = |v: usize| height.checked_sub(1)?.checked_sub(v);
+        let rotate = |u: usize, v: usize| match rotation {
+            Rotation::None => Some((u, v)),
+            Rotation::Cw90 => Some((flip_v(v)?, u)),
+            Rotation::Ccw90 => Some((v, flip_u(u)?)),
+            Rotation::Rot180 => Some((flip_u(u)?, flip_v(v)?)),
+        };
+        self.render_core(s, 0, 0, |gx, gy, glyph, slice| {
+            let width = glyph.width_in_pixels();
+            let rows = (gy..).zip(slice.chunks(glyph.row_bytes_usize()));
+            for (v, row) in rows {
+                for (u, set) in (gx..).zip(row_pixels(row, width)) {
+                    if let Some((rx, ry)) = rotate(u, v).filter(|_| set) {
+                        target.put_pixel_slow(
+                            x.saturating_add(rx),
+                            y.saturating_add(ry),
+                            fg,
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// Renders text on a single line, rotated clockwise by any angle, given in
+    /// `turn.radians`, by nearest-neighbor sampling.
+    ///
+    /// The text is placed as for `render_rotated`, with the top left corner of
+    /// its unrotated box -- `self.width(s)` wide and `ascent + descent` tall --
+    /// at `(x, y)`, and then turned about `turn.pivot`, a point given relative
+    /// to that corner, which stays put. A pivot of
+    /// `(0., f32::from(self.ascent))` turns the text about the left end of its
+    /// baseline, for instance, and one at the middle of the box turns it in
+    /// place. Ink rotated to a negative coordinate is clipped.
+    ///
+    /// To match one of `render_rotated`'s quarter turns, which keep the top
+    /// left corner of the rotated box at `(x, y)`, pivot on the point that
+    /// stays put under the turn: for `Cw90`, where the box is `height` tall,
+    /// that's `(height / 2., height / 2.)`.
+    ///
+    /// Bitmap text doesn't survive this well at angles off the axes: strokes
+    /// come out ragged, and thin ones can break up. For multiples of 90
+    /// degrees, `render_rotated` is exact, and cheaper.
+    ///
+    /// This draws the text into a scratch buffer first, so it's only
+    /// available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn render_rotated_arbitrary<T>(
+        &self,
+        s: &str,
+        x: usize,
+        y: usize,
+        turn: Turn,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        let width = self.width(s);
+        let height = usize::from(self.ascent) + usize::from(self.descent);
+        let mut scratch = vec![false; width.saturating_mul(height)];
+        let mut block = SliceTarget::new(&mut scratch, width, width);
+        self.render(s, 0, 0, &mut block, true);
+
+        let (sin, cos) = turn.radians.sin_cos();
+        let (w, h) = (width as f32, height as f32);
+        let (pu, pv) = turn.pivot;
+        // Where the pivot lands in the target.
+        let (px, py) = (x as f32 + pu, y as f32 + pv);
+        // The rotated box's corners, relative to the pivot, bound the pixels
+        // that can be drawn.
+        let corners = [(0., 0.), (w, 0.), (0., h), (w, h)].map(|(u, v): (f32, f32)| {
+            let (du, dv) = (u - pu, v - pv);
+            (du * cos - dv * sin, du * sin + dv * cos)
+        });
+        let span = |pick: fn(&(f32, f32)) -> f32, origin: f32| {
+            let lo = corners.iter().map(pick).fold(f32::INFINITY, f32::min);
+            let hi = corners.iter().map(pick).fold(f32::NEG_INFINITY, f32::max);
+            // Casts saturate, which clips anything left of or above zero.
+            (origin + lo).floor() as usize..(origin + hi).ceil() as usize
+        };
+        let (xs, ys) = (span(|c| c.0, px), span(|c| c.1, py));
+        // Each pixel of the rotated box is mapped back through the opposite
+        // rotation to the pixel of `scratch` it samples.
+        for ty in ys {
+            for tx in xs.clone() {
+                let (dx, dy) = (tx as f32 + 0.5 - px, ty as f32 + 0.5 - py);
+                let u = (dx * cos + dy * sin + pu).floor();
+                let v = (dy * cos - dx * sin + pv).floor();
+                if u < 0. || v < 0. || u >= w || v >= h {
+                    continue;
+                }
+                if scratch[v as usize * width + u as usize] {
+                    target.put_pixel_slow(tx, ty, fg);
+                }
+            }
+        }
+    }
+
+    /// Computes the size of the bounding box of `s` rendered with
+    /// `render_rotated`, as `(width, height)` in pixels.
+    pub fn rotated_extent(&self, s: &str, rotation: Rotation) -> (usize, usize) {
+        let width = self.width(s);
+        let height = usize::from(self.ascent) + usize::from(self.descent);
+        match rotation {
+            Rotation::None | Rotation::Rot180 => (width, height),
+            Rotation::Cw90 | Rotation::Ccw90 => (height, width),
+        }
+    }
+
+    /// Renders text on a single line, slightly faster.
+    ///
+    /// This behaves just like `render` but uses the `DirectRenderTarget` API,
+    /// which lets us make more assumptions about memory layout and winds up
+    /// being slightly cheaper.
+    ///
+    /// See `render` for more details.
+    pub fn render_direct<T>(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: DirectRenderTarget,
+    {
+        self.render_core(string, x, y, |gx, gy, glyph, slice| {
+            paint_direct(target, fg, gx, gy, glyph, slice)
+        });
+    }
+
+    /// Implementation factor of both `render` and `render_direct`, exposed here
+    /// in case you're doing something unexpected.
+    ///
+    /// This will call `action` with the X, Y coordinates for each non-empty
+    /// glyph rendered from `string`, starting at the given location, as well as
+    /// the font's `Glyph` for the character and the actual slice of bitmap
+    /// data.
+    ///
+    /// If the font's bitmaps are compressed, each glyph's image is first
+    /// decompressed into a buffer on the stack, which holds any glyph up to 64
+    /// pixels wide (as wide as the loaders make them). Wider compressed
+    /// glyphs, which only hand-built fonts can have, are passed to `action` in
+    /// several calls, each with a consecutive band of the glyph's rows; the Y
+    /// coordinate is that of the band's first row, and the slice contains only
+    /// the band's rows. Code that iterates over the rows in the slice, rather
+    /// than over `image_height`, will handle both cases.
+    pub fn render_core(
+        &self,
+        string: &str,
+        x: usize,
+        y: usize,
+        mut action: impl FnMut(usize, usize, &Glyph, &[u8]),
+    ) {
+        self.layout_clusters(string, x, |cluster, pen_x| {
+            self.draw_glyph(cluster.glyph, pen_x, y, &mut action);
+        });
+    }
+
+    /// Walks the glyphs used to draw `string` starting with the pen at `x`,
+    /// calling `each` with each cluster and its pen position after kerning,
+    /// and returns the final pen position.
+    fn layout_clusters(
+        &self,
+        string: &str,
+        x: usize,
+        each: impl FnMut(&Cluster<'_>, usize),
+    ) -> usize {
+        self.layout_clusters_with(string, x, MissingPolicy::Replacement, each)
+    }
+
+    /// Like `layout_clusters`, but handling chars that have no glyph according
+    /// to `missing`.
+    fn layout_clusters_with(
+        &self,
+        string: &str,
+        x: usize,
+        missing: MissingPolicy,
+        mut each: impl FnMut(&Cluster<'_>, usize),
+    ) -> usize {
+        let mut pen_x = x;
+        let mut kerning = self.start_kerning();
+        for cluster in self.clusters(string) {
+            let space;
+            let cluster = match missing {
+                MissingPolicy::Skip if cluster.missing => continue,
+                MissingPolicy::Space(advance) if cluster.missing => {
+                    space = Glyph::blank(advance);
+                    Cluster { glyph: &space, ..cluster }
+                }
+                _ => cluster,
+            };
+            kerning.adjust_usize_for_cluster(&cluster, &mut pen_x);
+
+            each(&cluster, pen_x);
+
+            pen_x = pen_x.saturating_add(self.advance_usize(cluster.glyph));
+        }
+        pen_x
+    }
+
+    /// Calls `action` as described for `render_core` for a single glyph, with
+    /// its bounding box's upper left corner at `(x, y)`.
+    fn draw_glyph(
+        &self,
+        glyph: &Glyph,
+        x: usize,
+        y: usize,
+        action: &mut impl FnMut(usize, usize, &Glyph, &[u8]),
+    ) {
+        if glyph.has_image() {
+            let (gx, gy) = glyph.displace_usize(x, y);
+            // Glyphs whose far edge can't even be represented are certainly
+            // off any target, and drawing them would overflow.
+            let height = usize::from(glyph.image_height);
+            if gx.checked_add(glyph.width_in_pixels()).is_none()
+                || gy.checked_add(height).is_none()
+            {
+                return;
+            }
+            match self.bitmap_encoding {
+                BitmapEncoding::Raw => action(
+                    gx,
+                    gy,
+                    glyph,
+                    glyph.slice_bitmap(self.bitmaps),
+                ),
+                BitmapEncoding::PackBits => {
+                    self.decode_bands(glyph, |row, band| {
+                        action(gx, gy + row, glyph, band)
+                    });
+                }
+            }
+        }
+    }
+
+    /// Decompresses `glyph`'s image into bands of rows that fit into a stack
+    /// buffer, calling `action` with the index of the first row in each band
+    /// and the band's data. Glyphs up to 64 pixels wide fit in one band.
+    fn decode_bands(&self, glyph: &Glyph, mut action: impl FnMut(usize, &[u8])) {
+        const BUFFER_LEN: usize = 8 * u8::MAX as usize;
+        let mut buffer = [0; BUFFER_LEN];
+
+        let row_bytes = glyph.row_bytes_usize();
+        let height = usize::from(glyph.image_height);
+        // row_bytes is a u8, so this is always at least 1.
+        let rows_per_band = BUFFER_LEN / row_bytes;
+        let mut bytes = glyph.bitmap_bytes(self.bitmaps, self.bitmap_encoding);
+
+        let mut row = 0;
+        while row < height {
+            let rows = usize::min(rows_per_band, height - row);
+            let band = &mut buffer[..rows * row_bytes];
+            let mut filled = 0;
+            for (dest, b) in band.iter_mut().zip(&mut bytes) {
+                *dest = b;
+                filled += 1;
+            }
+            action(row, &band[..filled]);
+            if filled < band.len() {
+                // Data ran out early; the font is damaged, but there's no
+                // point in drawing empty bands.
+                break;
+            }
+            row += rows;
+        }
+    }
+
+    /// Looks up the kerning adjustment between `before` and `after`, if the
+    /// font has one. This is the same adjustment rendering applies.
+    pub fn kern(&self, before: char, after: char) -> Option<i8> {
+        self.kerning.get(before, after)
+    }
+
+    /// Iterates over the font's kerning table, as `((before, after), adjust)`.
+    pub fn kerning_pairs(&self) -> impl Iterator<Item = ((char, char), i8)> + 'k {
+        self.kerning.pairs()
+    }
+
+    /// Returns a `KerningState` ready to being kerning characters. This is
+    /// appropriate for use at the beginning of a line.
+    pub fn start_kerning(&self) -> KerningState<'k> {
+        KerningState::new(self.kerning)
+    }
+
+    /// Returns a `PenState` for drawing text one char at a time with
+    /// `render_char`, with the upper left of the first line at `(x, y)`.
+    pub fn start_pen(&self, x: usize, y: usize) -> PenState<'k> {
+        PenState {
+            x,
+            y,
+            left: x,
+            kerning: self.start_kerning(),
+        }
+    }
+
+    /// Draws the single char `c` at `pen`'s position, like `render`, and moves
+    /// the pen past it.
+    ///
+    /// Kerning is carried in `pen` between calls, so drawing a string one char
+    /// at a time puts the glyphs in the same places as `render`. Ligatures
+    /// aren't, since they'd need to see chars that haven't been given yet.
+    ///
+    /// A `'\n'` isn't drawn. Instead, it moves the pen to the start of the
+    /// next line: back to the X coordinate given to `start_pen`, and down by
+    /// the font's line spacing.
+    pub fn render_char<T>(
+        &self,
+        pen: &mut PenState<'k>,
+        c: char,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        if c == '\n' {
+            pen.x = pen.left;
+            pen.y = pen.y.saturating_add(self.line_spacing_usize());
+            pen.kerning = self.start_kerning();
+            return;
+        }
+        let glyph = self.get_glyph_or_replacement(c);
+        pen.kerning.adjust_usize_for_char(c, &mut pen.x);
+        self.draw_glyph(glyph, pen.x, pen.y, &mut |gx, gy, glyph, slice| {
+            paint_slow(target, fg, gx, gy, glyph, slice)
+        });
+        pen.x = pen.x.saturating_add(self.advance_usize(glyph));
+    }
+
+    /// Draws the glyph at `index` in the font's glyph storage, as from
+    /// `GlyphStorage::index_of`, with its bounding box's upper left corner at
+    /// `(x, y)`. This is for fonts used as sprite sheets, where glyphs are
+    /// picked by number rather than by char.
+    ///
+    /// Returns `false`, drawing nothing, if there's no glyph at `index`.
+    pub fn render_glyph_index<T>(
+        &self,
+        index: usize,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    ) -> bool
+        where T: RenderTarget,
+    {
+        match self.glyph_storage.get_by_index(index) {
+            Some(glyph) => {
+                self.render_glyph(glyph, x, y, target, fg);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draws `glyph`, with its bounding box's upper left corner at `(x, y)`,
+    /// just as `render` would draw it for its char there. There's no kerning
+    /// or ligature handling, since there are no neighboring chars.
+    ///
+    /// `glyph` should belong to this font, since its image is found in the
+    /// font's bitmaps.
+    pub fn render_glyph<T>(
+        &self,
+        glyph: &Glyph,
+        x: usize,
+        y: usize,
+        target: &mut T,
+        fg: T::Pixel,
+    )
+        where T: RenderTarget,
+    {
+        self.draw_glyph(glyph, x, y, &mut |gx, gy, glyph, slice| {
+            paint_slow(target, fg, gx, gy, glyph, slice)
+        });
+    }
+}
+
+/// Number of subpixel steps per pixel, for `Font::width_subpixel` and
+/// `Font::render_offset`.
+pub const SUBPIXELS: usize = 8;
+
+/// Splits `line` into words separated by ASCII whitespace, returning each
+/// word's byte offset along with its text.
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    let mut pos = 0;
+    core::iter::from_fn(move || {
+        let start = pos + line[pos..].find(|c: char| !c.is_ascii_whitespace())?;
+        pos = line[start..].find(|c: char| c.is_ascii_whitespace())
+            .map_or(line.len(), |len| start + len);
+        Some((start, &line[start..pos]))
+    })
+}
+
+/// Replaces each run of ASCII whitespace in `line` with a single space.
+#[cfg(feature = "std")]
+fn collapse_whitespace(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_space = false;
+    for c in line.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// Where the next char goes when drawing text incrementally with
+/// `Font::render_char`. Create one with `Font::start_pen`.
+#[derive(Clone, Debug)]
+pub struct PenState<'k> {
+    /// X coordinate of the upper left of the next glyph's bounding box,
+    /// before kerning.
+    pub x: usize,
+    /// Y coordinate of the top of the current line's bounding box.
+    pub y: usize,
+    /// X coordinate that newlines return to.
+    left: usize,
+    kerning: KerningState<'k>,
+}
+
+/// Counts of the glyphs in some text, from `Font::render_counted`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RenderStats {
+    /// Glyphs that were drawn into the target, at least in part.
+    pub drawn: usize,
+    /// Glyphs with no image, such as spaces, which had nothing to draw.
+    pub blank: usize,
+    /// Glyphs that were skipped for being entirely outside the target.
+    pub clipped: usize,
+}
+
+/// A line of text laid out by `Font::lines_layout`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LineInfo<'s> {
+    /// The line's text, without its line ending.
+    pub text: &'s str,
+    /// Width of the line in pixels, as computed by `Font::width`.
+    pub width: usize,
+    /// Offset of the top of the line's bounding box from the top of the first
+    /// line's.
+    pub y: usize,
+}
+
+/// Storage for the set of glyphs that make up a font.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum GlyphStorage<'g> {
     /// The font provides a set of glyphs for a contiguous range of characters
     /// in ISO8859-1.
@@ -237,7 +1847,10 @@ pub enum GlyphStorage<'g> {
         /// Glyph data for a consecutive sequence of codepoints starting at
         /// `first`.
         ///
-        /// In practice, this should be no longer than `256 - first` entries.
+        /// This covers exactly the chars from `first` up to, but not
+        /// including, `first + glyphs.len()`. That range can extend past
+        /// 255, for encodings that continue beyond ISO8859-1, but never wraps
+        /// around: chars below `first` are never found here.
         glyphs: &'g [Glyph],
     },
     /// The font provides a set of glyphs for an arbitrary set of Unicode
@@ -245,209 +1858,2644 @@ pub enum GlyphStorage<'g> {
     Sparse {
         sorted_glyphs: &'g [(char, Glyph)],
     },
+    /// The font provides glyphs for runs of consecutive Unicode codepoints,
+    /// such as a few Unicode blocks. This is more compact than `Sparse` when
+    /// the runs are long, since the codepoints of glyphs within a run aren't
+    /// stored.
+    Ranges {
+        /// Runs of codepoints, as `(start, len, offset)`: the run covers `len`
+        /// codepoints starting at `start`, whose glyphs are found starting at
+        /// `glyphs[offset]`.
+        ///
+        /// Runs must be sorted by `start` and must not overlap, so they can be
+        /// binary searched.
+        ranges: &'g [(char, u16, u16)],
+        /// Glyph data for all runs.
+        glyphs: &'g [Glyph],
+    },
+}
+
+impl GlyphStorage<'_> {
+    /// Looks up a `char` in glyph storage. Returns `None` if the `char` is not
+    /// explicitly represented in storage.
+    pub fn get(&self, c: char) -> Option<&Glyph> {
+        self.get_by_index(self.index_of(c)?)
+    }
+
+    /// Finds the glyph index of `c`, for use with `get_by_index`. Returns
+    /// `None` if the `char` is not explicitly represented in storage.
+    pub fn index_of(&self, c: char) -> Option<usize> {
+        match self {
+            Self::Dense { first, glyphs } => {
+                let i = u32::from(c).checked_sub(u32::from(*first))?;
+                let i = usize::try_from(i).ok()?;
+                (i < glyphs.len()).then_some(i)
+            },
+            Self::Sparse { sorted_glyphs } => {
+                sorted_glyphs.binary_search_by_key(&c, |(cp, _)| *cp).ok()
+            },
+            Self::Ranges { ranges, glyphs } => {
+                let c = u32::from(c);
+                // Find the last run starting at or before `c`, which is the
+                // only one that could contain it.
+                let i = ranges.partition_point(|&(start, _, _)| u32::from(start) <= c)
+                    .checked_sub(1)?;
+                let (start, len, offset) = ranges[i];
+                let delta = c - u32::from(start);
+                let index = usize::from(offset) + delta as usize;
+                (delta < u32::from(len) && index < glyphs.len()).then_some(index)
+            },
+        }
+    }
+
+    /// Looks up a glyph by glyph _index,_ which is mostly only used during
+    /// replacement glyph processing, but maybe you've got ideas.
+    ///
+    /// This is a `const fn`, so that glyphs of a font defined as a constant
+    /// can be looked at in other constants.
+    pub const fn get_by_index(&self, index: usize) -> Option<&Glyph> {
+        // Slices' `get` isn't usable in a `const fn`, so bounds are checked by
+        // hand.
+        match self {
+            Self::Dense { glyphs, .. } | Self::Ranges { glyphs, .. } => {
+                if index < glyphs.len() { Some(&glyphs[index]) } else { None }
+            },
+            Self::Sparse { sorted_glyphs, .. } => {
+                if index < sorted_glyphs.len() { Some(&sorted_glyphs[index].1) } else { None }
+            },
+        }
+    }
+}
+
+/// Data for a single glyph in a font.
+///
+/// This is `repr(C)` so that tables of glyphs can be borrowed directly out of
+/// the binary font format; see the `binary` module.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Glyph {
+    /// Width of each pixel row in `image`, measured in bytes, or units of 8
+    /// pixels.
+    pub row_bytes: u8,
+    /// Width of the glyph's image in pixels, if it doesn't fill the last byte
+    /// of each row. Pixels past this width are padding and are never drawn.
+    /// Zero means the image is the full `row_bytes * 8` pixels wide.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pixel_width: u8,
+    /// Image data slice position in the font's image data array.
+    pub image_offset: u16,
+    /// Number of rows of pixels in this glyph's image.
+    pub image_height: u8,
+    /// Displacement in (X, Y) from the top-left of the glyph's bounding box to
+    /// the top-left pixel in `image`. This allows an image to be smaller than
+    /// the bounding box, e.g. omitting data above a lowercase letter.
+    pub origin: (u8, u8),
+    /// Default X advance from the left side of this glyph's bounding box to the
+    /// left side of the next glyph. This can be overridden by kerning
+    /// information.
+    pub advance: u8,
+}
+
+impl Glyph {
+    /// A blank glyph with no image and no advance. `has_image` returns
+    /// `false` for it, and drawing it draws nothing.
+    pub const EMPTY: Glyph = Glyph::blank(0);
+
+    /// Makes a blank glyph, like `EMPTY`, that advances the pen by `advance`
+    /// pixels: a space, in other words. This can be used in `const` and
+    /// `static` items, such as hand-written glyph tables.
+    pub const fn blank(advance: u8) -> Self {
+        Self {
+            row_bytes: 0,
+            pixel_width: 0,
+            image_offset: 0,
+            image_height: 0,
+            origin: (0, 0),
+            advance,
+        }
+    }
+
+    /// Checks whether this glyph has an image, i.e. is not blank.
+    pub fn has_image(&self) -> bool {
+        self.row_bytes != 0
+    }
+
+    /// Adds this glyph's `origin` to the given X/Y coordinates to produce the
+    /// coordinates of the top left of the glyph's rendered area. This
+    /// saturates rather than overflowing.
+    pub fn displace_usize(&self, x: usize, y: usize) -> (usize, usize) {
+        (
+            x.saturating_add(usize::from(self.origin.0)),
+            y.saturating_add(usize::from(self.origin.1)),
+        )
+    }
+
+    /// Computes the width in pixels of this glyph's rendered area. This is
+    /// `pixel_width` if it's set, and the width of `row_bytes` otherwise.
+    pub fn width_in_pixels(&self) -> usize {
+        let padded = self.row_bytes_usize() * 8;
+        match usize::from(self.pixel_width) {
+            0 => padded,
+            w => usize::min(w, padded),
+        }
+    }
+
+    /// Slices this glyph's bitmap out of a shared bitmap slice.
+    ///
+    /// This only makes sense for fonts using `BitmapEncoding::Raw`; see
+    /// `bitmap_bytes` for a version that handles any encoding.
+    ///
+    /// # Panics
+    ///
+    /// If this glyph's offset and size wind up being out of range for `bitmap`,
+    /// which probably means you're attempting to use a `Glyph` from one font
+    /// with a bitmap array from another, or something.
+    pub fn slice_bitmap<'b>(&self, bitmap: &'b [u8]) -> &'b [u8] {
+        let data_off = usize::from(self.image_offset);
+        let height = usize::from(self.image_height);
+        let data_len = self.row_bytes_usize() * height;
+        &bitmap[data_off..data_off + data_len]
+    }
+
+    /// Slices this glyph's bitmap out of a shared bitmap slice, like
+    /// `slice_bitmap`, but returns `None` instead of panicking if the glyph's
+    /// offset and size are out of range for `bitmap`.
+    pub fn try_slice_bitmap<'b>(&self, bitmap: &'b [u8]) -> Option<&'b [u8]> {
+        let data_off = usize::from(self.image_offset);
+        let height = usize::from(self.image_height);
+        let data_len = self.row_bytes_usize() * height;
+        bitmap.get(data_off..data_off.checked_add(data_len)?)
+    }
+
+    /// Returns an iterator over the bytes of this glyph's image, decoding them
+    /// from `bitmap` according to `encoding`.
+    ///
+    /// Unlike `slice_bitmap`, this works for compressed fonts. If the glyph's
+    /// data is out of range for `bitmap`, this produces fewer bytes than
+    /// expected (possibly none) rather than panicking.
+    pub fn bitmap_bytes<'b>(
+        &self,
+        bitmap: &'b [u8],
+        encoding: BitmapEncoding,
+    ) -> GlyphBytes<'b> {
+        let data_off = usize::from(self.image_offset);
+        let data_len = self.row_bytes_usize() * usize::from(self.image_height);
+        let data = bitmap.get(data_off..).unwrap_or(&[]);
+        match encoding {
+            BitmapEncoding::Raw => {
+                let data = &data[..usize::min(data_len, data.len())];
+                GlyphBytes::Raw(data.iter())
+            }
+            BitmapEncoding::PackBits => {
+                GlyphBytes::PackBits(PackBits::new(data).take(data_len))
+            }
+        }
+    }
+
+    /// Returns an iterator over the pixels of this glyph's image, decoding
+    /// them from `bitmap` according to `encoding`, as `(x, y, set)`.
+    ///
+    /// Pixels are produced row by row, covering the full `width_in_pixels` by
+    /// `image_height` image area, with `x` and `y` relative to the image's top
+    /// left corner. Like `bitmap_bytes`, this stops early rather than
+    /// panicking if the glyph's data is out of range for `bitmap`.
+    pub fn pixels<'b>(
+        &self,
+        bitmap: &'b [u8],
+        encoding: BitmapEncoding,
+    ) -> impl Iterator<Item = (usize, usize, bool)> + 'b {
+        let row_bits = self.row_bytes_usize() * 8;
+        let width = self.width_in_pixels();
+        self.bitmap_bytes(bitmap, encoding)
+            .flat_map(byte_pixels)
+            .enumerate()
+            .map(move |(i, set)| (i % row_bits, i / row_bits, set))
+            .filter(move |&(x, _, _)| x < width)
+    }
+
+    /// Finds the tight bounding box of the set pixels in this glyph's image,
+    /// decoded from `bitmap` according to `encoding`, as `(min_x, min_y,
+    /// max_x, max_y)`. The maximums are inclusive, and all coordinates are
+    /// relative to the image's top left corner, like those from `pixels`.
+    ///
+    /// Returns `None` if no pixels are set, including for a blank glyph.
+    pub fn ink_bounds(
+        &self,
+        bitmap: &[u8],
+        encoding: BitmapEncoding,
+    ) -> Option<(usize, usize, usize, usize)> {
+        self.pixels(bitmap, encoding)
+            .filter(|&(_, _, set)| set)
+            .fold(None, |bounds, (x, y, _)| Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            }))
+    }
+
+    /// Returns the default horizontal advance for glyphs in this font as a
+    /// `usize`.
+    pub fn default_advance_usize(&self) -> usize {
+        usize::from(self.advance)
+    }
+
+    /// Returns the number of bitmap bytes in a row of this glyph's image, as a
+    /// `usize`.
+    pub fn row_bytes_usize(&self) -> usize {
+        usize::from(self.row_bytes)
+    }
+}
+
+/// Ways that glyph images can be encoded in a font's bitmap storage.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitmapEncoding {
+    /// Each glyph's image is stored as `row_bytes * image_height` consecutive
+    /// bytes starting at its `image_offset`.
+    #[default]
+    Raw,
+    /// Each glyph's image is compressed separately using PackBits, starting at
+    /// its `image_offset`. This is smaller for large glyphs with lots of
+    /// repetition, but costs some time to decode during rendering, and means
+    /// `Glyph::slice_bitmap` can't be used.
+    ///
+    /// In PackBits, each run starts with a header byte `n`. If `n` is 127 or
+    /// less, it's followed by `n + 1` literal bytes. If `n` is 129 or more, it's
+    /// followed by a single byte that gets repeated `257 - n` times. A header
+    /// of 128 is ignored.
+    PackBits,
+}
+
+/// Iterator over the bytes of a glyph's image, produced by
+/// `Glyph::bitmap_bytes`.
+#[derive(Clone, Debug)]
+pub enum GlyphBytes<'b> {
+    Raw(core::slice::Iter<'b, u8>),
+    PackBits(core::iter::Take<PackBits<'b>>),
+}
+
+impl Iterator for GlyphBytes<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        match self {
+            Self::Raw(i) => i.next().copied(),
+            Self::PackBits(i) => i.next(),
+        }
+    }
+}
+
+/// Decoder for PackBits-compressed data (see `BitmapEncoding::PackBits`).
+///
+/// This will happily decode past the end of one glyph's data into the next, so
+/// it's normally used with `take`.
+#[derive(Clone, Debug)]
+pub struct PackBits<'b> {
+    data: &'b [u8],
+    literal: usize,
+    repeat: usize,
+    byte: u8,
+}
+
+impl<'b> PackBits<'b> {
+    /// Starts decoding `data`.
+    pub fn new(data: &'b [u8]) -> Self {
+        Self { data, literal: 0, repeat: 0, byte: 0 }
+    }
+
+    fn next_input(&mut self) -> Option<u8> {
+        let (&b, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(b)
+    }
+}
+
+impl Iterator for PackBits<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        loop {
+            if self.repeat != 0 {
+                self.repeat -= 1;
+                return Some(self.byte);
+            }
+            if self.literal != 0 {
+                self.literal -= 1;
+                return self.next_input();
+            }
+            match self.next_input()? {
+                n @ 0..=127 => self.literal = usize::from(n) + 1,
+                128 => (),
+                n => {
+                    self.byte = self.next_input()?;
+                    self.repeat = 257 - usize::from(n);
+                }
+            }
+        }
+    }
+}
+
+/// Draws one band of a glyph image, as passed to a `render_core` action, using
+/// `RenderTarget`.
+fn paint_slow<T: RenderTarget>(
+    target: &mut T,
+    fg: T::Pixel,
+    gx: usize,
+    gy: usize,
+    glyph: &Glyph,
+    slice: &[u8],
+) {
+    let height = usize::from(glyph.image_height);
+    let row_bytes = glyph.row_bytes_usize();
+    let width = glyph.width_in_pixels();
+
+    for (y, data) in (gy..gy + height).zip(slice.chunks(row_bytes)) {
+        for (x, set) in (gx..).zip(row_pixels(data, width)) {
+            if set {
+                target.put_pixel_slow(x, y, fg);
+            }
+        }
+    }
+}
+
+/// Unpacks the first `width` pixels of a row of glyph image data, leftmost
+/// first, `true` for ink.
+fn row_pixels(row: &[u8], width: usize) -> impl Iterator<Item = bool> + '_ {
+    row.iter().copied().flat_map(byte_pixels).take(width)
+}
+
+/// Unpacks one byte of glyph image data into its eight pixels, leftmost (most
+/// significant) first.
+fn byte_pixels(byte: u8) -> impl Iterator<Item = bool> {
+    (0..8).map(move |bit| byte & 0x80 >> bit != 0)
+}
+
+/// Draws one band of a glyph image, as passed to a `render_core` action, using
+/// `DirectRenderTarget`.
+fn paint_direct<T: DirectRenderTarget>(
+    target: &mut T,
+    fg: T::Pixel,
+    gx: usize,
+    gy: usize,
+    glyph: &Glyph,
+    slice: &[u8],
+) {
+    let height = usize::from(glyph.image_height);
+    let row_bytes = glyph.row_bytes_usize();
+    let width = glyph.width_in_pixels();
+
+    for (y, data) in (gy..gy + height).zip(slice.chunks(row_bytes)) {
+        let dest = target.subrow_mut(y, gx..gx + width);
+        // Each source byte covers eight destination pixels (fewer if the row
+        // was clipped, or at the end of a narrower image). Skip blank bytes
+        // entirely, and fill runs of set bits rather than testing every
+        // pixel.
+        for (pels, &byte) in dest.chunks_mut(8).zip(data) {
+            let mut byte = byte;
+            let mut x = 0;
+            while byte != 0 {
+                let skip = byte.leading_zeros();
+                x += skip as usize;
+                byte <<= skip;
+                let run = byte.leading_ones();
+                if x >= pels.len() {
+                    break;
+                }
+                let end = usize::min(x + run as usize, pels.len());
+                pels[x..end].fill(fg);
+                x = end;
+                byte = byte.checked_shl(run).unwrap_or(0);
+            }
+        }
+    }
+}
+
+
+/// A drop shadow, for `Font::render_shadow`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Shadow<P> {
+    /// Color to draw the shadow in.
+    pub color: P,
+    /// Displacement of the shadow from the text, in pixels, as (X, Y).
+    pub offset: (i8, i8),
+}
+
+/// The shape of a block of justified text, for `Font::render_justified`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Justify {
+    /// Width in pixels to stretch lines to.
+    pub width: usize,
+    /// Distance between the tops of lines, or `None` for the font's line
+    /// spacing.
+    pub line_spacing: Option<usize>,
+}
+
+/// The shape of a block of wrapped text, for `Font::render_paragraph`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Paragraph {
+    /// Width in pixels to wrap lines to.
+    pub max_width: usize,
+    /// Distance between the tops of lines, or `None` for the font's line
+    /// spacing.
+    pub line_spacing: Option<usize>,
+}
+
+/// How to shrink and smooth text, for `Font::render_aa_gamma`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Antialias {
+    /// Number of the font's pixels, in each direction, that make up one
+    /// pixel drawn.
+    pub scale: u8,
+    /// The level of the background the text is drawn over.
+    pub bg: u8,
+}
+
+/// An angle and a point to turn text about, for
+/// `Font::render_rotated_arbitrary`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Turn {
+    /// Clockwise angle to turn by, in radians.
+    pub radians: f32,
+    /// Point to turn about, relative to the top left corner of the unrotated
+    /// text.
+    pub pivot: (f32, f32),
+}
+
+/// Directions to mirror text in, for `Font::render_flipped`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Flip {
+    /// Mirror left-to-right.
+    pub x: bool,
+    /// Mirror top-to-bottom.
+    pub y: bool,
+}
+
+/// What to do with chars that have no glyph in a font, for
+/// `Font::render_with_missing` and `Font::width_with_missing`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum MissingPolicy {
+    /// Draw the font's replacement glyph, as `Font::render` does.
+    #[default]
+    Replacement,
+    /// Leave a blank space of the given width.
+    Space(u8),
+    /// Leave the char out entirely. Kerning applies between the chars on
+    /// either side as if it weren't there.
+    Skip,
+}
+
+/// How `Font::lines_layout`, and the functions built on it, break text into
+/// lines and space them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayoutOptions {
+    /// Distance between the tops of consecutive lines, in pixels, overriding
+    /// the font's `line_spacing`.
+    pub line_spacing: Option<usize>,
+    /// What a newline at the very end of the text does.
+    pub newline: TrailingNewline,
+}
+
+/// How `Font::lines_layout`, and the functions built on it, treat a newline
+/// at the very end of the text.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum TrailingNewline {
+    /// A final newline just ends the last line, as in `str::lines`: `"a\n"`
+    /// is one line, and `""` is no lines at all.
+    #[default]
+    Ends,
+    /// A final newline starts another, empty, line, as in a text editor with
+    /// the cursor after it: `"a\n"` is two lines, and `""` is one empty line.
+    StartsLine,
+}
+
+/// Ways to combine a pixel being drawn with the one already in a render
+/// target, for `RenderTarget::put_pixel_blended`.
+///
+/// `Or` and `Xor` combine pixels bitwise, channel by channel, so they're only
+/// meaningful for pixels made of integers (or `bool`s).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BlendMode {
+    /// Overwrite the existing pixel, as `put_pixel_slow` does.
+    #[default]
+    Replace,
+    /// Set the existing pixel to the bitwise OR of it and the new one.
+    Or,
+    /// Set the existing pixel to the bitwise XOR of it and the new one.
+    Xor,
+}
+
+/// Ways to rotate text, for `Font::render_rotated`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Rotation {
+    /// Ordinary horizontal text.
+    #[default]
+    None,
+    /// Rotated 90 degrees clockwise, reading top to bottom.
+    Cw90,
+    /// Rotated 90 degrees counterclockwise, reading bottom to top.
+    Ccw90,
+    /// Upside down.
+    Rot180,
+}
+
+/// A font's kerning table, in one of three formats.
+///
+/// With the `serde` feature, this serializes as a plain list of entries of
+/// whichever kind it holds. A `Class` table is written out as the wide entries
+/// for every pair it kerns.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+pub enum Kerning<'k> {
+    /// Compact entries that can only kern pairs of chars in ISO8859-1.
+    Narrow(KerningTable<'k>),
+    /// Entries that can kern any pair of chars, at four times the size.
+    Wide(WideKerningTable<'k>),
+    /// Kerning between classes of chars, with exceptions for particular pairs.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_class_kerning"))]
+    Class(ClassKerningTable<'k>),
+}
+
+impl Default for Kerning<'_> {
+    fn default() -> Self {
+        Self::Narrow(KerningTable::default())
+    }
+}
+
+impl<'k> Kerning<'k> {
+    /// Looks up the adjustment to the advance between `before` and `after`,
+    /// if the table has one.
+    pub fn get(&self, before: char, after: char) -> Option<i8> {
+        match self {
+            Self::Narrow(table) => table.get(before, after).map(|e| e.adjust),
+            Self::Wide(table) => table.get(before, after).map(|e| e.adjust),
+            Self::Class(table) => table.get(before, after),
+        }
+    }
+
+    /// Iterates over the table's entries, as `((before, after), adjust)`, in
+    /// table order. For a `Class` table, this is every pair it kerns, sorted.
+    pub fn pairs(&self) -> impl Iterator<Item = ((char, char), i8)> + 'k {
+        let (narrow, wide, class): (&[KerningEntry], &[WideKerningEntry], _) = match *self {
+            Self::Narrow(table) => (table.entries, &[], None),
+            Self::Wide(table) => (&[], table.entries, None),
+            Self::Class(table) => (&[], &[], Some(table.pairs())),
+        };
+        let narrow = narrow.iter()
+            .map(|e| ((char::from(e.pair.0), char::from(e.pair.1)), e.adjust));
+        let wide = wide.iter().map(|e| (e.pair, e.adjust));
+        narrow.chain(wide).chain(class.into_iter().flatten())
+    }
+
+    /// Returns the number of entries in the table. For a `Class` table, this
+    /// is the number of pairs it kerns, which takes a while to count.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Narrow(table) => table.entries.len(),
+            Self::Wide(table) => table.entries.len(),
+            Self::Class(table) => table.pairs().count(),
+        }
+    }
+
+    /// Checks whether the table is empty, so that nothing is kerned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A kerning table of compact entries, for chars in ISO8859-1.
+///
+/// With the `serde` feature, this serializes as a plain list of entries.
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct KerningTable<'k> {
+    pub entries: &'k [KerningEntry],
+}
+
+impl<'k> KerningTable<'k> {
+    /// Wraps `entries` as a kerning table, without checking them.
+    ///
+    /// `entries` must be sorted by `pair` with no duplicates, or lookups will
+    /// give wrong answers. This is checked in debug builds only; use
+    /// `new_checked` for data you don't trust.
+    pub const fn new(entries: &'k [KerningEntry]) -> Self {
+        debug_assert!(first_misordered_pair(entries).is_none());
+        Self { entries }
+    }
+
+    /// Wraps `entries` as a kerning table, checking that they are sorted by
+    /// `pair` with no duplicates.
+    pub fn new_checked(entries: &'k [KerningEntry]) -> Result<Self, KerningError> {
+        match first_misordered_pair(entries) {
+            None => Ok(Self { entries }),
+            Some(index) if entries[index - 1].pair == entries[index].pair => {
+                Err(KerningError::Duplicate { index })
+            }
+            Some(index) => Err(KerningError::Unsorted { index }),
+        }
+    }
+
+    /// Sorts `entries` by `pair` in place, and wraps them as a kerning table.
+    ///
+    /// This fails if any pair appears more than once.
+    pub fn sorted_from(entries: &'k mut [KerningEntry]) -> Result<Self, KerningError> {
+        entries.sort_unstable_by_key(|e| e.pair);
+        Self::new_checked(entries)
+    }
+
+    pub fn get(&self, before: char, after: char) -> Option<&KerningEntry> {
+        // Due to the limited size of the entry, we definitely don't have any
+        // entries for chars outside of ISO8859-1.
+        let before = u8::try_from(before).ok()?;
+        let after = u8::try_from(after).ok()?;
+
+        self.entries.binary_search_by_key(&(before, after), |e| e.pair)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+}
+
+/// Returns the index of the first entry in `entries` whose pair is not
+/// strictly greater than the one before it, if any.
+const fn first_misordered_pair(entries: &[KerningEntry]) -> Option<usize> {
+    // This is a `while` loop comparing fields by hand so that it works in a
+    // `const fn`.
+    let mut i = 1;
+    while i < entries.len() {
+        let (a, b) = (entries[i - 1].pair, entries[i].pair);
+        if a.0 > b.0 || (a.0 == b.0 && a.1 >= b.1) {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// A kerning table whose entries can hold any pair of chars.
+///
+/// With the `serde` feature, this serializes as a plain list of entries.
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct WideKerningTable<'k> {
+    pub entries: &'k [WideKerningEntry],
+}
+
+impl<'k> WideKerningTable<'k> {
+    /// Wraps `entries` as a kerning table, without checking them.
+    ///
+    /// `entries` must be sorted by `pair` with no duplicates, or lookups will
+    /// give wrong answers. This is checked in debug builds only; use
+    /// `new_checked` for data you don't trust.
+    pub const fn new(entries: &'k [WideKerningEntry]) -> Self {
+        debug_assert!(first_misordered_wide_pair(entries).is_none());
+        Self { entries }
+    }
+
+    /// Wraps `entries` as a kerning table, checking that they are sorted by
+    /// `pair` with no duplicates.
+    pub fn new_checked(entries: &'k [WideKerningEntry]) -> Result<Self, KerningError> {
+        match first_misordered_wide_pair(entries) {
+            None => Ok(Self { entries }),
+            Some(index) if entries[index - 1].pair == entries[index].pair => {
+                Err(KerningError::Duplicate { index })
+            }
+            Some(index) => Err(KerningError::Unsorted { index }),
+        }
+    }
+
+    /// Sorts `entries` by `pair` in place, and wraps them as a kerning table.
+    ///
+    /// This fails if any pair appears more than once.
+    pub fn sorted_from(entries: &'k mut [WideKerningEntry]) -> Result<Self, KerningError> {
+        entries.sort_unstable_by_key(|e| e.pair);
+        Self::new_checked(entries)
+    }
+
+    pub fn get(&self, before: char, after: char) -> Option<&WideKerningEntry> {
+        self.entries.binary_search_by_key(&(before, after), |e| e.pair)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+}
+
+/// Like `first_misordered_pair`, for wide entries.
+const fn first_misordered_wide_pair(entries: &[WideKerningEntry]) -> Option<usize> {
+    let mut i = 1;
+    while i < entries.len() {
+        let (a, b) = (entries[i - 1].pair, entries[i].pair);
+        if a.0 > b.0 || (a.0 == b.0 && a.1 >= b.1) {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// A kerning table that kerns classes of chars against each other, so that,
+/// say, every accented `A` needs only one entry against `V`.
+///
+/// Each char in `classes` has a left class, used when it comes before another
+/// char, and a right class, used when it comes after. A pair in `pairs` takes
+/// precedence over the classes; failing that, both chars must be listed in
+/// `classes` to be kerned.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ClassKerningTable<'k> {
+    /// Adjustments for particular pairs, overriding their classes.
+    pub pairs: WideKerningTable<'k>,
+    /// `(c, left, right)` for each char with classes, sorted by char.
+    pub classes: &'k [(char, u8, u8)],
+    /// The number of right classes, which is the length of each row of
+    /// `matrix`.
+    pub right_classes: u8,
+    /// The adjustment for each pair of classes, one row per left class: left
+    /// class `l` followed by right class `r` is at `l * right_classes + r`.
+    /// Zero means the classes aren't kerned.
+    pub matrix: &'k [i8],
+}
+
+impl<'k> ClassKerningTable<'k> {
+    /// Looks up the adjustment to the advance between `before` and `after`,
+    /// from `pairs` if it has them, and from their classes otherwise.
+    pub fn get(&self, before: char, after: char) -> Option<i8> {
+        if let Some(e) = self.pairs.get(before, after) {
+            return Some(e.adjust);
+        }
+        let (left, _) = self.classes_of(before)?;
+        let (_, right) = self.classes_of(after)?;
+        self.class_adjust(left, right)
+    }
+
+    /// Iterates over every pair this table kerns, as
+    /// `((before, after), adjust)`, sorted by pair.
+    pub fn pairs(&self) -> impl Iterator<Item = ((char, char), i8)> + 'k {
+        let table = *self;
+        let mut exact = self.pairs.entries.iter()
+            .map(|e| (e.pair, e.adjust))
+            .peekable();
+        // Walking `classes` in both loops produces pairs in sorted order, so
+        // the two lists can be merged as they go.
+        let mut classed = self.classes.iter()
+            .flat_map(move |&(before, left, _)| {
+                table.classes.iter().filter_map(move |&(after, _, right)| {
+                    Some(((before, after), table.class_adjust(left, right)?))
+                })
+            })
+            .peekable();
+        core::iter::from_fn(move || {
+            let order = match (exact.peek(), classed.peek()) {
+                (Some(e), Some(c)) => c.0.cmp(&e.0),
+                (Some(_), None) => core::cmp::Ordering::Greater,
+                (None, _) => return classed.next(),
+            };
+            match order {
+                core::cmp::Ordering::Less => classed.next(),
+                core::cmp::Ordering::Equal => {
+                    classed.next();
+                    exact.next()
+                }
+                core::cmp::Ordering::Greater => exact.next(),
+            }
+        })
+    }
+
+    fn classes_of(&self, c: char) -> Option<(u8, u8)> {
+        let i = self.classes.binary_search_by_key(&c, |e| e.0).ok()?;
+        let (_, left, right) = self.classes[i];
+        Some((left, right))
+    }
+
+    fn class_adjust(&self, left: u8, right: u8) -> Option<i8> {
+        if right >= self.right_classes {
+            return None;
+        }
+        let i = usize::from(left) * usize::from(self.right_classes) + usize::from(right);
+        self.matrix.get(i).copied().filter(|&adjust| adjust != 0)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_class_kerning<S: serde::Serializer>(
+    table: &ClassKerningTable<'_>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(table.pairs().map(|(pair, adjust)| WideKerningEntry { pair, adjust }))
+}
+
+/// Reasons a kerning table can be rejected by `KerningTable::new_checked`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KerningError {
+    /// The entry at `index` has a pair that sorts before the previous entry's.
+    Unsorted { index: usize },
+    /// The entry at `index` has the same pair as the previous entry.
+    Duplicate { index: usize },
+}
+
+impl core::fmt::Display for KerningError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unsorted { index } => {
+                write!(f, "kerning entry {index} is out of order")
+            }
+            Self::Duplicate { index } => {
+                write!(f, "kerning entry {index} duplicates the one before it")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KerningError {}
+
+/// A table of ligatures: sequences of chars that are drawn using a single
+/// glyph, rather than one glyph per char.
+///
+/// Each entry maps a sequence of chars to a glyph index. During layout, at each
+/// position in a string the longest matching sequence wins; if none matches,
+/// the char is drawn with its usual glyph. Entries with empty sequences, or
+/// whose glyph index isn't present in the font, are ignored.
+///
+/// For kerning, a ligature is treated as a unit, kerned against the char before
+/// it as though it were its first char and against the char after it as though
+/// it were its last char.
+///
+/// With the `serde` feature, this serializes as a plain list of entries.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct LigatureTable<'l> {
+    pub entries: &'l [(&'l str, u16)],
+}
+
+/// An entry in the kerning table.
+///
+/// Like `Glyph`, this is `repr(C)` for the benefit of the binary font format.
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct KerningEntry {
+    /// Sequence of characters that cause this entry to apply. Characters here
+    /// are given by the bottom 8 bits of their codepoint, limiting this to
+    /// ISO8859-1.
+    pub pair: (u8, u8),
+    /// Adjustment to the advance between the two characters given in `pair`.
+    /// Negative values bring the glyphs closer together, positive values move
+    /// them farther apart.
+    pub adjust: i8,
+}
+
+impl KerningEntry {
+    /// Apply the tracking adjustment from this kerning table entry to a
+    /// position represented as a `usize` using saturating arithmetic.
+    #[must_use = "this doesn't adjust in-place"]
+    pub fn adjust_usize(&self, val: usize) -> usize {
+        kern_usize(self.adjust, val)
+    }
+
+    /// Apply the tracking adjustment from this kerning table entry to a
+    /// signed position, which can go negative, using saturating arithmetic.
+    #[must_use = "this doesn't adjust in-place"]
+    pub fn adjust_i32(&self, val: i32) -> i32 {
+        kern_i32(self.adjust, val)
+    }
+}
+
+/// An entry in a `WideKerningTable`.
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WideKerningEntry {
+    /// Sequence of characters that cause this entry to apply.
+    pub pair: (char, char),
+    /// Adjustment to the advance between the two characters given in `pair`,
+    /// as in `KerningEntry::adjust`.
+    pub adjust: i8,
+}
+
+impl WideKerningEntry {
+    /// Apply the tracking adjustment from this kerning table entry to a
+    /// position represented as a `usize` using saturating arithmetic.
+    #[must_use = "this doesn't adjust in-place"]
+    pub fn adjust_usize(&self, val: usize) -> usize {
+        kern_usize(self.adjust, val)
+    }
+
+    /// Apply the tracking adjustment from this kerning table entry to a
+    /// signed position, which can go negative, using saturating arithmetic.
+    #[must_use = "this doesn't adjust in-place"]
+    pub fn adjust_i32(&self, val: i32) -> i32 {
+        kern_i32(self.adjust, val)
+    }
+}
+
+/// Applies a kerning adjustment to a signed `val`, saturating only at the
+/// limits of `i32`.
+fn kern_i32(adjust: i8, val: i32) -> i32 {
+    val.saturating_add(i32::from(adjust))
+}
+
+/// Applies a kerning adjustment to `val`, saturating.
+fn kern_usize(adjust: i8, val: usize) -> usize {
+    if adjust < 0 {
+        val.saturating_sub(usize::from(adjust.unsigned_abs()))
+    } else {
+        val.saturating_add(usize::from(adjust as u8))
+    }
+}
+
+/// Tracks the last char laid out along a line, to kern each following char
+/// against it.
+///
+/// `Font::start_kerning` gives one for the font's own table, but one can be
+/// made for any table with `new`, for layout code that walks text itself.
+#[derive(Clone, Debug)]
+pub struct KerningState<'k> {
+    table: Kerning<'k>,
+    last_char: Option<char>,
+}
+
+impl<'k> KerningState<'k> {
+    /// Makes a state for kerning with `table`, at the start of a line.
+    pub const fn new(table: Kerning<'k>) -> Self {
+        Self { table, last_char: None }
+    }
+
+    /// Forgets the last char, as at a line break, so that the next char isn't
+    /// kerned.
+    pub fn reset(&mut self) {
+        self.last_char = None;
+    }
+
+    /// Returns the last char given to this state since it was made or reset,
+    /// if any.
+    pub fn last_char(&self) -> Option<char> {
+        self.last_char
+    }
+
+    /// Returns the adjustment that `c` would get if it came next, without
+    /// moving on to it.
+    pub fn peek(&self, c: char) -> Option<i8> {
+        self.table.get(self.last_char?, c)
+    }
+
+    pub fn adjust_usize_for_char(&mut self, c: char, x: &mut usize) {
+        if let Some(adjust) = self.next_adjust(c) {
+            *x = kern_usize(adjust, *x);
+        }
+    }
+
+    /// Like `adjust_usize_for_char`, but for a signed position, which can go
+    /// negative (say, for text scrolled partly off the left edge) rather than
+    /// stopping at zero.
+    pub fn adjust_i32_for_char(&mut self, c: char, x: &mut i32) {
+        if let Some(adjust) = self.next_adjust(c) {
+            *x = kern_i32(adjust, *x);
+        }
+    }
+
+    /// Moves on to `c`, returning the adjustment between it and the previous
+    /// char, if any.
+    fn next_adjust(&mut self, c: char) -> Option<i8> {
+        let adjust = self.peek(c);
+        self.last_char = Some(c);
+        adjust
+    }
+
+    /// Kerns a ligature as a unit: it's kerned against the previous char as
+    /// its first char, and against the next char as its last.
+    fn adjust_usize_for_cluster(&mut self, cluster: &Cluster<'_>, x: &mut usize) {
+        self.adjust_usize_for_char(cluster.first, x);
+        self.last_char = Some(cluster.last);
+    }
+}
+
+/// A glyph from a string being laid out, along with the chars it represents.
+/// Ordinarily this is one char, but a ligature can stand for several.
+pub(crate) struct Cluster<'f> {
+    /// Byte offset of `first` in the string.
+    start: usize,
+    /// Number of chars in the cluster.
+    chars: usize,
+    first: char,
+    last: char,
+    glyph: &'f Glyph,
+    /// Whether the font has no glyph for `first`, so that `glyph` is the
+    /// replacement.
+    missing: bool,
+}
+
+pub trait RenderTarget {
+    type Pixel: Copy + 'static;
+
+    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: Self::Pixel);
+
+    /// Combines `pixel` with the pixel at `(x, y)` according to `mode`.
+    ///
+    /// The default implementation ignores `mode` and just calls
+    /// `put_pixel_slow`, which is right for `BlendMode::Replace`. Targets that
+    /// can read back their pixels should override this to support the other
+    /// modes, using `BlendPixel::blended`.
+    fn put_pixel_blended(&mut self, x: usize, y: usize, pixel: Self::Pixel, mode: BlendMode)
+        where Self::Pixel: BlendPixel,
+    {
+        let _ = mode;
+        self.put_pixel_slow(x, y, pixel);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P, C> RenderTarget for image::ImageBuffer<P, C>
+    where P: Copy + image::Pixel + 'static,
+          C: core::ops::Deref<Target = [P::Subpixel]> + core::ops::DerefMut,
+{
+    type Pixel = P;
+    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: P) {
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            return;
+        };
+        if x < self.width() && y < self.height() {
+            self.put_pixel(x, y, pixel);
+        }
+    }
+
+    fn put_pixel_blended(&mut self, x: usize, y: usize, pixel: P, mode: BlendMode)
+        where P: BlendPixel,
+    {
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            return;
+        };
+        if x < self.width() && y < self.height() {
+            let dst = self.get_pixel_mut(x, y);
+            *dst = dst.blended(pixel, mode);
+        }
+    }
+}
+
+/// Pixels that can be combined with the pixel already in a render target,
+/// for `RenderTarget::put_pixel_blended`.
+///
+/// This is implemented for `bool` and the unsigned integers, which covers
+/// monochrome and packed-color framebuffers, and with the `std` feature, for
+/// `image`'s pixel types.
+pub trait BlendPixel: Copy {
+    /// Returns `src` combined with `self`, the existing pixel, according to
+    /// `mode`.
+    fn blended(self, src: Self, mode: BlendMode) -> Self;
+}
+
+macro_rules! impl_blend_pixel_bits {
+    ($($t:ty),*) => {
+        $(
+            impl BlendPixel for $t {
+                fn blended(self, src: Self, mode: BlendMode) -> Self {
+                    match mode {
+                        BlendMode::Replace => src,
+                        BlendMode::Or => self | src,
+                        BlendMode::Xor => self ^ src,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_blend_pixel_bits!(bool, u8, u16, u32, u64);
+
+/// Blends `image` pixels channel by channel. Channels that aren't integers,
+/// or that don't survive the trip through `u64`, are replaced.
+#[cfg(feature = "std")]
+macro_rules! impl_blend_pixel_image {
+    ($($p:ident),*) => {
+        $(
+            impl<S> BlendPixel for image::$p<S>
+                where S: image::Primitive,
+                      Self: image::Pixel<Subpixel = S>,
+            {
+                fn blended(self, src: Self, mode: BlendMode) -> Self {
+                    image::Pixel::map2(&self, &src, |d, s| blend_channel(d, s, mode))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "std")]
+impl_blend_pixel_image!(Luma, LumaA, Rgb, Rgba);
+
+/// Blends one channel of an `image` pixel, for `BlendPixel`.
+#[cfg(feature = "std")]
+fn blend_channel<S: image::Primitive>(dst: S, src: S, mode: BlendMode) -> S {
+    match (dst.to_u64(), src.to_u64()) {
+        (Some(d), Some(s)) => S::from(d.blended(s, mode)).unwrap_or(src),
+        _ => src,
+    }
+}
+
+pub trait DirectRenderTarget {
+    type Pixel: Copy + 'static;
+
+    fn subrow_mut(&mut self, y: usize, x: core::ops::Range<usize>) -> &mut [Self::Pixel];
+}
+
+#[cfg(feature = "std")]
+impl<P, C> DirectRenderTarget for image::ImageBuffer<image::Luma<P>, C>
+    where P: Copy + image::Primitive + 'static,
+          C: core::ops::Deref<Target = [P]> + core::ops::DerefMut + AsMut<[P]>,
+{
+    type Pixel = image::Luma<P>;
+
+    fn subrow_mut(&mut self, y: usize, x: core::ops::Range<usize>) -> &mut [Self::Pixel] {
+        let flat = self.as_flat_samples_mut();
+        if y >= flat.layout.height as usize {
+            return &mut [];
+        }
+        let row_i = y * flat.layout.width as usize;
+        let row = &mut flat.samples[row_i..row_i + flat.layout.width as usize];
+
+        let x_start = usize::min(x.start, flat.layout.width as usize);
+        let x_end = usize::min(x.end, flat.layout.width as usize);
+        let subpixels = &mut row[x_start..x_end];
+        // The reason this is only defined for Luma is so that I know it's a
+        // single-channel image, and I can do this. `Luma` is a `repr(C)`
+        // wrapper around `[P; 1]`, but check that its layout still matches
+        // `P`, so that a change in `image` fails to compile rather than
+        // corrupting memory.
+        const {
+            assert!(core::mem::size_of::<image::Luma<P>>() == core::mem::size_of::<P>());
+            assert!(core::mem::align_of::<image::Luma<P>>() == core::mem::align_of::<P>());
+        }
+        unsafe {
+            &mut *(subpixels as *mut [P] as *mut [image::Luma<P>])
+        }
+    }
+}
+
+/// A render target borrowing a plain slice of pixels, such as a framebuffer,
+/// stored in row-major order.
+///
+/// Each row starts `stride` pixels after the one before it, and only the first
+/// `width` pixels of each row are drawn into. Using a `stride` wider than
+/// `width` lets this cover a rectangular window of a larger buffer; pixels
+/// between the end of one row's window and the start of the next are left
+/// alone.
+#[derive(Debug)]
+pub struct SliceTarget<'a, P> {
+    pixels: &'a mut [P],
+    width: usize,
+    stride: usize,
+}
+
+impl<'a, P> SliceTarget<'a, P> {
+    /// Wraps `pixels` as a target `width` pixels wide, with rows `stride`
+    /// pixels apart. The target's height is however many whole rows of
+    /// `width` pixels fit in `pixels`.
+    ///
+    /// # Panics
+    ///
+    /// If `stride` is less than `width`.
+    pub fn new(pixels: &'a mut [P], width: usize, stride: usize) -> Self {
+        assert!(stride >= width, "stride must be at least width");
+        Self { pixels, width, stride }
+    }
+
+    /// Returns the width of the target, in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the target, in pixels.
+    pub fn height(&self) -> usize {
+        match self.pixels.len().checked_sub(self.width) {
+            // Don't divide by zero for zero-width targets, which have no
+            // pixels to draw into whatever their height.
+            Some(_) if self.stride == 0 => 0,
+            Some(rest) => rest / self.stride + 1,
+            None => 0,
+        }
+    }
+}
+
+impl<P: Copy + 'static> RenderTarget for SliceTarget<'_, P> {
+    type Pixel = P;
+
+    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: P) {
+        if x < self.width && y < self.height() {
+            self.pixels[y * self.stride + x] = pixel;
+        }
+    }
+
+    fn put_pixel_blended(&mut self, x: usize, y: usize, pixel: P, mode: BlendMode)
+        where P: BlendPixel,
+    {
+        if x < self.width && y < self.height() {
+            let dst = &mut self.pixels[y * self.stride + x];
+            *dst = dst.blended(pixel, mode);
+        }
+    }
+}
+
+impl<P: Copy + 'static> DirectRenderTarget for SliceTarget<'_, P> {
+    type Pixel = P;
+
+    fn subrow_mut(&mut self, y: usize, x: core::ops::Range<usize>) -> &mut [P] {
+        if y >= self.height() {
+            return &mut [];
+        }
+        let row = &mut self.pixels[y * self.stride..][..self.width];
+        let x_start = usize::min(x.start, self.width);
+        let x_end = usize::min(x.end, self.width).max(x_start);
+        &mut row[x_start..x_end]
+    }
+}
+
+/// Passes pixels left of `stop_x` through to `target`, and drops the rest.
+struct ClipRight<'t, T> {
+    target: &'t mut T,
+    stop_x: usize,
+}
+
+impl<T: RenderTarget> RenderTarget for ClipRight<'_, T> {
+    type Pixel = T::Pixel;
+
+    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: T::Pixel) {
+        if x < self.stop_x {
+            self.target.put_pixel_slow(x, y, pixel);
+        }
+    }
+
+    fn put_pixel_blended(&mut self, x: usize, y: usize, pixel: T::Pixel, mode: BlendMode)
+        where T::Pixel: BlendPixel,
+    {
+        if x < self.stop_x {
+            self.target.put_pixel_blended(x, y, pixel, mode);
+        }
+    }
 }
 
-impl GlyphStorage<'_> {
-    /// Looks up a `char` in glyph storage. Returns `None` if the `char` is not
-    /// explicitly represented in storage.
-    pub fn get(&self, c: char) -> Option<&Glyph> {
-        match self {
-            Self::Dense { first, glyphs } => {
-                let i = u32::from(c).wrapping_sub(u32::from(*first)) as usize;
-                glyphs.get(i)
-            },
-            Self::Sparse { sorted_glyphs } => {
-                sorted_glyphs.binary_search_by_key(&c, |(cp, _)| *cp)
-                    .ok()
-                    .map(|i| &sorted_glyphs[i].1)
-            },
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// Stand-in font for tests. Every printable ASCII char but space is the
+    /// same 5x7 "F", so that flips and turns are visible, and every glyph
+    /// advances 6 pixels.
+    const TEST_FONT: Font<'static, 'static, 'static, 'static> = Font {
+        ascent: 7,
+        descent: 1,
+        line_spacing: 8,
+        glyph_storage: GlyphStorage::Dense { first: b' ', glyphs: &TEST_GLYPHS },
+        replacement: b'?' - b' ',
+        replacement_char: None,
+        bitmaps: &[0xF8, 0x80, 0xF0, 0x80, 0x80, 0x80, 0x80],
+        bitmap_encoding: BitmapEncoding::Raw,
+        kerning: Kerning::Narrow(KerningTable { entries: &[] }),
+        advance_bias: 0,
+        ligatures: LigatureTable { entries: &[] },
+    };
+
+    const TEST_GLYPHS: [Glyph; 95] = {
+        let ink = Glyph {
+        pixel_width: 0,
+            row_bytes: 1,
+            image_offset: 0,
+            image_height: 7,
+            origin: (0, 0),
+            advance: 6,
+        };
+        let mut glyphs = [ink; 95];
+        glyphs[0] = Glyph { row_bytes: 0, image_height: 0, ..ink };
+        glyphs
+    };
+
+    /// `TEST_FONT` kerned with `entries`.
+    fn kerned(entries: &'static [KerningEntry]) -> Font<'static, 'static, 'static, 'static> {
+        Font {
+            kerning: Kerning::Narrow(KerningTable::new(entries)),
+            ..TEST_FONT
+        }
+    }
+
+    #[test]
+    fn ellipsis_at_exact_fit() {
+        let font = TEST_FONT;
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 36, '.'), "abcdef");
+        assert!(matches!(
+            font.truncate_with_ellipsis("abcdef", 36, '.'),
+            std::borrow::Cow::Borrowed(_),
+        ));
+        // Four chars and the ellipsis are exactly 30 pixels.
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 30, '.'), "abcd.");
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 29, '.'), "abc.");
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 35, '.'), "abcd.");
+    }
+
+    #[test]
+    fn ellipsis_too_wide_to_fit() {
+        let font = TEST_FONT;
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 5, '.'), "");
+        assert_eq!(font.truncate_with_ellipsis_str("abcdef", 12, "..."), "ab");
+    }
+
+    #[test]
+    fn multi_char_ellipsis() {
+        let font = TEST_FONT;
+        assert_eq!(font.truncate_with_ellipsis_str("abcdefgh", 30, "..."), "ab...");
+        assert_eq!(font.truncate_with_ellipsis_str("abcdefgh", 29, "..."), "a...");
+        // A multi-byte ellipsis char is cut and appended whole.
+        assert_eq!(font.truncate_with_ellipsis("abcd\u{e9}", 24, '\u{2026}'), "abc\u{2026}");
+    }
+
+    #[test]
+    fn ellipsis_is_kerned() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'd', b'.'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 29, '.'), "abcd.");
+        assert_eq!(font.truncate_with_ellipsis("abcdef", 27, '.'), "abc.");
+    }
+
+    #[test]
+    fn packbits_decodes_zero_rows() {
+        // Two rows of four zero bytes, as one repeat.
+        let data = [(257 - 8) as u8, 0x00];
+        assert!(PackBits::new(&data).eq([0; 8]));
+    }
+
+    #[test]
+    fn packbits_decodes_alternating_rows() {
+        // Rows of alternating bits don't repeat, so they're literals; a
+        // no-op header between them is skipped.
+        let data = [1, 0xAA, 0x55, 128, 1, 0x55, 0xAA];
+        assert!(PackBits::new(&data).eq([0xAA, 0x55, 0x55, 0xAA]));
+    }
+
+    #[test]
+    fn ranges_storage_lookup() {
+        const fn glyph(advance: u8) -> Glyph {
+            Glyph { advance, ..TEST_GLYPHS[0] }
+        }
+        static GLYPHS: [Glyph; 6] = [glyph(1), glyph(2), glyph(3), glyph(4), glyph(5), glyph(6)];
+        let storage = GlyphStorage::Ranges {
+            ranges: &[('A', 3, 0), ('a', 2, 3), ('\u{3b1}', 1, 5)],
+            glyphs: &GLYPHS,
+        };
+        let advance = |c| storage.get(c).map(|g| g.advance);
+
+        // Both ends of each run.
+        assert_eq!(advance('A'), Some(1));
+        assert_eq!(advance('C'), Some(3));
+        assert_eq!(advance('a'), Some(4));
+        assert_eq!(advance('b'), Some(5));
+        assert_eq!(advance('\u{3b1}'), Some(6));
+        // Before, between, and after the runs.
+        for c in ['\0', '@', 'D', '`', 'c', '\u{3b0}', '\u{3b2}', char::MAX] {
+            assert_eq!(advance(c), None, "{c:?}");
+        }
+
+        assert_eq!(storage.get_by_index(5).map(|g| g.advance), Some(6));
+        assert_eq!(storage.get_by_index(6), None);
+    }
+
+    #[test]
+    fn ranges_index_of() {
+        let storage = GlyphStorage::Ranges {
+            ranges: &[('A', 3, 0), ('a', 2, 3)],
+            glyphs: &TEST_GLYPHS[..5],
+        };
+        assert_eq!(storage.index_of('A'), Some(0));
+        assert_eq!(storage.index_of('C'), Some(2));
+        assert_eq!(storage.index_of('a'), Some(3));
+        assert_eq!(storage.index_of('b'), Some(4));
+        for c in ['@', 'D', '`', 'c'] {
+            assert_eq!(storage.index_of(c), None, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn replacement_char_in_sparse_font() {
+        static GLYPHS: [(char, Glyph); 3] = [
+            ('?', Glyph { advance: 3, ..TEST_GLYPHS[1] }),
+            ('a', Glyph { advance: 5, ..TEST_GLYPHS[1] }),
+            ('\u{fffd}', Glyph { advance: 7, ..TEST_GLYPHS[1] }),
+        ];
+        let font = Font {
+            glyph_storage: GlyphStorage::Sparse { sorted_glyphs: &GLYPHS },
+            replacement: 0,
+            replacement_char: Some('\u{fffd}'),
+            ..TEST_FONT
+        };
+        // '?' would be index 31 in a dense ASCII font, which doesn't exist
+        // here; the char finds the right glyph regardless of storage.
+        assert_eq!(font.width("a"), 5);
+        assert_eq!(font.width("z"), 7);
+
+        // A replacement char the font lacks falls back to the index.
+        let font = Font { replacement_char: Some('\u{25a1}'), ..font };
+        assert_eq!(font.width("z"), 3);
+    }
+
+    #[test]
+    fn checked_kerning_tables() {
+        let entry = |before, after| KerningEntry { pair: (before, after), adjust: -1 };
+        let sorted = [entry(b'A', b'V'), entry(b'V', b'A'), entry(b'V', b'a')];
+        assert_eq!(KerningTable::new_checked(&sorted), Ok(KerningTable { entries: &sorted }));
+
+        let unsorted = [entry(b'V', b'A'), entry(b'A', b'V'), entry(b'V', b'a')];
+        assert_eq!(KerningTable::new_checked(&unsorted), Err(KerningError::Unsorted { index: 1 }));
+
+        let duplicated = [entry(b'A', b'V'), entry(b'V', b'A'), entry(b'V', b'A')];
+        assert_eq!(KerningTable::new_checked(&duplicated), Err(KerningError::Duplicate { index: 2 }));
+
+        let mut shuffled = unsorted;
+        assert_eq!(KerningTable::sorted_from(&mut shuffled).map(|t| t.entries), Ok(&sorted[..]));
+    }
+
+    /// `TEST_FONT` with an extra glyph, index 95, advancing 9 pixels, for the
+    /// ligatures "fi" and "ffi".
+    fn ligatured() -> Font<'static, 'static, 'static, 'static> {
+        static GLYPHS: [Glyph; 96] = {
+            let mut glyphs = [Glyph { advance: 9, ..TEST_GLYPHS[1] }; 96];
+            let mut i = 0;
+            while i < TEST_GLYPHS.len() {
+                glyphs[i] = TEST_GLYPHS[i];
+                i += 1;
+            }
+            glyphs
+        };
+        Font {
+            glyph_storage: GlyphStorage::Dense { first: b' ', glyphs: &GLYPHS },
+            ligatures: LigatureTable { entries: &[("fi", 95), ("ffi", 95)] },
+            ..TEST_FONT
+        }
+    }
+
+    /// Returns the X position of each glyph drawn for `s`.
+    fn glyph_xs(font: &Font<'_, '_, '_, '_>, s: &str) -> Vec<usize> {
+        let mut xs = vec![];
+        font.render_core(s, 0, 0, |x, _, _, _| xs.push(x));
+        xs
+    }
+
+    #[test]
+    fn ligature_replaces_its_chars() {
+        let font = ligatured();
+        assert_eq!(font.width("fi"), 9);
+        assert_eq!(glyph_xs(&font, "fi"), [0]);
+        assert_eq!(font.width("fix"), 15);
+        assert_eq!(glyph_xs(&font, "fix"), [0, 9]);
+    }
+
+    #[test]
+    fn longest_ligature_wins() {
+        let font = ligatured();
+        assert_eq!(glyph_xs(&font, "ffi"), [0]);
+        assert_eq!(glyph_xs(&font, "fffi"), [0, 6]);
+    }
+
+    #[test]
+    fn partial_ligature_falls_back() {
+        let font = ligatured();
+        assert_eq!(glyph_xs(&font, "ff"), [0, 6]);
+        assert_eq!(glyph_xs(&font, "f i"), [0, 12]);
+        assert_eq!(font.width("ffx"), 18);
+    }
+
+    #[test]
+    fn lines_layout_widths_and_offsets() {
+        let lines: Vec<_> = TEST_FONT.lines_layout("a\nbb\nccc", LayoutOptions::default())
+            .map(|l| (l.text, l.width, l.y))
+            .collect();
+        assert_eq!(lines, [("a", 6, 0), ("bb", 12, 8), ("ccc", 18, 16)]);
+
+        // A trailing newline adds no line.
+        assert_eq!(TEST_FONT.lines_layout("a\nbb\n", LayoutOptions::default()).count(), 2);
+    }
+
+
+    #[test]
+    fn empty_text_has_no_width() {
+        assert_eq!(TEST_FONT.width(""), 0);
+        assert!(TEST_FONT.lines_layout("\n\n", LayoutOptions::default()).all(|l| l.width == 0));
+    }
+
+
+    /// A linear congruential generator, for tests that want varied inputs
+    /// without another dependency.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 33) as usize % bound
+        }
+    }
+
+    /// `paint_direct` as it was before it learned to skip blank bytes and
+    /// fill runs: one destination pixel at a time.
+    fn paint_bitwise<T: DirectRenderTarget>(
+        target: &mut T,
+        fg: T::Pixel,
+        gx: usize,
+        gy: usize,
+        glyph: &Glyph,
+        slice: &[u8],
+    ) {
+        let height = usize::from(glyph.image_height);
+        let row_bytes = glyph.row_bytes_usize();
+        let width = glyph.width_in_pixels();
+
+        for (y, data) in (gy..gy + height).zip(slice.chunks(row_bytes)) {
+            let dest = target.subrow_mut(y, gx..gx + width);
+            let mut data = data.iter().cloned();
+            let mut byte = 0;
+            let mut bits_left = 0_usize;
+            for pel in dest {
+                if let Some(n) = bits_left.checked_sub(1) {
+                    bits_left = n;
+                } else if let Some(b) = data.next() {
+                    byte = b;
+                    bits_left = 7;
+                } else {
+                    break;
+                }
+
+                if byte & 0x80 != 0 {
+                    *pel = fg;
+                }
+                byte <<= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn paint_direct_matches_per_pixel() {
+        let mut rng = Lcg(548);
+        for _ in 0..2000 {
+            let row_bytes = rng.next(4) + 1;
+            let glyph = Glyph {
+                row_bytes: row_bytes as u8,
+                pixel_width: rng.next(row_bytes * 8 + 1) as u8,
+                image_offset: 0,
+                image_height: rng.next(4) as u8 + 1,
+                origin: (0, 0),
+                advance: 0,
+            };
+            // Mostly blank bytes, solid bytes, and noise, to exercise both
+            // skipping and runs.
+            let data: Vec<u8> = (0..row_bytes * usize::from(glyph.image_height))
+                .map(|_| match rng.next(4) {
+                    0 => 0,
+                    1 => 0xFF,
+                    _ => rng.next(256) as u8,
+                })
+                .collect();
+            // Targets both wider and narrower than the glyph, so that some
+            // draws are clipped on the right.
+            let width = rng.next(40) as u32 + 1;
+            let (gx, gy) = (rng.next(12), rng.next(3));
+
+            let draw = |paint: &dyn Fn(&mut image::GrayImage)| {
+                let mut img = image::GrayImage::new(width, 6);
+                paint(&mut img);
+                img
+            };
+            let fg = image::Luma([1]);
+            let expected = draw(&|t| paint_bitwise(t, fg, gx, gy, &glyph, &data));
+            assert_eq!(draw(&|t| paint_direct(t, fg, gx, gy, &glyph, &data)), expected,
+                "{:?} {:02x?} at ({}, {}) in {} px", glyph, data, gx, gy, width);
+            assert_eq!(draw(&|t| paint_slow(t, fg, gx, gy, &glyph, &data)), expected);
+        }
+    }
+
+
+    #[test]
+    fn try_slice_bitmap_checks_range() {
+        let glyph = Glyph { row_bytes: 2, image_offset: 1, image_height: 2, ..TEST_GLYPHS[1] };
+        assert_eq!(glyph.try_slice_bitmap(&[0, 1, 2, 3, 4]), Some(&[1, 2, 3, 4][..]));
+        // Length past the end.
+        assert_eq!(glyph.try_slice_bitmap(&[0, 1, 2, 3]), None);
+        // Offset past the end.
+        let glyph = Glyph { image_offset: 9, ..glyph };
+        assert_eq!(glyph.try_slice_bitmap(&[0, 1, 2, 3, 4]), None);
+        let glyph = Glyph { image_offset: u16::MAX, ..glyph };
+        assert_eq!(glyph.try_slice_bitmap(&[0; 5]), None);
+    }
+
+
+    #[test]
+    fn rendering_near_usize_max_draws_nothing() {
+        for x in [usize::MAX, usize::MAX - 5, usize::MAX - 100] {
+            let mut img = image::GrayImage::new(16, 8);
+            TEST_FONT.render("FFF", x, 0, &mut img, image::Luma([0xFF]));
+            TEST_FONT.render_direct("FFF", x, 0, &mut img, image::Luma([0xFF]));
+            assert!(img.pixels().all(|p| p.0 == [0]), "at {x}");
+        }
+        let mut img = image::GrayImage::new(16, 8);
+        TEST_FONT.render("FFF", 0, usize::MAX, &mut img, image::Luma([0xFF]));
+        assert!(img.pixels().all(|p| p.0 == [0]));
+    }
+
+
+    #[test]
+    fn render_baseline_clips_above_top() {
+        let font = Font { ascent: 5, descent: 3, ..TEST_FONT };
+        let canvas = || image::GrayImage::new(6, 8);
+
+        let mut whole = canvas();
+        font.render("F", 0, 0, &mut whole, image::Luma([0xFF]));
+        let mut clipped = canvas();
+        font.render_baseline("F", 0, 2, &mut clipped, image::Luma([0xFF]));
+        // The glyph's top three rows are lost, and the rest moves up.
+        for (x, y, p) in clipped.enumerate_pixels() {
+            let expected = whole.get_pixel_checked(x, y + 3).copied().unwrap_or(image::Luma([0]));
+            assert_eq!(*p, expected, "({x}, {y})");
+        }
+
+        // Where it fits, the top is just `ascent` above the baseline.
+        let mut shifted = canvas();
+        font.render("F", 0, 1, &mut shifted, image::Luma([0xFF]));
+        let mut placed = canvas();
+        font.render_baseline("F", 0, 6, &mut placed, image::Luma([0xFF]));
+        assert_eq!(placed, shifted);
+    }
+
+    #[test]
+    fn slice_target_stride_doesnt_bleed() {
+        // A 4x8 window into a buffer 7 pixels wide, with "FFF" overhanging
+        // it on the right.
+        const STRIDE: usize = 7;
+        let mut direct = [0u8; STRIDE * 8];
+        TEST_FONT.render_direct("FFF", 1, 0, &mut SliceTarget::new(&mut direct, 4, STRIDE), 1);
+        let mut slow = [0u8; STRIDE * 8];
+        TEST_FONT.render("FFF", 1, 0, &mut SliceTarget::new(&mut slow, 4, STRIDE), 1);
+        assert_eq!(direct, slow);
+
+        let rows: Vec<&[u8]> = direct.chunks(STRIDE).collect();
+        assert_eq!(rows[0], [0, 1, 1, 1, 0, 0, 0]);
+        assert_eq!(rows[1], [0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(rows[2], [0, 1, 1, 1, 0, 0, 0]);
+        for row in &rows[3..7] {
+            assert_eq!(*row, [0, 1, 0, 0, 0, 0, 0]);
         }
+        assert_eq!(rows[7], [0; STRIDE]);
     }
 
-    /// Looks up a glyph by glyph _index,_ which is mostly only used during
-    /// replacement glyph processing, but maybe you've got ideas.
-    pub fn get_by_index(&self, index: usize) -> Option<&Glyph> {
-        match self {
-            Self::Dense { glyphs, .. } => {
-                glyphs.get(index)
-            },
-            Self::Sparse { sorted_glyphs, .. } => {
-                sorted_glyphs.get(index).map(|(_, g)| g)
-            },
+    #[test]
+    fn slice_target_clips_rows_below() {
+        // Room for two and a bit rows, and the leftovers aren't a row.
+        let mut pixels = [0u8; 4 * 2 + 3];
+        let mut target = SliceTarget::new(&mut pixels, 3, 4);
+        assert_eq!(target.height(), 3);
+        assert!(target.subrow_mut(3, 0..3).is_empty());
+        TEST_FONT.render_direct("F", 0, 0, &mut target, 1);
+        assert_eq!(pixels, [1, 1, 1, 0, 1, 0, 0, 0, 1, 1, 1]);
+    }
+
+
+    #[test]
+    fn missing_chars_in_order() {
+        let missing: Vec<char> = TEST_FONT.missing_chars("a\u{e9}b\tc\u{e9}~").collect();
+        assert_eq!(missing, ['\u{e9}', '\t', '\u{e9}']);
+        assert!(!TEST_FONT.has_all_glyphs("a\u{e9}"));
+        assert!(TEST_FONT.has_all_glyphs("Hello, world!"));
+        assert!(TEST_FONT.has_all_glyphs(""));
+    }
+
+
+    #[test]
+    fn styled_glyphs_get_their_own_colors() {
+        let mut pixels = [0u8; 12 * 8];
+        let mut seen = vec![];
+        TEST_FONT.render_styled("AB", 0, 0, &mut SliceTarget::new(&mut pixels, 12, 12), |c, i| {
+            seen.push((c, i));
+            if c == 'A' { 1 } else { 2 }
+        });
+        assert_eq!(seen, [('A', 0), ('B', 1)]);
+        // The top bar of each "F".
+        assert_eq!(pixels[..12], [1, 1, 1, 1, 1, 0, 2, 2, 2, 2, 2, 0]);
+        // Each glyph's ink is all in its own color.
+        for row in pixels.chunks(12) {
+            assert!(row[..6].iter().all(|&p| p != 2));
+            assert!(row[6..].iter().all(|&p| p != 1));
         }
     }
-}
 
-/// Data for a single glyph in a font.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct Glyph {
-    /// Width of each pixel row in `image`, measured in bytes, or units of 8
-    /// pixels.
-    pub row_bytes: u8,
-    /// Image data slice position in the font's image data array.
-    pub image_offset: u16,
-    /// Number of rows of pixels in this glyph's image.
-    pub image_height: u8,
-    /// Displacement in (X, Y) from the top-left of the glyph's bounding box to
-    /// the top-left pixel in `image`. This allows an image to be smaller than
-    /// the bounding box, e.g. omitting data above a lowercase letter.
-    pub origin: (u8, u8),
-    /// Default X advance from the left side of this glyph's bounding box to the
-    /// left side of the next glyph. This can be overridden by kerning
-    /// information.
-    pub advance: u8,
-}
 
-impl Glyph {
-    /// Checks whether this glyph has an image, i.e. is not blank.
-    pub fn has_image(&self) -> bool {
-        self.row_bytes != 0
+    #[test]
+    fn flipped_matches_flipped_reference() {
+        let draw = |flip: Option<Flip>| {
+            let mut img = image::GrayImage::new(12, 8);
+            match flip {
+                Some(flip) => TEST_FONT.render_flipped("FF", 0, 0, &mut img, image::Luma([0xFF]), flip),
+                None => TEST_FONT.render("FF", 0, 0, &mut img, image::Luma([0xFF])),
+            }
+            img
+        };
+        let plain = draw(None);
+
+        let mirrored = draw(Some(Flip { x: true, y: false }));
+        assert_eq!(mirrored, image::imageops::flip_horizontal(&plain));
+
+        // Only the 7-row glyph images are turned over, not the whole line.
+        let upside_down = draw(Some(Flip { x: false, y: true }));
+        let mut expected = plain.clone();
+        for (x, y, p) in expected.enumerate_pixels_mut() {
+            if y < 7 {
+                *p = *plain.get_pixel(x, 6 - y);
+            }
+        }
+        assert_eq!(upside_down, expected);
+
+        let both = draw(Some(Flip { x: true, y: true }));
+        assert_eq!(both, image::imageops::flip_horizontal(&expected));
+        assert_eq!(draw(Some(Flip::default())), plain);
     }
 
-    /// Adds this glyph's `origin` to the given X/Y coordinates to produce the
-    /// coordinates of the top left of the glyph's rendered area.
-    pub fn displace_usize(&self, x: usize, y: usize) -> (usize, usize) {
-        (x + usize::from(self.origin.0), y + usize::from(self.origin.1))
+
+    #[test]
+    fn rotated_cw90_moves_corners() {
+        assert_eq!(TEST_FONT.rotated_extent("F", Rotation::Cw90), (8, 6));
+        let mut img = image::GrayImage::new(8, 6);
+        TEST_FONT.render_rotated("F", 0, 0, Rotation::Cw90, &mut img, image::Luma([0xFF]));
+        // The top left of the "F" is now the top right, and the bottom of its
+        // stem is at the top left, just inside the descender.
+        assert_eq!(img.get_pixel(7, 0).0, [0xFF]);
+        assert_eq!(img.get_pixel(1, 0).0, [0xFF]);
+        assert_eq!(img.get_pixel(0, 0).0, [0]);
+        // The end of the top bar.
+        assert_eq!(img.get_pixel(7, 4).0, [0xFF]);
+
+        let mut plain = image::GrayImage::new(6, 8);
+        TEST_FONT.render("F", 0, 0, &mut plain, image::Luma([0xFF]));
+        assert_eq!(img, image::imageops::rotate90(&plain));
+        for (rotation, expected) in [
+            (Rotation::Ccw90, image::imageops::rotate270(&plain)),
+            (Rotation::Rot180, image::imageops::rotate180(&plain)),
+        ] {
+            let (w, h) = TEST_FONT.rotated_extent("F", rotation);
+            let mut img = image::GrayImage::new(w as u32, h as u32);
+            TEST_FONT.render_rotated("F", 0, 0, rotation, &mut img, image::Luma([0xFF]));
+            assert_eq!(img, expected, "{rotation:?}");
+        }
     }
 
-    /// Computes the width in pixels of this glyph's rendered area.
-    pub fn width_in_pixels(&self) -> usize {
-        self.row_bytes_usize() * 8
+
+    #[test]
+    fn pixels_rebuild_the_bitmap() {
+        let bitmap = [0x12, 0x34, 0xAB, 0xCD, 0x80, 0x01];
+        let glyph = Glyph { row_bytes: 2, image_offset: 0, image_height: 3, ..TEST_GLYPHS[1] };
+        let mut rebuilt = [0u8; 6];
+        let mut count = 0;
+        for (x, y, set) in glyph.pixels(&bitmap, BitmapEncoding::Raw) {
+            assert!(x < 16 && y < 3);
+            if set {
+                rebuilt[y * 2 + x / 8] |= 0x80 >> (x % 8);
+            }
+            count += 1;
+        }
+        assert_eq!(count, 16 * 3);
+        assert_eq!(rebuilt, bitmap);
+
+        // The same goes for the compressed form.
+        let packed = [5, 0x12, 0x34, 0xAB, 0xCD, 0x80, 0x01];
+        let repacked: Vec<bool> = glyph.pixels(&packed, BitmapEncoding::PackBits)
+            .map(|(_, _, set)| set)
+            .collect();
+        let raw: Vec<bool> = glyph.pixels(&bitmap, BitmapEncoding::Raw)
+            .map(|(_, _, set)| set)
+            .collect();
+        assert_eq!(repacked, raw);
     }
 
-    /// Slices this glyph's bitmap out of a shared bitmap slice.
-    ///
-    /// # Panics
-    ///
-    /// If this glyph's offset and size wind up being out of range for `bitmap`,
-    /// which probably means you're attempting to use a `Glyph` from one font
-    /// with a bitmap array from another, or something.
-    pub fn slice_bitmap<'b>(&self, bitmap: &'b [u8]) -> &'b [u8] {
-        let data_off = usize::from(self.image_offset);
-        let height = usize::from(self.image_height);
-        let data_len = self.row_bytes_usize() * height;
-        &bitmap[data_off..data_off + data_len]
+
+    /// Renders `text` justified to `width`, and returns the X position of each
+    /// glyph on each line. (The second row of every glyph in `TEST_FONT` is a
+    /// single pixel at its left edge.)
+    fn justified_xs(text: &str, width: usize) -> Vec<Vec<u32>> {
+        let mut img = image::GrayImage::new(width as u32 + 1, 16);
+        let justify = Justify { width, line_spacing: None };
+        TEST_FONT.render_justified(text, 0, 0, justify, &mut img, image::Luma([0xFF]));
+        [1, 9].iter()
+            .map(|&y| (0..img.width()).filter(|&x| img.get_pixel(x, y).0 == [0xFF]).collect())
+            .collect()
     }
 
-    /// Returns the default horizontal advance for glyphs in this font as a
-    /// `usize`.
-    pub fn default_advance_usize(&self) -> usize {
-        usize::from(self.advance)
+    #[test]
+    fn justified_lines_fill_the_width() {
+        // "ab cd ef" is 48 pixels, leaving 12 to split between two gaps.
+        let xs = justified_xs("ab cd ef gh", 60);
+        assert_eq!(xs[0], [0, 6, 24, 30, 48, 54]);
+        assert_eq!(*xs[0].last().unwrap() as usize + 6, 60);
+        // The paragraph's last line is left alone.
+        assert_eq!(xs[1], [0, 6]);
+
+        // The odd pixel goes to the first gap.
+        let xs = justified_xs("ab cd ef gh", 61);
+        assert_eq!(xs[0], [0, 6, 25, 31, 49, 55]);
+        assert_eq!(*xs[0].last().unwrap() as usize + 6, 61);
     }
 
-    /// Returns the number of bitmap bytes in a row of this glyph's image, as a
-    /// `usize`.
-    pub fn row_bytes_usize(&self) -> usize {
-        usize::from(self.row_bytes)
+
+    #[test]
+    fn caret_at_end_is_width() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', b'V'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        for s in ["", "a", "AVA", "h\u{e9}llo"] {
+            assert_eq!(font.caret_x(s, s.chars().count()), font.width(s), "{s:?}");
+            assert_eq!(font.caret_x(s, 99), font.width(s), "{s:?}");
+        }
+        // Kerning moves the caret before "V" along with it.
+        assert_eq!(font.caret_x("AVA", 1), 4);
+        assert_eq!(font.caret_x("AVA", 2), 10);
     }
-}
 
-/// A kerning table.
-#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
-pub struct KerningTable<'k> {
-    pub entries: &'k [KerningEntry],
-}
+    #[test]
+    fn char_at_x_rounds_to_nearest_caret() {
+        let font = TEST_FONT;
+        assert_eq!(font.char_at_x("abc", 0), 0);
+        assert_eq!(font.char_at_x("abc", 2), 0);
+        // The middle of "b" goes to the caret after it.
+        assert_eq!(font.char_at_x("abc", 9), 2);
+        assert_eq!(font.char_at_x("abc", 10), 2);
+        assert_eq!(font.char_at_x("abc", 8), 1);
+        assert_eq!(font.char_at_x("abc", 500), 3);
+        // Carets inside a ligature are spread across it.
+        let font = ligatured();
+        assert_eq!((font.caret_x("fix", 1), font.caret_x("fix", 2)), (4, 9));
+        assert_eq!(font.char_at_x("fix", 4), 1);
+    }
 
-impl KerningTable<'_> {
-    pub fn get(&self, before: char, after: char) -> Option<&KerningEntry> {
-        // Due to the limited size of the entry, we definitely don't have any
-        // entries for chars outside of ISO8859-1.
-        let before = u8::try_from(before).ok()?;
-        let after = u8::try_from(after).ok()?;
 
-        self.entries.binary_search_by_key(&(before, after), |e| e.pair)
-            .ok()
-            .map(|i| &self.entries[i])
+    #[test]
+    fn padding_bits_are_not_drawn() {
+        // A 9-pixel-wide glyph, two rows tall, with every bit set, padding
+        // included.
+        static GLYPHS: [Glyph; 1] = [Glyph {
+            row_bytes: 2,
+            pixel_width: 9,
+            image_offset: 0,
+            image_height: 2,
+            origin: (0, 0),
+            advance: 10,
+        }];
+        let font = Font {
+            ascent: 2,
+            descent: 0,
+            line_spacing: 2,
+            glyph_storage: GlyphStorage::Dense { first: b'A', glyphs: &GLYPHS },
+            replacement: 0,
+            bitmaps: &[0xFF; 4],
+            ..TEST_FONT
+        };
+        assert_eq!(GLYPHS[0].width_in_pixels(), 9);
+
+        let mut expected = [0; 16 * 2];
+        for row in expected.chunks_mut(16) {
+            row[..9].fill(1);
+        }
+        let mut pixels = [0; 16 * 2];
+        font.render("A", 0, 0, &mut SliceTarget::new(&mut pixels, 16, 16), 1);
+        assert_eq!(pixels, expected);
+        let mut pixels = [0; 16 * 2];
+        font.render_direct("A", 0, 0, &mut SliceTarget::new(&mut pixels, 16, 16), 1);
+        assert_eq!(pixels, expected);
     }
-}
 
-/// An entry in the kerning table.
-#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
-pub struct KerningEntry {
-    /// Sequence of characters that cause this entry to apply. Characters here
-    /// are given by the bottom 8 bits of their codepoint, limiting this to
-    /// ISO8859-1.
-    pub pair: (u8, u8),
-    /// Adjustment to the advance between the two characters given in `pair`.
-    /// Negative values bring the glyphs closer together, positive values move
-    /// them farther apart.
-    pub adjust: i8,
-}
 
-impl KerningEntry {
-    /// Apply the tracking adjustment from this kerning table entry to a
-    /// position represented as a `usize` using saturating arithmetic.
-    #[must_use = "this doesn't adjust in-place"]
-    pub fn adjust_usize(&self, val: usize) -> usize {
-        if self.adjust < 0 {
-            val.saturating_sub(usize::from((-self.adjust) as u8))
-        } else {
-            val.saturating_add(usize::from(self.adjust as u8))
+    #[test]
+    fn outline_rings_a_square() {
+        static GLYPHS: [Glyph; 1] = [Glyph {
+            row_bytes: 1,
+            pixel_width: 2,
+            image_offset: 0,
+            image_height: 2,
+            origin: (0, 0),
+            advance: 3,
+        }];
+        let font = Font {
+            ascent: 2,
+            descent: 0,
+            line_spacing: 2,
+            glyph_storage: GlyphStorage::Dense { first: b'#', glyphs: &GLYPHS },
+            replacement: 0,
+            bitmaps: &[0xC0, 0xC0],
+            ..TEST_FONT
+        };
+        let mut pixels = [0; 5 * 5];
+        font.render_outlined("#", 0, 0, &mut SliceTarget::new(&mut pixels, 5, 5), 1, 2);
+        assert_eq!(pixels, [
+            2, 2, 2, 2, 0,
+            2, 1, 1, 2, 0,
+            2, 1, 1, 2, 0,
+            2, 2, 2, 2, 0,
+            0, 0, 0, 0, 0,
+        ]);
+    }
+
+
+    #[test]
+    fn shadow_falls_down_right() {
+        let mut pixels = [0; 8 * 9];
+        let shadow = Shadow { color: 2, offset: (1, 1) };
+        TEST_FONT.render_shadow("F", 0, 0, &mut SliceTarget::new(&mut pixels, 8, 8), 1, shadow);
+        let at = |x: usize, y: usize| pixels[y * 8 + x];
+        // The ink of the "F"'s top bar is untouched, with the shadow showing
+        // below and right of it.
+        for x in 0..5 {
+            assert_eq!(at(x, 0), 1);
+        }
+        assert_eq!(at(5, 1), 2);
+        assert_eq!(at(1, 7), 2);
+        assert_eq!(at(0, 7), 0);
+        // Every pixel of the "F" itself is still fg.
+        let mut plain = [0; 8 * 9];
+        TEST_FONT.render("F", 0, 0, &mut SliceTarget::new(&mut plain, 8, 8), 1);
+        for (p, q) in pixels.iter().zip(&plain) {
+            if *q == 1 {
+                assert_eq!(*p, 1);
+            }
         }
+
+        // A shadow up and left moves everything right and down to fit.
+        let mut pixels = [0; 8 * 9];
+        let shadow = Shadow { color: 2, offset: (-1, -1) };
+        TEST_FONT.render_shadow("F", 0, 0, &mut SliceTarget::new(&mut pixels, 8, 8), 1, shadow);
+        assert_eq!(pixels[0], 2);
+        assert_eq!(pixels[8 + 1], 1);
     }
-}
 
-pub struct KerningState<'k> {
-    table: KerningTable<'k>,
-    last_char: Option<char>,
-}
+    #[test]
+    fn each_missing_policy() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'a', b'b'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        // The test font has no glyph for the accented char.
+        let text = "a\u{e9}b";
+        assert_eq!(font.width_with_missing(text, MissingPolicy::Replacement), font.width(text));
+        assert_eq!(font.width_with_missing(text, MissingPolicy::Replacement), 18);
+        assert_eq!(font.width_with_missing(text, MissingPolicy::Space(2)), 14);
+        // Skipping the char kerns the chars on either side together.
+        assert_eq!(font.width_with_missing(text, MissingPolicy::Skip), 10);
 
-impl KerningState<'_> {
-    pub fn adjust_usize_for_char(&mut self, c: char, x: &mut usize) {
-        if let Some(prev) = self.last_char.replace(c) {
-            if let Some(entry) = self.table.get(prev, c) {
-                *x = entry.adjust_usize(*x);
+        let draw = |missing| {
+            let mut pixels = [0; 18 * 7];
+            let mut target = SliceTarget::new(&mut pixels, 18, 18);
+            font.render_with_missing(text, 0, 0, &mut target, 1, missing);
+            pixels
+        };
+        let mut reference = [0; 18 * 7];
+        font.render(text, 0, 0, &mut SliceTarget::new(&mut reference, 18, 18), 1);
+        assert_eq!(draw(MissingPolicy::Replacement), reference);
+        // A space draws nothing, and "b" moves left to the end of it.
+        let spaced = draw(MissingPolicy::Space(2));
+        assert_eq!(spaced[6..8], [0, 0]);
+        assert_eq!(spaced[8], 1);
+        // With the char skipped, "b" lands under the kerned pen.
+        let skipped = draw(MissingPolicy::Skip);
+        assert_eq!(skipped[4], 1);
+        assert!(skipped[9..18].iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn wide_kerning_kerns_greek_pairs() {
+        static GLYPHS: [(char, Glyph); 3] = [
+            ('A', TEST_GLYPHS[1]),
+            ('\u{3b1}', TEST_GLYPHS[1]),
+            ('\u{3b2}', TEST_GLYPHS[1]),
+        ];
+        static KERNING: [WideKerningEntry; 2] = [
+            WideKerningEntry { pair: ('A', 'A'), adjust: -1 },
+            WideKerningEntry { pair: ('\u{3b1}', '\u{3b2}'), adjust: -2 },
+        ];
+        let font = Font {
+            glyph_storage: GlyphStorage::Sparse { sorted_glyphs: &GLYPHS },
+            replacement: 0,
+            kerning: Kerning::Wide(WideKerningTable::new(&KERNING)),
+            ..TEST_FONT
+        };
+        assert_eq!(font.kerning.get('\u{3b1}', '\u{3b2}'), Some(-2));
+        assert_eq!(font.kerning.get('\u{3b2}', '\u{3b1}'), None);
+        assert_eq!(font.kerning.len(), 2);
+        assert_eq!(font.width("\u{3b1}\u{3b2}"), 10);
+        assert_eq!(font.width("\u{3b2}\u{3b1}"), 12);
+        // Pairs in ISO8859-1 work from a wide table too.
+        assert_eq!(font.width("AA"), 11);
+    }
+
+    #[test]
+    fn pen_matches_render() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'a', b'b'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        let mut expected = [0; 12 * 16];
+        let mut target = SliceTarget::new(&mut expected, 12, 12);
+        font.render("ab", 0, 0, &mut target, 1);
+        font.render("b", 0, 8, &mut target, 1);
+
+        let mut pixels = [0; 12 * 16];
+        let mut target = SliceTarget::new(&mut pixels, 12, 12);
+        let mut pen = font.start_pen(0, 0);
+        for c in "ab\nb".chars() {
+            font.render_char(&mut pen, c, &mut target, 1);
+        }
+        assert_eq!(pixels, expected);
+        assert_eq!((pen.x, pen.y), (6, 8));
+    }
+
+    #[test]
+    fn subpixel_steps_are_monotonic() {
+        assert_eq!(TEST_FONT.width_subpixel("ab"), 12 * SUBPIXELS);
+        let mut last = 0;
+        for step in 0..=2 * SUBPIXELS {
+            let mut pixels = [0; 16 * 7];
+            let mut target = SliceTarget::new(&mut pixels, 16, 16);
+            TEST_FONT.render_offset("ab", step, 0, &mut target, 1);
+            // The left edges of both "F"s, from the top row.
+            let edges: Vec<usize> = (0..16)
+                .filter(|&x| pixels[x] == 1 && (x == 0 || pixels[x - 1] == 0))
+                .collect();
+            assert_eq!(edges.len(), 2, "step {step}");
+            // The glyphs keep their spacing, and never move left.
+            assert_eq!(edges[1] - edges[0], 6, "step {step}");
+            assert!(edges[0] >= last, "step {step}");
+            assert_eq!(edges[0], (step + SUBPIXELS / 2) / SUBPIXELS, "step {step}");
+            last = edges[0];
+        }
+        assert_eq!(last, 2);
+    }
+
+    #[test]
+    fn ink_bounds_of_lower_right_quadrant() {
+        // An 8x4 image with ink only in its lower right 4x2.
+        let bitmap = [0x00, 0x00, 0x0C, 0x03];
+        let glyph = Glyph { image_offset: 0, image_height: 4, ..TEST_GLYPHS[1] };
+        assert_eq!(glyph.ink_bounds(&bitmap, BitmapEncoding::Raw), Some((4, 2, 7, 3)));
+        assert_eq!(glyph.ink_bounds(&[0; 4], BitmapEncoding::Raw), None);
+        assert_eq!(TEST_GLYPHS[0].ink_bounds(&bitmap, BitmapEncoding::Raw), None);
+    }
+
+    #[test]
+    fn rendered_image_is_measured_size() {
+        let text = "ab\nabc";
+        assert_eq!(TEST_FONT.measure(text, LayoutOptions::default()), (18, 16));
+        let img = TEST_FONT.render_to_image(text, 0xFF, 0, LayoutOptions::default());
+        assert_eq!(img.dimensions(), (18, 16));
+        assert_eq!(img.get_pixel(12, 8).0, [0xFF]);
+        assert_eq!(img.get_pixel(12, 0).0, [0]);
+
+        // Text with no size still makes an image to hold it.
+        assert_eq!(TEST_FONT.measure("", LayoutOptions::default()), (0, 0));
+        assert_eq!(TEST_FONT.render_to_image("", 0xFF, 0, LayoutOptions::default()).dimensions(), (1, 1));
+        assert_eq!(TEST_FONT.render_to_image(" ", 0xFF, 0, LayoutOptions::default()).dimensions(), (6, 8));
+    }
+
+    #[test]
+    fn custom_line_spacing() {
+        let text = "ab\nab";
+        let spaced = LayoutOptions { line_spacing: Some(4), ..LayoutOptions::default() };
+        let ys: Vec<usize> = TEST_FONT.lines_layout(text, spaced).map(|l| l.y).collect();
+        assert_eq!(ys, [0, 4]);
+        // The last line still gets its full height.
+        assert_eq!(TEST_FONT.measure(text, spaced), (12, 12));
+        let img = TEST_FONT.render_to_image(text, 0xFF, 0, spaced);
+        assert_eq!(img.dimensions(), (12, 12));
+        // The second line's top bar is on row 4, over the first line's stem.
+        assert_eq!(img.get_pixel(4, 4).0, [0xFF]);
+        assert_eq!(img.get_pixel(4, 3).0, [0]);
+        assert_eq!(img.get_pixel(0, 10).0, [0xFF]);
+    }
+
+    #[test]
+    fn rgba_ink_is_opaque() {
+        let mut img = image::RgbaImage::new(6, 8);
+        TEST_FONT.render_rgba("F", 0, 0, &mut img, image::Rgb([10, 20, 30]));
+        let mut mask = image::GrayImage::new(6, 8);
+        TEST_FONT.render("F", 0, 0, &mut mask, image::Luma([1]));
+        for (p, m) in img.pixels().zip(mask.pixels()) {
+            if m.0 == [1] {
+                assert_eq!(p.0, [10, 20, 30, 0xFF]);
+            } else {
+                assert_eq!(p.0, [0; 4]);
             }
         }
+        assert_eq!(img.get_pixel(0, 0).0[3], 0xFF);
+        assert_eq!(img.get_pixel(5, 0).0[3], 0);
     }
-}
 
-pub trait RenderTarget {
-    type Pixel: Copy + 'static;
+    #[test]
+    fn advance_bias_applies_to_every_glyph() {
+        let biased = |advance_bias| Font { advance_bias, ..TEST_FONT };
+        assert_eq!(TEST_FONT.width("abc"), 18);
+        assert_eq!(biased(1).width("abc"), 21);
+        assert_eq!(biased(1).char_width('a'), 7);
+        assert_eq!(biased(-2).width("abc"), 12);
+        // Advances saturate at zero rather than going backward.
+        assert_eq!(biased(-10).width("abc"), 0);
+    }
 
-    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: Self::Pixel);
-}
+    #[test]
+    fn empties_draw_nothing_but_advance() {
+        assert!(!Glyph::EMPTY.has_image());
+        assert!(!Glyph::blank(4).has_image());
+        assert_eq!(Glyph::blank(4).advance, 4);
 
-#[cfg(feature = "std")]
-impl<P, C> RenderTarget for image::ImageBuffer<P, C>
-    where P: Copy + image::Pixel + 'static,
-          C: core::ops::Deref<Target = [P::Subpixel]> + core::ops::DerefMut,
-{
-    type Pixel = P;
-    fn put_pixel_slow(&mut self, x: usize, y: usize, pixel: P) {
-        let x = u32::try_from(x).unwrap();
-        let y = u32::try_from(y).unwrap();
-        if x < self.width() && y < self.height() {
-            self.put_pixel(x, y, pixel);
+        static GLYPHS: [Glyph; 2] = [Glyph::blank(4), Glyph::EMPTY];
+        let font = Font {
+            glyph_storage: GlyphStorage::Dense { first: b'a', glyphs: &GLYPHS },
+            replacement: 0,
+            ..TEST_FONT
+        };
+        assert_eq!(font.width("aaba"), 12);
+        let mut pixels = [0; 12 * 8];
+        font.render("aaba", 0, 0, &mut SliceTarget::new(&mut pixels, 12, 12), 1);
+        assert!(pixels.iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn suffix_is_measured_alone() {
+        static KERNING: [KerningEntry; 2] = [
+            KerningEntry { pair: (b'a', b'b'), adjust: -2 },
+            KerningEntry { pair: (b'b', b'c'), adjust: -1 },
+        ];
+        let font = kerned(&KERNING);
+        assert_eq!(font.width_suffix("abc", 1), font.width("bc"));
+        assert_eq!(font.width_suffix("abc", 1), 11);
+        // Within the whole string, "b" is pulled left toward "a".
+        assert_eq!(font.width("abc") - font.width("a"), 9);
+        assert_eq!(font.width_suffix("abc", 0), font.width("abc"));
+        assert_eq!(font.width_suffix("\u{e9}bc", 1), 11);
+        assert_eq!(font.width_suffix("abc", 3), 0);
+        assert_eq!(font.width_suffix("abc", 10), 0);
+    }
+
+    #[test]
+    fn render_until_clips_the_straddling_glyph() {
+        let mut pixels = [0; 18 * 7];
+        let mut target = SliceTarget::new(&mut pixels, 18, 18);
+        // The second "F" starts at 6 and straddles 8.
+        assert_eq!(TEST_FONT.render_until("abc", 0, 0, 8, &mut target, 1), 2);
+        assert_eq!(pixels[..12], [1, 1, 1, 1, 1, 0, 1, 1, 0, 0, 0, 0]);
+        assert!(pixels[18..].chunks(18).all(|row| row[8..].iter().all(|&p| p == 0)));
+
+        // A glyph that would start right at `stop_x` isn't drawn at all.
+        let mut target = SliceTarget::new(&mut pixels, 18, 18);
+        assert_eq!(TEST_FONT.render_until("abc", 0, 0, 6, &mut target, 1), 1);
+        let mut target = SliceTarget::new(&mut pixels, 18, 18);
+        assert_eq!(TEST_FONT.render_until("abc", 0, 0, 100, &mut target, 1), 3);
+    }
+
+    #[test]
+    fn trailing_newline_modes() {
+        let font = TEST_FONT;
+        let ends = LayoutOptions::default();
+        let starts = LayoutOptions { newline: TrailingNewline::StartsLine, ..ends };
+        assert_eq!(font.measure("a\n", ends), (6, 8));
+        assert_eq!(font.measure("a\n", starts), (6, 16));
+        assert_eq!(font.measure("a", starts), (6, 8));
+        assert_eq!(font.measure("", ends), (0, 0));
+        assert_eq!(font.measure("", starts), (0, 8));
+        let ys: [usize; 2] = [0, 8];
+        assert!(font.lines_layout("a\n", starts).map(|l| l.y).eq(ys));
+        assert_eq!(font.lines_layout("a\n", starts).last().unwrap().text, "");
+        assert_eq!(font.render_to_image("a\n", 0xFF, 0, starts).dimensions(), (6, 16));
+        // An override spaces the lines, but the last is still a full line tall.
+        let tight = LayoutOptions { line_spacing: Some(4), ..starts };
+        assert_eq!(font.measure("a\n", tight), (6, 12));
+    }
+
+    #[test]
+    fn kerning_introspection() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', b'V'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        assert_eq!(font.kern('A', 'V'), Some(-2));
+        assert_eq!(font.kern('V', 'A'), None);
+        assert_eq!(font.kern('A', '\u{3b1}'), None);
+        assert!(font.kerning_pairs().eq([(('A', 'V'), -2)]));
+        assert_eq!(TEST_FONT.kerning_pairs().count(), 0);
+
+        static WIDE: [WideKerningEntry; 1] = [WideKerningEntry { pair: ('A', '\u{3b1}'), adjust: 1 }];
+        let font = Font { kerning: Kerning::Wide(WideKerningTable::new(&WIDE)), ..TEST_FONT };
+        assert_eq!(font.kern('A', '\u{3b1}'), Some(1));
+        assert!(font.kerning_pairs().eq([(('A', '\u{3b1}'), 1)]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn mirrored_block_matches_flipped_image() {
+        let font = TEST_FONT;
+        let text = "Hi,\nthere!";
+        let expected = image::imageops::flip_horizontal(
+            &font.render_to_image(text, 0xFF, 0, LayoutOptions::default()),
+        );
+        let mut img = image::GrayImage::new(expected.width(), expected.height());
+        font.render_mirrored_block(text, 0, 0, &mut img, image::Luma([0xFF]), None);
+        assert_eq!(img, expected);
+
+        // Nothing to draw, and nothing drawn.
+        let mut img = image::GrayImage::new(4, 4);
+        font.render_mirrored_block("", 0, 0, &mut img, image::Luma([0xFF]), None);
+        assert_eq!(img, image::GrayImage::new(4, 4));
+    }
+
+    #[test]
+    fn xor_twice_restores_target() {
+        let font = TEST_FONT;
+        // A background that isn't blank, so that XOR has something to undo.
+        let mut pixels = [0u8; 16 * 8];
+        for (i, p) in pixels.iter_mut().enumerate() {
+            *p = (i * 37) as u8;
         }
+        let original = pixels;
+        let mut target = SliceTarget::new(&mut pixels, 16, 16);
+        font.render_blended("Ab", 1, 0, &mut target, 0xA5, BlendMode::Xor);
+        let once = pixels;
+        assert_ne!(once, original);
+        let mut target = SliceTarget::new(&mut pixels, 16, 16);
+        font.render_blended("Ab", 1, 0, &mut target, 0xA5, BlendMode::Xor);
+        assert_eq!(pixels, original);
+
+        let mut mono = [true; 16 * 8];
+        let mut target = SliceTarget::new(&mut mono, 16, 16);
+        font.render_blended("Ab", 1, 0, &mut target, true, BlendMode::Xor);
+        assert!(mono.contains(&false));
+        let mut target = SliceTarget::new(&mut mono, 16, 16);
+        font.render_blended("Ab", 1, 0, &mut target, true, BlendMode::Xor);
+        assert_eq!(mono, [true; 16 * 8]);
     }
-}
 
-pub trait DirectRenderTarget {
-    type Pixel: Copy + 'static;
+    #[cfg(feature = "std")]
+    #[test]
+    fn xor_twice_restores_image() {
+        let font = TEST_FONT;
+        let original = image::RgbImage::from_fn(16, 8, |x, y| image::Rgb([x as u8, y as u8, 7]));
+        let mut img = original.clone();
+        let fg = image::Rgb([0xFF, 0x0F, 0x01]);
+        font.render_blended("Ab", 1, 0, &mut img, fg, BlendMode::Xor);
+        assert_ne!(img, original);
+        font.render_blended("Ab", 1, 0, &mut img, fg, BlendMode::Xor);
+        assert_eq!(img, original);
+    }
 
-    fn subrow_mut(&mut self, y: usize, x: core::ops::Range<usize>) -> &mut [Self::Pixel];
-}
+    #[test]
+    fn counted_render_skips_offscreen_glyphs() {
+        let mut pixels = [false; 14 * 8];
+        let mut target = SliceTarget::new(&mut pixels, 14, 14);
+        // 'B' at x = 12 straddles the right edge, and 'C' at 18 is past it.
+        let stats = TEST_FONT.render_counted("A BC", 0, 0, &mut target, true, (14, 8));
+        assert_eq!(stats, RenderStats { drawn: 2, blank: 1, clipped: 1 });
+        assert!(pixels.chunks(14).all(|row| !row[5] && row[12] == row[0]));
 
-#[cfg(feature = "std")]
-impl<P, C> DirectRenderTarget for image::ImageBuffer<image::Luma<P>, C>
-    where P: Copy + image::Primitive + 'static,
-          C: core::ops::Deref<Target = [P]> + core::ops::DerefMut + AsMut<[P]>,
-{
-    type Pixel = image::Luma<P>;
+        let mut target = SliceTarget::new(&mut pixels, 14, 14);
+        let stats = TEST_FONT.render_counted("AB", 0, 8, &mut target, true, (14, 8));
+        assert_eq!(stats, RenderStats { drawn: 0, blank: 0, clipped: 2 });
+    }
 
-    fn subrow_mut(&mut self, y: usize, x: core::ops::Range<usize>) -> &mut [Self::Pixel] {
-        let flat = self.as_flat_samples_mut();
-        let row_i = y as usize * flat.layout.width as usize;
-        let row = &mut flat.samples[row_i..row_i + flat.layout.width as usize];
+    #[test]
+    fn dense_storage_does_not_wrap() {
+        static GLYPHS: [Glyph; 10] = [Glyph::blank(1); 10];
+        let storage = GlyphStorage::Dense { first: 250, glyphs: &GLYPHS };
+        // 250 through 259 are in the table, including the ones past 255.
+        for i in 0..10 {
+            let c = char::from_u32(250 + i).unwrap();
+            assert_eq!(storage.index_of(c), Some(i as usize));
+        }
+        assert_eq!(storage.index_of('\u{104}'), None);
+        // Chars below `first`, which would wrap around into the table under
+        // modular arithmetic, are not.
+        for c in ['\0', '\u{5}', '\u{9}', 'A', '\u{f9}'] {
+            assert_eq!(storage.index_of(c), None, "{:?}", c);
+            assert!(storage.get(c).is_none());
+        }
+    }
 
-        let x_start = usize::min(x.start as usize, flat.layout.width as usize);
-        let x_end = usize::min(x.end as usize, flat.layout.width as usize);
-        let subpixels = &mut row[x_start..x_end];
-        // The reason this is only defined for Luma is so that I know it's a
-        // single-channel image, and I can do this:
-        unsafe {
-            &mut *(subpixels as *mut [P] as *mut [image::Luma<P>])
+    #[test]
+    fn glyph_index_renders_like_its_char() {
+        // The built-in font, for glyphs that actually differ.
+        let font = DEFAULT_FONT;
+        let render = |draw: &dyn Fn(&mut SliceTarget<'_, bool>)| {
+            let mut pixels = [false; 8 * 10];
+            draw(&mut SliceTarget::new(&mut pixels, 8, 8));
+            pixels
+        };
+        let by_index = render(&|t| assert!(font.render_glyph_index(3, 1, 2, t, true)));
+        assert_eq!(font.glyph_storage.index_of('#'), Some(3));
+        assert_eq!(by_index, render(&|t| font.render("#", 1, 2, t, true)));
+        assert_ne!(by_index, render(&|t| font.render("$", 1, 2, t, true)));
+
+        assert!(!font.render_glyph_index(95, 1, 2, &mut SliceTarget::new(&mut [false; 64], 8, 8), true));
+    }
+
+    #[test]
+    fn cells_are_filled_full_height() {
+        // An "F" drawn two rows down, so that the cell has rows of background
+        // above the ink as well as below it.
+        static GLYPHS: [Glyph; 1] = [Glyph { origin: (0, 2), ..TEST_GLYPHS[1] }];
+        let font = Font {
+            line_spacing: 10,
+            glyph_storage: GlyphStorage::Dense { first: b'F', glyphs: &GLYPHS },
+            replacement: 0,
+            ..TEST_FONT
+        };
+        let mut pixels = [7u8; 16 * 12];
+        let mut target = SliceTarget::new(&mut pixels, 16, 16);
+        font.render_cells("FF", 1, 1, &mut target, 0xFF, 1);
+        for (y, row) in pixels.chunks(16).enumerate() {
+            for (x, &p) in row.iter().enumerate() {
+                let in_cell = (1..13).contains(&x) && (1..11).contains(&y);
+                let ink = (3..10).contains(&y) && (1..13).contains(&x)
+                    && TEST_FONT.bitmaps[y - 3] & (0x80 >> ((x - 1) % 6)) != 0;
+                let expected = if ink { 0xFF } else if in_cell { 1 } else { 7 };
+                assert_eq!(p, expected, "at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn signed_kerning_goes_negative() {
+        let entry = KerningEntry { pair: (b'A', b'V'), adjust: -2 };
+        assert_eq!(entry.adjust_i32(-3), -5);
+        assert_eq!(entry.adjust_usize(1), 0);
+        let wide = WideKerningEntry { pair: ('A', '\u{3b1}'), adjust: -2 };
+        assert_eq!(wide.adjust_i32(-3), -5);
+
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', b'V'), adjust: -2 }];
+        let mut state = kerned(&KERNING).start_kerning();
+        let mut x = -10;
+        state.adjust_i32_for_char('A', &mut x);
+        assert_eq!(x, -10);
+        x += 6;
+        state.adjust_i32_for_char('V', &mut x);
+        assert_eq!(x, -6);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn luma_layout_matches_subpixel() {
+        use core::mem::{align_of, size_of};
+        fn check<P>() {
+            assert_eq!(size_of::<image::Luma<P>>(), size_of::<P>());
+            assert_eq!(align_of::<image::Luma<P>>(), align_of::<P>());
+        }
+        check::<u8>();
+        check::<u16>();
+        check::<u32>();
+        check::<f32>();
+
+        // And the reinterpreted row lands on the right samples.
+        let mut img = image::ImageBuffer::<image::Luma<u16>, _>::new(4, 2);
+        img.subrow_mut(1, 1..3).fill(image::Luma([0xBEEF]));
+        assert_eq!(img.as_raw()[..], [0, 0, 0, 0, 0, 0xBEEF, 0xBEEF, 0]);
+    }
+
+    #[test]
+    fn debug_render_draws_each_line() {
+        let font = TEST_FONT;
+        assert_eq!(font.debug_render("F\nFF"), "\
+|*****       |
+|*           |
+|****        |
+|*           |
+|*           |
+|*           |
+|*           |
+|            |
+|***** ***** |
+|*     *     |
+|****  ****  |
+|*     *     |
+|*     *     |
+|*     *     |
+|*     *     |
+|            |
+");
+        assert_eq!(font.debug_render(""), "");
+    }
+
+    #[test]
+    fn tight_image_is_cropped_to_ink() {
+        // The built-in font, for an "o" shorter than the line.
+        let font = DEFAULT_FONT;
+        let glyph = font.glyph_storage.get('o').unwrap();
+        let (x0, y0, x1, y1) = glyph.ink_bounds(font.bitmaps, font.bitmap_encoding).unwrap();
+        let ink = ((x1 - x0 + 1) as u32, (y1 - y0 + 1) as u32);
+        assert!(ink.1 < u32::from(font.line_spacing));
+
+        let img = font.render_to_image_tight("o", 0xFF, 0, LayoutOptions::default(), 0);
+        assert_eq!(img.dimensions(), ink);
+        assert!(img.rows().all(|row| row.clone().any(|p| p.0[0] == 0xFF)));
+
+        let img = font.render_to_image_tight("o", 0xFF, 0, LayoutOptions::default(), 1);
+        assert_eq!(img.dimensions(), (ink.0 + 2, ink.1 + 2));
+        assert!(img.rows().next().unwrap().all(|p| p.0[0] == 0));
+
+        let blank = font.render_to_image_tight(" ", 0xFF, 0, LayoutOptions::default(), 1);
+        assert_eq!(blank.dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn substring_lands_where_the_full_line_puts_it() {
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', b'V'), adjust: -2 }];
+        let font = kerned(&KERNING);
+        let render = |draw: &dyn Fn(&mut SliceTarget<'_, bool>)| {
+            let mut pixels = [false; 20 * 8];
+            draw(&mut SliceTarget::new(&mut pixels, 20, 20));
+            pixels
+        };
+        // "V" is drawn kerned against the "A" before it, which isn't drawn.
+        let part = render(&|t| font.render_substring("AVA", 1..2, 1, 0, t, true));
+        assert_eq!(part, render(&|t| font.render("V", 5, 0, t, true)));
+        let whole = render(&|t| font.render("AVA", 1, 0, t, true));
+        assert!(part.iter().zip(&whole).all(|(&p, &w)| !p || w));
+
+        let none = render(&|t| font.render_substring("AVA", 3..3, 1, 0, t, true));
+        assert_eq!(none, [false; 20 * 8]);
+    }
+
+    #[test]
+    fn collapsed_wrap_measures_like_single_spaces() {
+        let font = TEST_FONT;
+        let lines = font.wrap_collapsed("a    b", 100);
+        assert_eq!(lines, ["a b"]);
+        assert_eq!(font.width(&lines[0]), font.width("a b"));
+        // Tabs collapse too, and newlines still break, keeping blank lines.
+        assert_eq!(font.wrap_collapsed("a \t b\n\nc", 100), ["a b", "", "c"]);
+        assert_eq!(font.wrap_collapsed("aa  bb  cc", 12), ["aa", "bb", "cc"]);
+    }
+
+    #[test]
+    fn storage_introspection() {
+        assert_eq!(TEST_FONT.glyph_count(), 95);
+        assert_eq!(TEST_FONT.char_range(), Some((' ', '~')));
+        assert!(TEST_FONT.covers('~') && !TEST_FONT.covers('\u{7f}'));
+
+        static SPARSE: [(char, Glyph); 2] = [('A', TEST_GLYPHS[1]), ('\u{3b1}', TEST_GLYPHS[1])];
+        let sparse = Font { glyph_storage: GlyphStorage::Sparse { sorted_glyphs: &SPARSE }, ..TEST_FONT };
+        assert_eq!(sparse.glyph_count(), 2);
+        assert_eq!(sparse.char_range(), None);
+        assert!(sparse.covers('\u{3b1}') && !sparse.covers('B'));
+
+        // Two runs sharing three glyphs, plus one that no char maps to.
+        static RANGES: [(char, u16, u16); 2] = [('A', 3, 0), ('a', 3, 0)];
+        let glyph_storage = GlyphStorage::Ranges { ranges: &RANGES, glyphs: &TEST_GLYPHS[..4] };
+        let ranged = Font { glyph_storage, ..TEST_FONT };
+        assert_eq!(ranged.glyph_count(), 4);
+        assert_eq!(ranged.char_range(), None);
+        assert!(ranged.covers('C') && ranged.covers('c') && !ranged.covers('d'));
+
+        let empty = Font { glyph_storage: GlyphStorage::Dense { first: b'A', glyphs: &[] }, ..TEST_FONT };
+        assert_eq!((empty.glyph_count(), empty.char_range()), (0, None));
+    }
+
+    #[test]
+    fn accented_chars_share_a_kerning_class() {
+        // 'A' and its accented forms are left class 1, and 'V' is right class
+        // 1, with an exception for 'Â'.
+        static CLASSES: [(char, u8, u8); 4] = [('A', 1, 0), ('V', 0, 1), ('\u{c1}', 1, 0), ('\u{c2}', 1, 0)];
+        static EXCEPTIONS: [WideKerningEntry; 1] = [WideKerningEntry { pair: ('\u{c2}', 'V'), adjust: -1 }];
+        let table = ClassKerningTable {
+            pairs: WideKerningTable::new(&EXCEPTIONS),
+            classes: &CLASSES,
+            right_classes: 2,
+            matrix: &[0, 0, 0, -2],
+        };
+        assert_eq!(table.get('A', 'V'), Some(-2));
+        assert_eq!(table.get('\u{c1}', 'V'), Some(-2));
+        assert_eq!(table.get('\u{c2}', 'V'), Some(-1));
+        assert_eq!(table.get('V', 'A'), None);
+        assert_eq!(table.get('B', 'V'), None);
+
+        let font = Font { kerning: Kerning::Class(table), ..TEST_FONT };
+        assert_eq!(font.width("\u{c1}V"), font.width("AV"));
+        assert_eq!(font.width("BV"), font.width("AV") + 2);
+        assert_eq!(font.kerning.len(), 3);
+        assert!(font.kerning_pairs().eq([
+            (('A', 'V'), -2),
+            (('\u{c1}', 'V'), -2),
+            (('\u{c2}', 'V'), -1),
+        ]));
+    }
+
+    #[test]
+    fn half_coverage_blends_in_linear_light() {
+        let mut pixels = [0u8; 8 * 4];
+        let mut target = SliceTarget::new(&mut pixels, 8, 8);
+        let aa = Antialias { scale: 2, bg: 0 };
+        TEST_FONT.render_aa_gamma("F", 1, 0, &mut target, 0xFF, aa);
+        // The top of the "F" is five pixels wide, over two rows, the second
+        // with only the stem. So the first 2x2 square is three-quarters
+        // covered, the second half covered, and the third a quarter covered.
+        let expected = |coverage: f64| {
+            let srgb = 1.055 * coverage.powf(1. / 2.4) - 0.055;
+            (srgb * 255.).round() as u8
+        };
+        assert_eq!(expected(0.5), 188);
+        assert_eq!(pixels[1..4], [expected(0.75), expected(0.5), expected(0.25)]);
+        assert_eq!(pixels[4], 0);
+        // Blending over a background of the same level as the text changes
+        // nothing, however much of it is covered.
+        let mut pixels = [0x40u8; 8 * 4];
+        let mut target = SliceTarget::new(&mut pixels, 8, 8);
+        TEST_FONT.render_aa_gamma("F", 1, 0, &mut target, 0x40, Antialias { scale: 2, bg: 0x40 });
+        assert_eq!(pixels, [0x40; 8 * 4]);
+    }
+
+    #[test]
+    fn paragraph_lines_and_height() {
+        let font = TEST_FONT;
+        let mut pixels = [false; 12 * 32];
+        let ink_rows = |pixels: &[bool]| -> Vec<usize> {
+            (0..32).filter(|&y| pixels[y * 12..][..12].contains(&true)).collect()
+        };
+
+        let paragraph = Paragraph { max_width: 12, line_spacing: None };
+        let mut target = SliceTarget::new(&mut pixels, 12, 12);
+        assert_eq!(font.render_paragraph("aa bb cc", 0, 0, paragraph, &mut target, true), 24);
+        assert_eq!(font.wrap("aa bb cc", 12).count(), 3);
+        // Each "F" is seven rows tall, on lines 8 pixels apart.
+        let expected: Vec<usize> = [0, 8, 16].into_iter().flat_map(|y| y..y + 7).collect();
+        assert_eq!(ink_rows(&pixels), expected);
+
+        let mut pixels = [false; 12 * 32];
+        let paragraph = Paragraph { max_width: 12, line_spacing: Some(10) };
+        let mut target = SliceTarget::new(&mut pixels, 12, 12);
+        assert_eq!(font.render_paragraph("aa bb cc", 0, 1, paragraph, &mut target, true), 30);
+        let expected: Vec<usize> = [1, 11, 21].into_iter().flat_map(|y| y..y + 7).collect();
+        assert_eq!(ink_rows(&pixels), expected);
+
+        // Too narrow for any glyph, so each char gets a line.
+        let paragraph = Paragraph { max_width: 3, line_spacing: None };
+        let mut target = SliceTarget::new(&mut pixels, 12, 12);
+        assert_eq!(font.render_paragraph("ab", 0, 0, paragraph, &mut target, true), 16);
+        assert_eq!(font.render_paragraph("", 0, 0, paragraph, &mut target, true), 0);
+    }
+
+    #[test]
+    fn caret_is_drawn_at_caret_x() {
+        // Extra space between lines, which the caret doesn't cover.
+        let font = Font { line_spacing: 10, ..TEST_FONT };
+        for (char_index, column) in [(0, 1), (2, 13), (3, 19), (9, 19)] {
+            let mut pixels = [false; 24 * 12];
+            let mut target = SliceTarget::new(&mut pixels, 24, 24);
+            font.render_caret("abc", char_index, 1, 2, &mut target, true);
+            assert_eq!(1 + font.caret_x("abc", char_index), column);
+            let set: Vec<_> = (0..pixels.len()).filter(|&i| pixels[i]).map(|i| (i % 24, i / 24)).collect();
+            let bar: Vec<_> = (2..10).map(|y| (column, y)).collect();
+            assert_eq!(set, bar, "caret before char {char_index}");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn arbitrary_rotation_matches_quarter_turn() {
+        let font = DEFAULT_FONT;
+        // A bar of underscores, with some letters to tell the ends apart.
+        let text = "T___y";
+        let height = f32::from(font.ascent + font.descent);
+        let blank = image::GrayImage::new(24, 40);
+        let ink = image::Luma([0xFF]);
+
+        let mut exact = blank.clone();
+        font.render_rotated(text, 3, 2, Rotation::Cw90, &mut exact, ink);
+        let mut sampled = blank.clone();
+        let pivot = (height / 2., height / 2.);
+        let turn = Turn { radians: core::f32::consts::FRAC_PI_2, pivot };
+        font.render_rotated_arbitrary(text, 3, 2, turn, &mut sampled, ink);
+        // Rounding in the sampling can move a stray pixel, but no more.
+        let set = exact.pixels().filter(|p| p.0[0] != 0).count();
+        let wrong = exact.pixels().zip(sampled.pixels()).filter(|(a, b)| a != b).count();
+        assert!(set > 0);
+        assert!(wrong * 50 <= set, "{} of {} pixels differ", wrong, set);
+
+        // Without rotation, the pivot doesn't matter, and nothing moves.
+        let mut plain = blank.clone();
+        font.render(text, 3, 2, &mut plain, ink);
+        let mut unrotated = blank.clone();
+        let turn = Turn { radians: 0., pivot: (5., 1.) };
+        font.render_rotated_arbitrary(text, 3, 2, turn, &mut unrotated, ink);
+        assert_eq!(unrotated, plain);
+    }
+
+    #[test]
+    fn standalone_kerning_matches_font() {
+        static KERNING: [KerningEntry; 3] = [
+            KerningEntry { pair: (b'A', b'V'), adjust: -2 },
+            KerningEntry { pair: (b'T', b'o'), adjust: -1 },
+            KerningEntry { pair: (b'V', b'A'), adjust: -3 },
+        ];
+        let font = kerned(&KERNING);
+        let text = "AVATox";
+
+        let mut state = KerningState::new(Kerning::Narrow(KerningTable::new(&KERNING)));
+        let mut x = 0;
+        for (i, c) in text.chars().enumerate() {
+            state.adjust_usize_for_char(c, &mut x);
+            assert_eq!(x, font.caret_x(text, i), "before {:?}", c);
+            x += font.char_width(c);
         }
+        assert_eq!(x, font.width(text));
+        assert_eq!(state.last_char(), Some('x'));
+
+        // After a reset, nothing is kerned against what came before.
+        let mut state = KerningState::new(font.kerning);
+        let mut x = 6;
+        state.adjust_usize_for_char('A', &mut x);
+        assert_eq!(state.peek('V'), Some(-2));
+        state.reset();
+        assert_eq!((state.last_char(), state.peek('V')), (None, None));
+        state.adjust_usize_for_char('V', &mut x);
+        assert_eq!(x, 6);
     }
 }
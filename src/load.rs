@@ -1,22 +1,137 @@
 use crate::*;
+use std::collections::HashMap;
+use std::fmt;
 use std::io::{BufRead, Seek};
-use image::Rgb;
+use image::{Pixel, Rgb, Rgba};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, clap::ArgEnum)]
-pub enum GlyphOrderArg {
-    Iso8859_1,
-    Cp437,
+/// Ways that loading a font from an image can fail.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The input couldn't be read.
+    Io(std::io::Error),
+    /// The image couldn't be read or decoded.
+    Image(image::ImageError),
+    /// The image doesn't contain any bands (no red separator lines in the
+    /// left margin).
+    NoBands,
+    /// A band has no blue baseline marker. The band starts at row `band_top`.
+    MissingBaseline { band_top: u32 },
+    /// A band has more than one row containing a blue baseline marker. The
+    /// band starts at row `band_top`.
+    AmbiguousBaseline { band_top: u32 },
+    /// A glyph is wider than the loader can handle. The glyph's left edge is
+    /// at `(x, y)`.
+    GlyphTooWide { x: u32, y: u32, width: u32 },
+    /// A glyph is too big to load: glyphs can be at most 255 pixels tall,
+    /// with an advance of at most 255 pixels, and `FontBuilder` glyphs can be
+    /// at most 64 pixels wide. `index` is the glyph's position in the input
+    /// (for `FontBuilder`, the number of glyphs added before it), and `ch` is
+    /// the char it stands for, if that's known when the problem is found.
+    GlyphTooLarge { index: usize, ch: Option<char>, width: usize, height: usize },
+    /// Some font metric or glyph measurement doesn't fit in the field that
+    /// stores it.
+    OutOfRange(&'static str),
+    /// An explicit glyph order was given, but it contains no chars.
+    EmptyGlyphOrder,
+    /// No `first` was given, and we couldn't infer it from the pattern of
+    /// blank glyphs (whose indices are included).
+    AmbiguousBlanks(Vec<usize>),
+    /// The glyph separator at `(x, y)` contains more than one kerning mark.
+    AmbiguousKerning { x: u32, y: u32 },
+    /// A kerning mark was found after glyph index `index`, but either there's
+    /// no following glyph, or one of the glyphs doesn't map to a char.
+    BadKerningPair { index: usize },
+    /// A BDF file is malformed. `line` is the 1-based line number where the
+    /// problem was noticed.
+    Bdf { line: usize, what: &'static str },
+    /// A BMFont descriptor is malformed. `line` is the 1-based line number
+    /// where the problem was noticed.
+    BmFont { line: usize, what: &'static str },
+    /// The `GridSpec` given in `LoadOptions` doesn't describe the image.
+    BadGrid(&'static str),
+    /// The replacement char given in `LoadOptions` has no glyph in the font.
+    MissingReplacement(char),
+    /// `LoadOptions::leading_separator` is set, but the top left pixel of the
+    /// sheet isn't a band separator.
+    MissingLeadingSeparator,
+    /// `LoadOptions::keep_blanks` is set, but no `first` was given.
+    MissingFirst,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Image(e) => write!(f, "can't load image: {e}"),
+            Self::NoBands => f.write_str("image contains no bands"),
+            Self::MissingBaseline { band_top } => {
+                write!(f, "missing baseline in band starting at y={band_top}")
+            }
+            Self::AmbiguousBaseline { band_top } => {
+                write!(f, "ambiguous baseline in band starting at y={band_top}")
+            }
+            Self::GlyphTooWide { x, y, width } => {
+                write!(f, "glyph at ({x}, {y}) is too wide ({width} pixels)")
+            }
+            Self::GlyphTooLarge { index, ch, width, height } => {
+                write!(f, "glyph {index} ")?;
+                if let Some(c) = ch {
+                    write!(f, "({c:?}) ")?;
+                }
+                write!(f, "is too large ({width}x{height} pixels)")
+            }
+            Self::OutOfRange(what) => write!(f, "{what} out of range"),
+            Self::EmptyGlyphOrder => f.write_str("explicit glyph order is empty"),
+            Self::AmbiguousBlanks(blanks) => write!(
+                f,
+                "can't detect the first char from the blank glyphs at indices \
+                {blanks:?}; give the first char explicitly",
+            ),
+            Self::AmbiguousKerning { x, y } => {
+                write!(f, "more than one kerning mark in separator at ({x}, {y})")
+            }
+            Self::BadKerningPair { index } => {
+                write!(f, "kerning mark after glyph {index} doesn't apply to a valid pair")
+            }
+            Self::Bdf { line, what } => write!(f, "BDF line {line}: {what}"),
+            Self::BmFont { line, what } => write!(f, "BMFont line {line}: {what}"),
+            Self::BadGrid(what) => write!(f, "bad grid: {what}"),
+            Self::MissingReplacement(c) => {
+                write!(f, "replacement char {c:?} has no glyph in the font")
+            }
+            Self::MissingLeadingSeparator => {
+                f.write_str("sheet doesn't start with a band separator")
+            }
+            Self::MissingFirst => {
+                f.write_str("keeping blank glyphs requires an explicit first char")
+            }
+        }
+    }
 }
 
-impl From<GlyphOrderArg> for GlyphOrder<'_> {
-    fn from(a: GlyphOrderArg) -> Self {
-        match a {
-            GlyphOrderArg::Iso8859_1 => Self::Iso8859_1,
-            GlyphOrderArg::Cp437 => Self::Cp437,
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Image(e) => Some(e),
+            _ => None,
         }
     }
 }
 
+impl From<image::ImageError> for LoadError {
+    fn from(e: image::ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Which chars the glyphs in a glyph sheet stand for, in reading order.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum GlyphOrder<'a> {
     Iso8859_1,
@@ -24,87 +139,359 @@ pub enum GlyphOrder<'a> {
     Explicit(&'a [char]),
 }
 
+/// Colors used to mark up a glyph sheet for `load_font_from_png`.
+///
+/// These are matched against pixels' RGB values, for pixels that are at least
+/// half opaque. More transparent pixels never match, so they are background.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MarkerColors {
+    /// Color of the lines separating bands, and of the marks on the baseline
+    /// separating glyphs within a band. Default: red.
+    pub band: Rgb<u8>,
+    /// Color of the baseline marker in each band. Default: blue.
+    pub baseline: Rgb<u8>,
+    /// Color of pixels that are set in a glyph. Default: black.
+    pub ink: Rgb<u8>,
+    /// Color of kerning marks that move a pair of glyphs closer together.
+    /// Default: green.
+    pub kern_tighten: Rgb<u8>,
+    /// Color of kerning marks that move a pair of glyphs farther apart.
+    /// Default: magenta.
+    pub kern_loosen: Rgb<u8>,
+}
+
+impl Default for MarkerColors {
+    fn default() -> Self {
+        Self {
+            band: Rgb([0xFF, 0, 0]),
+            baseline: Rgb([0, 0, 0xFF]),
+            ink: Rgb([0, 0, 0]),
+            kern_tighten: Rgb([0, 0xFF, 0]),
+            kern_loosen: Rgb([0xFF, 0, 0xFF]),
+        }
+    }
+}
+
+/// A test applied to each pixel of a glyph sheet, for `MarkerPredicates`.
+pub type PixelPredicate<'p> = &'p (dyn Fn(Rgba<u8>) -> bool + Sync);
+
+/// Custom tests for recognizing the parts of a glyph sheet, for sheets that
+/// can't be described by `MarkerColors` alone. Each predicate that's given
+/// replaces the corresponding color comparison everywhere the loader makes it.
+#[derive(Copy, Clone, Default)]
+pub struct MarkerPredicates<'p> {
+    /// Recognizes band separators, in the left margin and along the bottom of
+    /// each band, and glyph separators on the baseline. Replaces
+    /// `colors.band`.
+    pub band: Option<PixelPredicate<'p>>,
+    /// Recognizes the baseline marker in each band. Replaces
+    /// `colors.baseline`.
+    pub baseline: Option<PixelPredicate<'p>>,
+    /// Recognizes pixels that are set in glyphs, including in grid sheets.
+    /// Replaces both `colors.ink` and `ink_threshold`.
+    pub ink: Option<PixelPredicate<'p>>,
+}
+
+impl fmt::Debug for MarkerPredicates<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |p: &Option<PixelPredicate<'_>>| if p.is_some() { "Some(..)" } else { "None" };
+        f.debug_struct("MarkerPredicates")
+            .field("band", &format_args!("{}", show(&self.band)))
+            .field("baseline", &format_args!("{}", show(&self.baseline)))
+            .field("ink", &format_args!("{}", show(&self.ink)))
+            .finish()
+    }
+}
+
+/// Options controlling how `load_font_from_png` interprets a glyph sheet.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LoadOptions<'p> {
+    /// Colors used to mark up the sheet.
+    pub colors: MarkerColors,
+    /// Tests overriding some of `colors`. Default: none, so that only
+    /// `colors` is used.
+    pub predicates: MarkerPredicates<'p>,
+    /// If `None` (the default), only pixels exactly matching `colors.ink` are
+    /// treated as set in glyphs. Like marker colors, this ignores pixels that
+    /// are less than half opaque, so sheets can have a transparent background.
+    ///
+    /// If `Some(t)`, glyph pixels are instead composited over white using
+    /// their alpha channel (if the image has one), and any pixel whose
+    /// resulting luma is below `t` is treated as set. This is useful for sheets
+    /// with antialiased or partially transparent edges. Pixels in the band and
+    /// baseline marker colors are never set.
+    pub ink_threshold: Option<u8>,
+    /// If `Some(gap)`, generate kerning automatically from glyph shapes.
+    ///
+    /// For each pair of glyphs in ISO8859-1, the loader finds the narrowest
+    /// horizontal gap between the ink of the two glyphs on any row where both
+    /// have ink, when set side by side at their default advance. If that gap
+    /// is wider than `gap`, the pair is tightened by the difference. Pairs
+    /// that never share an inked row are left alone.
+    ///
+    /// Kerning marks in the sheet take precedence over generated kerning.
+    pub auto_kern: Option<u8>,
+    /// How hard to work at sharing bitmap data between glyphs. Default:
+    /// `DedupMode::ExactSuffix`.
+    pub dedup: DedupMode,
+    /// If `Some`, the sheet is a plain grid of equal-sized cells, with no
+    /// bands, baselines, or separators, and is sliced up as described.
+    /// Marker colors other than `colors.ink` aren't used.
+    pub grid: Option<GridSpec>,
+    /// If `Some(c)`, the glyph for `c` replaces chars that are missing from
+    /// the font: `c` becomes the font's `replacement_char`, and `replacement`
+    /// is set to its glyph index. It's an error if the font has no glyph for
+    /// `c`. Default: `None`, so the replacement is glyph index 0.
+    pub replacement: Option<char>,
+    /// Whether the sheet starts with a band separator line above the first
+    /// band, as well as having one below each band.
+    ///
+    /// If `false` (the default), the first band starts at the top row of the
+    /// sheet, and a separator there would be taken as the bottom of an empty
+    /// first band. If `true`, the top row must be a separator, and the first
+    /// band starts just below it. Either way, every band ends with a
+    /// separator. Grid sheets ignore this.
+    pub leading_separator: bool,
+    /// Whether to take every cell of the sheet as a glyph, in order, for fonts
+    /// with many intentionally blank glyphs.
+    ///
+    /// If `false` (the default), a missing `first` is guessed from the
+    /// positions of blank glyphs, and back-to-back separators in a band, with
+    /// no pixels between them, are skipped. If `true`, `first` must be given
+    /// unless the glyph order is `Explicit`, since nothing is guessed, and
+    /// back-to-back separators hold a zero-width blank glyph, so that every
+    /// glyph index stays lined up with its cell in the sheet.
+    pub keep_blanks: bool,
+}
+
+/// Layout of a glyph sheet that's a plain grid of cells, for
+/// `LoadOptions::grid`.
+///
+/// Cells are read left to right, then top to bottom, starting at the top left
+/// corner of the image. Every whole cell in the first `cols` columns is a
+/// glyph, even if it's blank, so the glyph order (or `first`) needs to account
+/// for any unused cells at the end. Each glyph's advance is `cell_w`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct GridSpec {
+    /// Width of each cell, in pixels. At most 64.
+    pub cell_w: u32,
+    /// Height of each cell, in pixels.
+    pub cell_h: u32,
+    /// Number of columns of cells.
+    pub cols: u32,
+    /// Number of rows in each cell above the baseline, including the baseline
+    /// row itself. At most `cell_h`.
+    pub ascent: u32,
+}
+
+/// How the loader shares bitmap data between glyphs with matching images.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum DedupMode {
+    /// Store each glyph's image separately. This is the fastest, and produces
+    /// the largest fonts.
+    None,
+    /// Share a glyph's image with any identical run of bytes already in the
+    /// bitmap data, possibly spanning several earlier glyphs' images. This
+    /// takes time roughly linear in the size of the bitmap data for each
+    /// distinct image size.
+    #[default]
+    ExactSuffix,
+    /// Pack the bitmap data more aggressively, sharing storage between glyphs
+    /// whose images are contained within, or overlap the end of, other glyphs'
+    /// images. This can be noticeably slower on large fonts.
+    Full,
+}
+
+// Marker colors are matched on RGB alone, but only for pixels that are at least
+// half opaque, so that a transparent background reads as background whatever
+// color its pixels nominally have. Predicates see the whole pixel.
+impl LoadOptions<'_> {
+    fn is_band(&self, p: Rgba<u8>) -> bool {
+        match self.predicates.band {
+            Some(f) => f(p),
+            None => opaque_rgb(p) == Some(self.colors.band),
+        }
+    }
+
+    fn is_baseline(&self, p: Rgba<u8>) -> bool {
+        match self.predicates.baseline {
+            Some(f) => f(p),
+            None => opaque_rgb(p) == Some(self.colors.baseline),
+        }
+    }
+
+    fn is_ink(&self, p: Rgba<u8>) -> bool {
+        if let Some(f) = self.predicates.ink {
+            return f(p);
+        }
+        match self.ink_threshold {
+            None => opaque_rgb(p) == Some(self.colors.ink),
+            Some(t) => {
+                // Markers on the baseline row sit among the glyph pixels, and
+                // are never ink however dark they are.
+                let rgb = opaque_rgb(p);
+                rgb != Some(self.colors.band) && rgb != Some(self.colors.baseline)
+                    && composite_luma(p) < t
+            }
+        }
+    }
+}
+
+/// Returns the color of `p` if it's at least half opaque, for matching against
+/// marker colors.
+fn opaque_rgb(p: Rgba<u8>) -> Option<Rgb<u8>> {
+    (p[3] >= 0x80).then(|| p.to_rgb())
+}
+
+/// Computes the luma of `p` composited over a white background.
+fn composite_luma(p: Rgba<u8>) -> u8 {
+    let luma = u32::from(p.to_luma()[0]);
+    let alpha = u32::from(p[3]);
+    (0xFF - (alpha * (0xFF - luma) + 0x7F) / 0xFF) as u8
+}
+
+/// Loads a font from a PNG (or other image format supported by `image`) glyph
+/// sheet, and calls `body` with the result.
+///
+/// If `options.grid` is set, the sheet is a plain grid of cells instead, and
+/// has no kerning marks; see `GridSpec`.
+///
+/// # Band heights
+///
+/// Bands needn't all be the same height, or have their baselines at the same
+/// height: a band of capitals can be taller than a band of punctuation, say,
+/// and a band with no descenders can have its baseline on its bottom row.
+/// Each band's glyphs keep their places relative to its own baseline, and the
+/// font's ascent and descent are the largest of any band's, so that its line
+/// spacing fits the tallest glyphs above and below the baseline.
+///
+/// # Kerning marks
+///
+/// Kerning between two glyphs that are _adjacent in the sheet_ can be given by
+/// placing a single mark in the separator column between them -- that is, the
+/// column holding the red separator pixel on the baseline. A pixel of color
+/// `kern_tighten` in the `N`th row of the band (where the band's top row is row
+/// number one) produces a kerning entry with `adjust = -N`; a pixel of color
+/// `kern_loosen` produces `adjust = N`. A separator can contain at most one
+/// mark. If any kerned pair involves chars outside ISO8859-1, the font gets a
+/// `Kerning::Wide` table.
+///
+/// Problems with the image are reported as a `LoadError`, boxed into the
+/// returned error. Errors returned by `body` are passed through unchanged.
+///
+/// With the `rayon` feature, the bands of the sheet are scanned in parallel.
+/// The resulting font is the same either way.
 pub fn load_font_from_png<R>(
     png: impl BufRead + Seek,
     order: GlyphOrder<'_>,
     first: Option<u8>,
-    body: impl FnOnce(&Font<'_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
+    options: &LoadOptions<'_>,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
 ) -> Result<R, Box<dyn std::error::Error>> {
-    let img = image::io::Reader::new(png)
-        .with_guessed_format()?
-        .decode()?
-        .to_rgb8();
+    build_from_pngs(core::iter::once(png), order, first, options, true, |font, _| body(font))?
+}
 
-    // Scan the left margin to find band boundaries.
-    let mut last_y = 0;
-    let mut bands = vec![];
-    for y in 0..img.height() {
-        if *img.get_pixel(0, y) == Rgb([0xFF, 0, 0]) {
-            // See how wide the red strip is.
-            let band_width = (1..img.width()).map(|x| *img.get_pixel(x, y) == Rgb([0xFF, 0, 0])).count() + 1;
-
-            let line_height = y - last_y;
-
-            // Scan to find all the blues.
-            let mut blues = vec![];
-            for by in last_y..y {
-                if (0..band_width).any(|bx| *img.get_pixel(bx as u32, by) == Rgb([0, 0, 0xFF])) {
-                    blues.push(by);
-                }
-            }
-            if blues.is_empty() {
-                panic!("missing baseline");
-            } else if blues.len() > 1 {
-                panic!("ambiguous baseline");
-            }
-
-            let baseline = blues.iter().cloned().next().unwrap();
-
-            let ascent = baseline + 1 - last_y;
-            let descent = line_height - ascent;
-
-            let mut glyph_widths = vec![];
-            let mut glyph_data = vec![];
-            let mut last_glyph_edge = 0;
-            for bx in 0..band_width {
-                if *img.get_pixel(bx as u32, baseline) == Rgb([0xFF, 0, 0]) {
-                    let w = bx - last_glyph_edge;
-                    assert!(w < 65);
-                    if w != 0 {
-                        let mut bits = vec![];
-                        for gy in last_y..y {
-                            let mut row = 0u64;
-                            let mut mask = 1 << 63;
-                            for gx in last_glyph_edge..bx {
-                                if *img.get_pixel(gx as u32, gy) == Rgb([0, 0, 0]) {
-                                    row |= mask;
-                                }
-                                mask >>= 1;
-                            }
-                            bits.push(row);
-                        }
-                        glyph_data.push(bits);
-                        glyph_widths.push(w);
-                    }
-                    last_glyph_edge = bx + 1;
-                }
-            }
+/// Loads a font from a glyph sheet split across several images, or pages, and
+/// calls `body` with the result.
+///
+/// This works like `load_font_from_png` given a single sheet with the bands of
+/// each page stacked below those of the page before: glyphs are numbered from
+/// the first band of the first page to the last band of the last, and every
+/// band is padded out to the largest ascent and descent of any page. The same
+/// `options` apply to every page.
+///
+/// Kerning marks sit between glyphs in the same band, so there's no way to
+/// mark a pair split across two pages (or two bands).
+pub fn load_font_from_pngs<P, R>(
+    pages: impl IntoIterator<Item = P>,
+    order: GlyphOrder<'_>,
+    first: Option<u8>,
+    options: &LoadOptions<'_>,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
+) -> Result<R, Box<dyn std::error::Error>>
+    where P: BufRead + Seek,
+{
+    build_from_pngs(pages, order, first, options, true, |font, _| body(font))?
+}
+
+/// Loads a font from a PNG glyph sheet, like `load_font_from_png`, but returns
+/// it as an `OwnedFont` rather than lending it to a callback. The font's
+/// `sources` record where in the sheet each glyph came from.
+///
+/// This is handy for loading a font once and keeping it around. It copies the
+/// font's tables once more than `load_font_from_png` does, which rarely
+/// matters.
+pub fn load_owned_font_from_png(
+    png: impl BufRead + Seek,
+    order: GlyphOrder<'_>,
+    first: Option<u8>,
+    options: &LoadOptions<'_>,
+) -> Result<OwnedFont, LoadError> {
+    load_owned_font_from_pngs(core::iter::once(png), order, first, options)
+}
 
+/// Loads a font from several pages of glyph sheet, like `load_font_from_pngs`,
+/// but returns it as an `OwnedFont` rather than lending it to a callback.
+pub fn load_owned_font_from_pngs<P>(
+    pages: impl IntoIterator<Item = P>,
+    order: GlyphOrder<'_>,
+    first: Option<u8>,
+    options: &LoadOptions<'_>,
+) -> Result<OwnedFont, LoadError>
+    where P: BufRead + Seek,
+{
+    build_from_pngs(pages, order, first, options, true, |font, sources| OwnedFont {
+        sources: sources.to_vec(),
+        ..OwnedFont::from(font)
+    })
+}
 
-            bands.push((
-                ascent,
-                descent,
-                glyph_data,
-                glyph_widths,
-            ));
+/// Does the work of `load_font_from_pngs`, returning whatever `body` makes of
+/// the font and where each of its glyphs came from, by glyph index. The bands
+/// of each page are scanned in parallel if `parallel` is set and the `rayon`
+/// feature is enabled.
+fn build_from_pngs<P, R>(
+    pages: impl IntoIterator<Item = P>,
+    order: GlyphOrder<'_>,
+    first: Option<u8>,
+    options: &LoadOptions<'_>,
+    parallel: bool,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>, &[GlyphSource]) -> R,
+) -> Result<R, LoadError>
+    where P: BufRead + Seek,
+{
+    let mut scanned = vec![];
+    for (page, png) in pages.into_iter().enumerate() {
+        scanned.extend(scan_sheet(png, options, parallel)?.into_iter().map(|band| (page, band)));
+    }
 
-            last_y = y + 1;
+    let mut bands = vec![];
+    // Glyphs seen in previous bands, for computing overall glyph indices.
+    let mut glyph_count = 0;
+    // Kerning marks found in the sheet, as (left glyph index, adjust).
+    let mut sheet_kerning = vec![];
+    // Where each glyph came from, in sheet order.
+    let mut sources = vec![];
+    for (page, band) in scanned {
+        let (band, kerning) = band?;
+        for (preceding, adjust) in kerning {
+            let index = (glyph_count + preceding)
+                .checked_sub(1)
+                .ok_or(LoadError::BadKerningPair { index: 0 })?;
+            sheet_kerning.push((index, adjust));
         }
+        glyph_count += band.2.len();
+        sources.extend(band.4.iter().map(|&rect| GlyphSource { page, rect }));
+        bands.push(band);
     }
-    let max_ascent = bands.iter().map(|&(ascent, _, _, _)| ascent).max().unwrap();
-    let max_descent = bands.iter().map(|&(_, descent, _, _)| descent).max().unwrap();
+    let max_ascent = bands.iter().map(|&(ascent, ..)| ascent).max()
+        .ok_or(LoadError::NoBands)?;
+    let max_descent = bands.iter().map(|&(_, descent, ..)| descent).max()
+        .ok_or(LoadError::NoBands)?;
 
-    for (ascent, descent, glyphs, _) in &mut bands {
+    // Line every band up on the same baseline, padding shorter bands above
+    // and below. (Glyphs are cropped later, so the padding costs nothing.)
+    for (ascent, descent, glyphs, ..) in &mut bands {
         let ascent_pad = max_ascent - *ascent;
         let descent_pad = max_descent - *descent;
         if ascent_pad != 0 || descent_pad != 0 {
@@ -122,141 +509,72 @@ pub fn load_font_from_png<R>(
     }
 
     let mut out_glyphs = vec![];
-    let mut out_bitmap = vec![];
+    let mut packer = BitmapPacker::new(options.dedup);
 
-    for (_, _, data, widths) in &bands {
+    // The char for each glyph index, if it can be known before `first` is
+    // detected, for error reporting.
+    let known_char = |i: usize| match order {
+        GlyphOrder::Explicit(chars) => chars.get(i).copied(),
+        GlyphOrder::Iso8859_1 => first.and_then(|f| char::from_u32(u32::from(f) + i as u32)),
+        GlyphOrder::Cp437 => first.and_then(|f| CP437_CODEPOINTS.get(usize::from(f) + i).copied()),
+    };
+    for (_, _, data, widths, _) in &bands {
         for (glyph, &width) in data.iter().zip(widths) {
-            let pad_top = glyph.iter().take_while(|&&row| row == 0).count();
-            let g = if pad_top == glyph.len() {
-                Glyph {
-                    row_bytes: 0,
-                    image_height: 0,
-                    image_offset: 0,
-                    origin: (0, 0),
-                    advance: u8::try_from(width).unwrap(),
-                }
-            } else {
-                let pad_bottom = glyph.iter().rev().take_while(|&&row| row == 0).count();
-                let pad_left = glyph.iter().map(|row| row.leading_zeros()).min().unwrap();
-                let pad_right = glyph.iter().map(|row| row.trailing_zeros()).min().unwrap();
-
-                let x_bits = 64 - pad_right - pad_left;
-                let height = glyph.len() - pad_bottom - pad_top;
-                let row_bytes = u8::try_from((x_bits + 7) / 8).unwrap();
-
-                let mut bytes = vec![];
-
-                for row in glyph[pad_top..glyph.len() - pad_bottom].iter().cloned() {
-                    let mut row = row << pad_left;
-                    for _ in 0..row_bytes {
-                        bytes.push(row.to_be_bytes()[0]);
-                        row <<= 8;
-                    }
-                }
-
-                // Search for any _existing_ copy of the bitmap data in our
-                // array. This finds actual hits for actual fonts, believe it or
-                // not.
-                //
-                // The windows + `==` + position approach being used here relies
-                // on the slice `==` implementation early exiting, which of
-                // course it does. So it's n^2 worst-case but in practice much
-                // closer to n.
-                let image_offset = if let Some(prev) = out_bitmap.windows(bytes.len()).position(|w| w == bytes) {
-                    u16::try_from(prev).unwrap()
-                } else {
-                    let image_offset = u16::try_from(out_bitmap.len()).unwrap();
-                    out_bitmap.extend(bytes);
-                    image_offset
-                };
-
-                Glyph {
-                    row_bytes,
-                    image_height: u8::try_from(height).unwrap(),
-                    origin: (
-                        u8::try_from(pad_left).unwrap(),
-                        u8::try_from(pad_top).unwrap(),
-                    ),
-                    advance: u8::try_from(width).unwrap(),
-
-                    image_offset,
-                }
-            };
-
-            out_glyphs.push(g);
+            let index = out_glyphs.len();
+            check_glyph_size(glyph, width, index, known_char(index))?;
+            out_glyphs.push(pack_glyph(glyph, width, &mut packer)?);
         }
     }
 
-    // Double-check the byte reuse logic above.
-    //
-    // Yeah, using Aho-Corasick for this is arguably massive overkill, but it's
-    // also _really easy._
-    {
-        let patterns = out_glyphs.iter()
-            .map(|g| {
-                let s = usize::from(g.image_offset);
-                let e = s + usize::from(g.image_height) * usize::from(g.row_bytes);
-                &out_bitmap[s..e]
-            })
-            .collect::<Vec<_>>();
-        let fsm = aho_corasick::AhoCorasick::new_auto_configured(&patterns);
-        for mat in fsm.find_overlapping_iter(&out_bitmap) {
-            let g = &out_glyphs[mat.pattern()];
-            let io = usize::from(g.image_offset);
-            if mat.start() < io && mat.end() <= io {
-                eprintln!("WARNING: data for glyph {} can be found earlier at {}",
-                    mat.pattern(), mat.start());
-                let orig = &out_bitmap[io..io + usize::from(g.row_bytes) * usize::from(g.image_height)];
-                let alt = &out_bitmap[mat.start()..mat.end()];
-                assert_eq!(orig, alt);
-                eprintln!("original at {}: {:x?}", io, orig);
-                eprintln!("alt at {}:      {:x?}", mat.start(), alt);
-            }
-        }
+    let mut out_bitmap = packer.bitmap;
+    if options.dedup == DedupMode::Full {
+        out_bitmap = overlap_bitmaps(&mut out_glyphs, &out_bitmap)?;
+    }
+    if options.dedup != DedupMode::None {
+        check_bitmap_reuse(&out_glyphs, &out_bitmap);
     }
 
     // Try to detect offset based on blanks.
     let first = if let GlyphOrder::Explicit(chars) = order {
-        chars[0] as u8
+        *chars.first().ok_or(LoadError::EmptyGlyphOrder)? as u8
     } else if let Some(f) = first {
         f
+    } else if options.keep_blanks {
+        return Err(LoadError::MissingFirst);
     } else {
         let blanks: Vec<usize> = out_glyphs.iter().enumerate()
             .filter_map(|(i, g)| if g.image_height == 0 { Some(i) } else { None })
             .collect();
-        match &blanks[..] {
-            [x] => b' ' - u8::try_from(*x).unwrap(),
-            [0, 32] => 0,
-            [x, y] if *y == *x + 223 => 32,
-            [0, 32, 255] => 0,
-            [0, 95] => 32,
-            _ => {
-                panic!("can't detect font offset due to ambiguous blank pattern: {:?}", blanks);
-            }
-        }
+        let first = match &blanks[..] {
+            [x] => u8::try_from(*x).ok().and_then(|x| b' '.checked_sub(x)),
+            [0, 32] => Some(0),
+            [x, y] if *y == *x + 223 => Some(32),
+            [0, 32, 255] => Some(0),
+            [0, 95] => Some(32),
+            _ => None,
+        };
+        first.ok_or(LoadError::AmbiguousBlanks(blanks))?
     };
 
     // Build sorted table of glyphs if required. Gotta do this out of the match
     // below because it winds up being borrowed.
-    let sorted_glyphs = {
-        let mut table = vec![];
-        match order {
-            GlyphOrder::Iso8859_1 => (),
-
-            GlyphOrder::Explicit(chars) => {
-                for (&g, &c) in out_glyphs.iter().zip(chars) {
-                    table.push((c, g));
-                }
-            }
-            GlyphOrder::Cp437 => {
-                for (&g, &c) in out_glyphs.iter().zip(&CP437_CODEPOINTS[first as usize..]) {
-                    table.push((c, g));
-                }
-            }
-        }
+    // The table holds each char with the glyph's index in the sheet, so that
+    // `sources` can be put in the same order.
+    let sorted = {
+        let chars: &[char] = match order {
+            GlyphOrder::Iso8859_1 => &[],
+            GlyphOrder::Explicit(chars) => chars,
+            GlyphOrder::Cp437 => &CP437_CODEPOINTS[first as usize..],
+        };
+        let mut table: Vec<(char, usize)> = chars.iter().copied()
+            .zip(0..out_glyphs.len())
+            .collect();
         table.sort_unstable_by_key(|&(c, _)| c);
         table
     };
+    let sorted_glyphs: Vec<(char, Glyph)> = sorted.iter()
+        .map(|&(c, i)| (c, out_glyphs[i]))
+        .collect();
 
     let glyph_storage = match order {
         GlyphOrder::Iso8859_1 => {
@@ -267,19 +585,1102 @@ pub fn load_font_from_png<R>(
         }
         _ => GlyphStorage::Sparse { sorted_glyphs: &sorted_glyphs },
     };
+    if !matches!(order, GlyphOrder::Iso8859_1) {
+        sources = sorted.iter().map(|&(_, i)| sources[i]).collect();
+    }
+
+    // Map glyph indices back to chars for kerning, which is char-based.
+    let char_for_index = |i: usize| -> Option<char> {
+        match order {
+            GlyphOrder::Iso8859_1 => char::from_u32(u32::from(first) + i as u32),
+            GlyphOrder::Explicit(chars) => chars.get(i).copied(),
+            GlyphOrder::Cp437 => CP437_CODEPOINTS.get(usize::from(first) + i).copied(),
+        }
+    };
+    let kerning_pair = |i: usize| -> Option<(char, char)> {
+        Some((char_for_index(i)?, char_for_index(i + 1)?))
+    };
+
+    let mut kerning_entries = vec![];
+    for &(index, adjust) in &sheet_kerning {
+        if index + 1 >= out_glyphs.len() {
+            return Err(LoadError::BadKerningPair { index });
+        }
+        let pair = kerning_pair(index)
+            .ok_or(LoadError::BadKerningPair { index })?;
+        kerning_entries.push(WideKerningEntry { pair, adjust });
+    }
+    if let Some(target) = options.auto_kern {
+        let all_glyphs: Vec<_> = bands.iter()
+            .flat_map(|(_, _, data, widths, _)| data.iter().zip(widths))
+            .collect();
+        // Only ISO8859-1 is kerned, to keep the kerning table compact.
+        let chars: Vec<_> = (0..all_glyphs.len())
+            .map(|i| char_for_index(i).filter(|c| u8::try_from(*c).is_ok()))
+            .collect();
+        for (i, &(left, &left_width)) in all_glyphs.iter().enumerate() {
+            let Some(before) = chars[i] else { continue };
+            for (j, &(right, _)) in all_glyphs.iter().enumerate() {
+                let Some(after) = chars[j] else { continue };
+                if let Some(adjust) = auto_kern_adjust(left, left_width, right, target) {
+                    kerning_entries.push(WideKerningEntry {
+                        pair: (before, after),
+                        adjust,
+                    });
+                }
+            }
+        }
+    }
+    // This sort is stable, so where a pair was both marked in the sheet and
+    // generated, the sheet's entry comes first and survives the dedup.
+    kerning_entries.sort_by_key(|e| e.pair);
+    kerning_entries.dedup_by_key(|e| e.pair);
+
+    let kerning = OwnedKerning::from_entries(kerning_entries);
+    // The replacement char is found by `replacement_char`, so if its index
+    // doesn't fit in `replacement`, that can be left at 0.
+    let replacement = match options.replacement {
+        Some(c) => glyph_storage.index_of(c)
+            .ok_or(LoadError::MissingReplacement(c))?,
+        None => 0,
+    };
+    let replacement = u8::try_from(replacement).unwrap_or(0);
+    let font = Font {
+        ascent: u8::try_from(max_ascent)
+            .map_err(|_| LoadError::OutOfRange("ascent"))?,
+        descent: u8::try_from(max_descent)
+            .map_err(|_| LoadError::OutOfRange("descent"))?,
+        line_spacing: u8::try_from(max_ascent + max_descent)
+            .map_err(|_| LoadError::OutOfRange("line spacing"))?,
+        glyph_storage,
+        replacement,
+        replacement_char: options.replacement,
+        bitmaps: &out_bitmap,
+        bitmap_encoding: BitmapEncoding::Raw,
+        kerning: kerning.as_kerning(),
+        advance_bias: 0,
+        ligatures: LigatureTable::default(),
+    };
+
+    Ok(body(&font, &sources))
+}
+
+/// Decodes a glyph sheet image and extracts the glyphs from each of its bands,
+/// in order. Problems with individual bands are returned per band.
+fn scan_sheet(
+    png: impl BufRead + Seek,
+    options: &LoadOptions<'_>,
+    #[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+    parallel: bool,
+) -> Result<Vec<Result<ScannedBand, LoadError>>, LoadError> {
+    let img = image::io::Reader::new(png)
+        .with_guessed_format()?
+        .decode()?
+        .to_rgba8();
+    let scanned = if let Some(grid) = &options.grid {
+        vec![scan_grid(&img, options, grid).map(|band| (band, vec![]))]
+    } else {
+        // Scan the left margin to find band boundaries, as (top row, separator
+        // row) pairs.
+        let mut band_rows = vec![];
+        let first_y = if options.leading_separator {
+            if img.height() == 0 || !options.is_band(*img.get_pixel(0, 0)) {
+                return Err(LoadError::MissingLeadingSeparator);
+            }
+            1
+        } else {
+            0
+        };
+        let mut last_y = first_y;
+        for y in first_y..img.height() {
+            if options.is_band(*img.get_pixel(0, y)) {
+                band_rows.push((last_y, y));
+                last_y = y + 1;
+            }
+        }
+
+        // Bands can be scanned independently, but errors are reported in
+        // sheet order either way.
+        let scan = |&(top, bottom): &(u32, u32)| scan_band(&img, options, top, bottom);
+        #[cfg(feature = "rayon")]
+        let scanned = if parallel {
+            use rayon::prelude::*;
+            band_rows.par_iter().map(scan).collect()
+        } else {
+            band_rows.iter().map(scan).collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let scanned = band_rows.iter().map(scan).collect();
+        scanned
+    };
+    Ok(scanned)
+}
+
+/// Computes the kerning adjustment (if any) needed to tighten the gap between
+/// `left` and `right` to `target` pixels. Each glyph is given as a sequence of
+/// rows, with its leftmost pixel in bit 63, and both must have the same height.
+fn auto_kern_adjust(
+    left: &[u64],
+    left_width: usize,
+    right: &[u64],
+    target: u8,
+) -> Option<i8> {
+    let min_gap = left.iter().zip(right)
+        .filter(|&(&l, &r)| l != 0 && r != 0)
+        .map(|(&l, &r)| {
+            // Distance from the left glyph's last ink pixel to the right
+            // glyph's first ink pixel on this row.
+            let left_ink_end = 64 - l.trailing_zeros() as usize;
+            let right_ink_start = left_width + r.leading_zeros() as usize;
+            right_ink_start as isize - left_ink_end as isize
+        })
+        .min()?;
+    let surplus = min_gap - isize::from(target);
+    if surplus > 0 {
+        Some(-(surplus.min(128) as i16) as i8)
+    } else {
+        None
+    }
+}
+
+/// Loads a font from a BDF (Glyph Bitmap Distribution Format) file, and calls
+/// `body` with the result.
+///
+/// The font's ascent and descent come from the `FONT_ASCENT` and `FONT_DESCENT`
+/// properties if present, or `FONTBOUNDINGBOX` if not, and are expanded if
+/// necessary to fit every glyph. Each glyph's `DWIDTH` becomes its advance, and
+/// its `BBX` offsets become its origin. The `DEFAULT_CHAR` property, if
+/// present, selects the replacement glyph. Glyphs with no encoding (`ENCODING
+/// -1`) are skipped.
+///
+/// Since seff can't represent glyphs extending to the left of their origin,
+/// glyphs with negative X offsets are shifted right to start at the origin.
+///
+/// Glyphs are stored `Dense` if their encodings form a contiguous range
+/// starting within ISO8859-1, and `Sparse` otherwise.
+pub fn load_font_from_bdf<R>(
+    bdf: impl BufRead,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
+) -> Result<R, Box<dyn std::error::Error>> {
+    build_from_bdf(bdf, body)?
+}
+
+/// Loads a font from a BDF file, like `load_font_from_bdf`, but returns it as
+/// an `OwnedFont` rather than lending it to a callback.
+pub fn load_owned_font_from_bdf(bdf: impl BufRead) -> Result<OwnedFont, LoadError> {
+    build_from_bdf(bdf, |font| OwnedFont::from(font))
+}
+
+/// Does the work of `load_font_from_bdf`, returning whatever `body` makes of
+/// the font.
+fn build_from_bdf<R>(
+    bdf: impl BufRead,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> R,
+) -> Result<R, LoadError> {
+    struct BdfChar {
+        encoding: u32,
+        advance: Option<usize>,
+        // width, height, x offset, y offset
+        bbx: Option<(usize, usize, isize, isize)>,
+        rows: Vec<u64>,
+    }
+
+    fn num<T: std::str::FromStr>(
+        field: Option<&str>,
+        line: usize,
+    ) -> Result<T, LoadError> {
+        field.and_then(|f| f.parse().ok())
+            .ok_or(LoadError::Bdf { line, what: "missing or invalid number" })
+    }
+
+    let mut bounding_box = None;
+    let mut font_ascent = None;
+    let mut font_descent = None;
+    let mut default_char = None;
+    let mut font_advance = None;
+    let mut chars = vec![];
+    let mut current: Option<BdfChar> = None;
+    let mut in_bitmap = false;
+
+    for (i, text) in bdf.lines().enumerate() {
+        let text = text.map_err(LoadError::from)?;
+        let line = i + 1;
+        let mut fields = text.split_whitespace();
+        let Some(keyword) = fields.next() else { continue };
+
+        if in_bitmap {
+            if keyword == "ENDCHAR" {
+                in_bitmap = false;
+                chars.extend(current.take());
+                continue;
+            }
+            let c = current.as_mut()
+                .ok_or(LoadError::Bdf { line, what: "bitmap outside char" })?;
+            let (width, _, x_offset, _) = c.bbx
+                .ok_or(LoadError::Bdf { line, what: "BITMAP before BBX" })?;
+            let bits = u64::from_str_radix(keyword, 16).ok()
+                .filter(|_| keyword.len() <= 16)
+                .ok_or(LoadError::Bdf { line, what: "invalid bitmap row" })?;
+            // Left-justify the row, then move it over by the glyph's X offset
+            // so it's relative to the origin.
+            let shift = x_offset.max(0) as usize;
+            if width + shift > 64 {
+                return Err(LoadError::Bdf { line, what: "glyph too wide" });
+            }
+            let row = (bits << (64 - keyword.len() * 4)) >> shift;
+            c.rows.push(row);
+            continue;
+        }
+
+        match keyword {
+            "FONTBOUNDINGBOX" => {
+                let _w: isize = num(fields.next(), line)?;
+                let h: isize = num(fields.next(), line)?;
+                let _x: isize = num(fields.next(), line)?;
+                let y: isize = num(fields.next(), line)?;
+                bounding_box = Some((h, y));
+            }
+            "FONT_ASCENT" => font_ascent = Some(num::<isize>(fields.next(), line)?),
+            "FONT_DESCENT" => font_descent = Some(num::<isize>(fields.next(), line)?),
+            "DEFAULT_CHAR" => default_char = Some(num::<u32>(fields.next(), line)?),
+            "DWIDTH" => {
+                let dx: usize = num(fields.next(), line)?;
+                match current.as_mut() {
+                    Some(c) => c.advance = Some(dx),
+                    None => font_advance = Some(dx),
+                }
+            }
+            "STARTCHAR" => {
+                if current.is_some() {
+                    return Err(LoadError::Bdf { line, what: "STARTCHAR inside char" });
+                }
+                current = Some(BdfChar {
+                    encoding: u32::MAX,
+                    advance: None,
+                    bbx: None,
+                    rows: vec![],
+                });
+            }
+            "ENCODING" => {
+                let c = current.as_mut()
+                    .ok_or(LoadError::Bdf { line, what: "ENCODING outside char" })?;
+                // Unencoded glyphs use -1, which we handle by failing to parse
+                // it as a u32 and leaving the sentinel in place.
+                c.encoding = fields.next()
+                    .and_then(|f| f.parse().ok())
+                    .unwrap_or(u32::MAX);
+            }
+            "BBX" => {
+                let c = current.as_mut()
+                    .ok_or(LoadError::Bdf { line, what: "BBX outside char" })?;
+                c.bbx = Some((
+                    num(fields.next(), line)?,
+                    num(fields.next(), line)?,
+                    num(fields.next(), line)?,
+                    num(fields.next(), line)?,
+                ));
+            }
+            "BITMAP" => {
+                if current.is_none() {
+                    return Err(LoadError::Bdf { line, what: "BITMAP outside char" });
+                }
+                in_bitmap = true;
+            }
+            "ENDCHAR" => {
+                chars.extend(current.take());
+            }
+            _ => (),
+        }
+    }
+
+    chars.retain(|c| char::from_u32(c.encoding).is_some());
+    chars.sort_by_key(|c| c.encoding);
+    chars.dedup_by_key(|c| c.encoding);
+
+    // Work out line metrics big enough for every glyph.
+    let (bbox_height, bbox_y) = bounding_box
+        .ok_or(LoadError::Bdf { line: 0, what: "missing FONTBOUNDINGBOX" })?;
+    let mut ascent = font_ascent.unwrap_or(bbox_height + bbox_y);
+    let mut descent = font_descent.unwrap_or(-bbox_y);
+    for c in &chars {
+        if let Some((_, h, _, y)) = c.bbx {
+            ascent = ascent.max(h as isize + y);
+            descent = descent.max(-y);
+        }
+    }
+    let ascent = usize::try_from(ascent)
+        .map_err(|_| LoadError::OutOfRange("ascent"))?;
+    let descent = usize::try_from(descent)
+        .map_err(|_| LoadError::OutOfRange("descent"))?;
+
+    let mut out_glyphs = vec![];
+    let mut packer = BitmapPacker::default();
+    for c in &chars {
+        // Place the glyph's rows into a cell the full height of the line.
+        let mut cell = vec![0; ascent + descent];
+        let mut natural_width = 0;
+        if let Some((w, h, x, y)) = c.bbx {
+            let top = (ascent as isize - (h as isize + y)) as usize;
+            for (dest, &row) in cell[top..].iter_mut().zip(&c.rows) {
+                *dest = row;
+            }
+            natural_width = (w as isize + x).max(0) as usize;
+        }
+        let advance = c.advance.or(font_advance).unwrap_or(natural_width);
+        check_glyph_size(&cell, advance, out_glyphs.len(), char::from_u32(c.encoding))?;
+        out_glyphs.push(pack_glyph(&cell, advance, &mut packer)?);
+    }
+    let out_bitmap = packer.bitmap;
+
+    check_bitmap_reuse(&out_glyphs, &out_bitmap);
+
+    let replacement = default_char
+        .and_then(|d| chars.iter().position(|c| c.encoding == d))
+        .and_then(|i| u8::try_from(i).ok())
+        .unwrap_or(0);
+
+    let encodings: Vec<char> = chars.iter()
+        .map(|c| char::from_u32(c.encoding).unwrap())
+        .collect();
+    let (dense_first, sorted_glyphs) = choose_storage(&encodings, &out_glyphs);
+    let glyph_storage = match dense_first {
+        Some(first) => GlyphStorage::Dense {
+            first,
+            glyphs: &out_glyphs,
+        },
+        None => GlyphStorage::Sparse { sorted_glyphs: &sorted_glyphs },
+    };
 
-    let kerning = KerningTable { entries: &[] };
     let font = Font {
-        ascent: u8::try_from(max_ascent).unwrap(),
-        descent: u8::try_from(max_descent).unwrap(),
-        line_spacing: u8::try_from(max_ascent + max_descent).unwrap(),
+        ascent: u8::try_from(ascent)
+            .map_err(|_| LoadError::OutOfRange("ascent"))?,
+        descent: u8::try_from(descent)
+            .map_err(|_| LoadError::OutOfRange("descent"))?,
+        line_spacing: u8::try_from(ascent + descent)
+            .map_err(|_| LoadError::OutOfRange("line spacing"))?,
+        glyph_storage,
+        replacement,
+        replacement_char: default_char.and_then(char::from_u32),
+        bitmaps: &out_bitmap,
+        bitmap_encoding: BitmapEncoding::Raw,
+        kerning: Kerning::default(),
+        advance_bias: 0,
+        ligatures: LigatureTable::default(),
+    };
+
+    Ok(body(&font))
+}
+
+/// Loads a font from an AngelCode BMFont text descriptor (`.fnt`) and its atlas
+/// images, and calls `body` with the result.
+///
+/// Atlas pages are opened on demand by calling `open_page` with the file name
+/// given in the descriptor's `page` line. Atlases are converted to one bit per
+/// pixel using `ink_threshold`: for images with an alpha channel, pixels with
+/// at least that much alpha are ink; for opaque images, pixels with at least
+/// that much luma are ink (BMFont's usual white-on-black convention).
+///
+/// The font's ascent is the descriptor's `base`, and its line spacing is
+/// `lineHeight`. Each glyph's `xoffset`/`yoffset` become its origin, and
+/// `xadvance` its advance. Since seff can't represent glyphs extending above or
+/// to the left of their origin, negative offsets are clamped to zero.
+///
+/// `kerning` lines are loaded into the kerning table. If any of them involve
+/// chars outside ISO8859-1, the font gets a `Kerning::Wide` table.
+///
+/// Only the text descriptor format is supported, not the XML or binary ones.
+pub fn load_font_from_bmfont<R, P>(
+    fnt: impl BufRead,
+    open_page: impl FnMut(&str) -> std::io::Result<P>,
+    ink_threshold: u8,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
+) -> Result<R, Box<dyn std::error::Error>>
+    where P: BufRead + Seek,
+{
+    build_from_bmfont(fnt, open_page, ink_threshold, body)?
+}
+
+/// Loads a font from a BMFont descriptor and its atlas images, like
+/// `load_font_from_bmfont`, but returns it as an `OwnedFont` rather than
+/// lending it to a callback.
+pub fn load_owned_font_from_bmfont<P>(
+    fnt: impl BufRead,
+    open_page: impl FnMut(&str) -> std::io::Result<P>,
+    ink_threshold: u8,
+) -> Result<OwnedFont, LoadError>
+    where P: BufRead + Seek,
+{
+    build_from_bmfont(fnt, open_page, ink_threshold, |font| OwnedFont::from(font))
+}
+
+/// Does the work of `load_font_from_bmfont`, returning whatever `body` makes
+/// of the font.
+fn build_from_bmfont<R, P>(
+    fnt: impl BufRead,
+    mut open_page: impl FnMut(&str) -> std::io::Result<P>,
+    ink_threshold: u8,
+    body: impl FnOnce(&Font<'_, '_, '_, '_>) -> R,
+) -> Result<R, LoadError>
+    where P: BufRead + Seek,
+{
+    struct BmChar {
+        id: char,
+        page: usize,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        x_offset: i32,
+        y_offset: i32,
+        advance: i32,
+    }
+
+    let mut line_height = None;
+    let mut base = None;
+    let mut page_files: Vec<Option<String>> = vec![];
+    let mut chars = vec![];
+    let mut kerning_entries = vec![];
+
+    for (i, text) in fnt.lines().enumerate() {
+        let text = text.map_err(LoadError::from)?;
+        let line = i + 1;
+        let (tag, attrs) = bmfont_attributes(&text);
+        let get = |key: &str| attrs.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+            .ok_or(LoadError::BmFont { line, what: "missing attribute" });
+        let num = |key: &str| -> Result<i32, LoadError> {
+            get(key)?.parse()
+                .map_err(|_| LoadError::BmFont { line, what: "invalid number" })
+        };
+        let unsigned = |key: &str| -> Result<u32, LoadError> {
+            u32::try_from(num(key)?)
+                .map_err(|_| LoadError::BmFont { line, what: "negative number" })
+        };
+
+        match tag {
+            "common" => {
+                line_height = Some(unsigned("lineHeight")?);
+                base = Some(unsigned("base")?);
+            }
+            "page" => {
+                let id = unsigned("id")? as usize;
+                if page_files.len() <= id {
+                    page_files.resize(id + 1, None);
+                }
+                page_files[id] = Some(get("file")?.to_string());
+            }
+            "char" => {
+                // BMFont uses an id of -1 for its "invalid char" glyph, which
+                // we can't map to a char, so it gets skipped.
+                let Some(id) = u32::try_from(num("id")?).ok().and_then(char::from_u32) else {
+                    continue;
+                };
+                chars.push(BmChar {
+                    id,
+                    page: unsigned("page")? as usize,
+                    x: unsigned("x")?,
+                    y: unsigned("y")?,
+                    width: unsigned("width")?,
+                    height: unsigned("height")?,
+                    x_offset: num("xoffset")?,
+                    y_offset: num("yoffset")?,
+                    advance: num("xadvance")?,
+                });
+            }
+            "kerning" => {
+                let before = u32::try_from(num("first")?).ok().and_then(char::from_u32);
+                let after = u32::try_from(num("second")?).ok().and_then(char::from_u32);
+                if let (Some(before), Some(after)) = (before, after) {
+                    let adjust = i8::try_from(num("amount")?)
+                        .map_err(|_| LoadError::OutOfRange("kerning adjustment"))?;
+                    kerning_entries.push(WideKerningEntry {
+                        pair: (before, after),
+                        adjust,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let line_height = line_height
+        .ok_or(LoadError::BmFont { line: 0, what: "missing common line" })?;
+    let base = base
+        .ok_or(LoadError::BmFont { line: 0, what: "missing common line" })?;
+
+    chars.sort_by_key(|c| c.id);
+    chars.dedup_by_key(|c| c.id);
+    kerning_entries.sort_by_key(|e| e.pair);
+    kerning_entries.dedup_by_key(|e| e.pair);
+    let kerning = OwnedKerning::from_entries(kerning_entries);
+
+    // Decoded pages, along with whether each has an alpha channel.
+    let mut pages: Vec<Option<(bool, image::RgbaImage)>> = vec![None; page_files.len()];
+    let mut out_glyphs = vec![];
+    let mut packer = BitmapPacker::default();
+    for c in &chars {
+        let file = page_files.get(c.page).and_then(|f| f.as_deref())
+            .ok_or(LoadError::BmFont { line: 0, what: "char refers to missing page" })?;
+        let page = match &mut pages[c.page] {
+            Some(page) => page,
+            slot => {
+                let reader = open_page(file).map_err(LoadError::from)?;
+                let img = image::io::Reader::new(reader)
+                    .with_guessed_format()
+                    .map_err(LoadError::from)?
+                    .decode()
+                    .map_err(LoadError::from)?;
+                slot.insert((img.color().has_alpha(), img.to_rgba8()))
+            }
+        };
+        let (has_alpha, page) = (page.0, &page.1);
+
+        let x_offset = c.x_offset.max(0) as usize;
+        let y_offset = c.y_offset.max(0) as usize;
+        let width = c.width as usize;
+        if x_offset + width > 64 {
+            return Err(LoadError::GlyphTooWide {
+                x: c.x,
+                y: c.y,
+                width: (x_offset + width) as u32,
+            });
+        }
+        let mut cell = vec![0_u64; y_offset + c.height as usize];
+        for (gy, row) in cell[y_offset..].iter_mut().enumerate() {
+            for gx in 0..width {
+                let (px, py) = (c.x + gx as u32, c.y + gy as u32);
+                if px >= page.width() || py >= page.height() {
+                    return Err(LoadError::BmFont {
+                        line: 0,
+                        what: "char extends outside its page",
+                    });
+                }
+                let p = page.get_pixel(px, py);
+                let level = if has_alpha { p[3] } else { p.to_luma()[0] };
+                if level >= ink_threshold {
+                    *row |= 1 << (63 - x_offset - gx);
+                }
+            }
+        }
+        let advance = usize::try_from(c.advance)
+            .map_err(|_| LoadError::OutOfRange("glyph advance"))?;
+        check_glyph_size(&cell, advance, out_glyphs.len(), Some(c.id))?;
+        out_glyphs.push(pack_glyph(&cell, advance, &mut packer)?);
+    }
+    let out_bitmap = packer.bitmap;
+
+    check_bitmap_reuse(&out_glyphs, &out_bitmap);
+
+    let ids: Vec<char> = chars.iter().map(|c| c.id).collect();
+    let (dense_first, sorted_glyphs) = choose_storage(&ids, &out_glyphs);
+    let glyph_storage = match dense_first {
+        Some(first) => GlyphStorage::Dense {
+            first,
+            glyphs: &out_glyphs,
+        },
+        None => GlyphStorage::Sparse { sorted_glyphs: &sorted_glyphs },
+    };
+
+    let descent = line_height.saturating_sub(base);
+    let font = Font {
+        ascent: u8::try_from(base)
+            .map_err(|_| LoadError::OutOfRange("ascent"))?,
+        descent: u8::try_from(descent)
+            .map_err(|_| LoadError::OutOfRange("descent"))?,
+        line_spacing: u8::try_from(line_height)
+            .map_err(|_| LoadError::OutOfRange("line spacing"))?,
         glyph_storage,
         replacement: 0,
+        replacement_char: None,
         bitmaps: &out_bitmap,
-        kerning,
+        bitmap_encoding: BitmapEncoding::Raw,
+        kerning: kerning.as_kerning(),
+        advance_bias: 0,
+        ligatures: LigatureTable::default(),
+    };
+
+    Ok(body(&font))
+}
+
+/// Splits a line of a BMFont text descriptor into its tag and its `key=value`
+/// attributes, removing quotes from quoted values.
+fn bmfont_attributes(line: &str) -> (&str, Vec<(&str, &str)>) {
+    let line = line.trim_start();
+    let (tag, mut rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut attrs = vec![];
+    loop {
+        rest = rest.trim_start();
+        let Some((key, after)) = rest.split_once('=') else { break };
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else {
+            after.split_once(char::is_whitespace).unwrap_or((after, ""))
+        };
+        attrs.push((key.trim(), value));
+        rest = after;
+    }
+    (tag, attrs)
+}
+
+/// Decides how to store `glyphs`, whose chars are given (in ascending order) by
+/// `chars`.
+///
+/// If the chars are contiguous and start within ISO8859-1, this returns
+/// `Some(first)` for use with `Dense` storage. Otherwise, it returns `None` and
+/// the table for `Sparse` storage.
+pub(crate) fn choose_storage(
+    chars: &[char],
+    glyphs: &[Glyph],
+) -> (Option<u8>, Vec<(char, Glyph)>) {
+    let contiguous = chars.windows(2)
+        .all(|w| u32::from(w[1]) == u32::from(w[0]) + 1);
+    let dense_first = chars.first()
+        .and_then(|&c| u8::try_from(c).ok())
+        .filter(|_| contiguous);
+    if dense_first.is_some() {
+        (dense_first, vec![])
+    } else {
+        (None, chars.iter().copied().zip(glyphs.iter().copied()).collect())
+    }
+}
+
+/// A band of a glyph sheet: its ascent and descent, and each glyph's rows,
+/// width, and cell rectangle in the sheet as `(x, y, width, height)`.
+type Band = (u32, u32, Vec<Vec<u64>>, Vec<usize>, Vec<(u32, u32, u32, u32)>);
+
+/// A band along with its kerning marks, as returned by `scan_band`.
+type ScannedBand = (Band, Vec<(usize, i8)>);
+
+/// Extracts the glyphs from the band of `img` starting at row `top`, whose
+/// bottom edge is the separator line at row `bottom`.
+///
+/// Kerning marks are returned as (number of glyphs in the band preceding the
+/// mark, adjust).
+fn scan_band(
+    img: &image::RgbaImage,
+    options: &LoadOptions<'_>,
+    top: u32,
+    bottom: u32,
+) -> Result<ScannedBand, LoadError> {
+    let colors = options.colors;
+    let rgb_at = |x: u32, y: u32| opaque_rgb(*img.get_pixel(x, y));
+    let pixel_at = |x: u32, y: u32| *img.get_pixel(x, y);
+
+    // See how wide the red strip is.
+    let band_width = (1..img.width()).take_while(|&x| options.is_band(pixel_at(x, bottom))).count() + 1;
+
+    let line_height = bottom - top;
+
+    // Scan to find all the blues.
+    let mut blues = vec![];
+    for by in top..bottom {
+        if (0..band_width).any(|bx| options.is_baseline(pixel_at(bx as u32, by))) {
+            blues.push(by);
+        }
+    }
+    let baseline = match blues[..] {
+        [b] => b,
+        [] => return Err(LoadError::MissingBaseline { band_top: top }),
+        _ => return Err(LoadError::AmbiguousBaseline { band_top: top }),
+    };
+
+    let ascent = baseline + 1 - top;
+    let descent = line_height - ascent;
+
+    let mut glyph_widths = vec![];
+    let mut glyph_data = vec![];
+    let mut rects = vec![];
+    let mut kerning = vec![];
+    let mut last_glyph_edge = 0;
+    for bx in 0..band_width {
+        if options.is_band(pixel_at(bx as u32, baseline)) {
+            let w = bx - last_glyph_edge;
+            if w > 64 {
+                return Err(LoadError::GlyphTooWide {
+                    x: last_glyph_edge as u32,
+                    y: top,
+                    width: w as u32,
+                });
+            }
+            if w != 0 || options.keep_blanks {
+                let mut bits = vec![];
+                for gy in top..bottom {
+                    let mut row = 0u64;
+                    let mut mask = 1 << 63;
+                    for gx in last_glyph_edge..bx {
+                        if options.is_ink(pixel_at(gx as u32, gy)) {
+                            row |= mask;
+                        }
+                        mask >>= 1;
+                    }
+                    bits.push(row);
+                }
+                glyph_data.push(bits);
+                glyph_widths.push(w);
+                rects.push((last_glyph_edge as u32, top, w as u32, line_height));
+            }
+            last_glyph_edge = bx + 1;
+
+            // Check the separator for a kerning mark.
+            let mut marks = (top..bottom).filter_map(|ky| {
+                let p = rgb_at(bx as u32, ky);
+                let n = i32::try_from(ky - top + 1).ok()?;
+                if p == Some(colors.kern_tighten) {
+                    Some((ky, -n))
+                } else if p == Some(colors.kern_loosen) {
+                    Some((ky, n))
+                } else {
+                    None
+                }
+            });
+            if let Some((ky, adjust)) = marks.next() {
+                if marks.next().is_some() {
+                    return Err(LoadError::AmbiguousKerning {
+                        x: bx as u32,
+                        y: ky,
+                    });
+                }
+                let adjust = i8::try_from(adjust)
+                    .map_err(|_| LoadError::OutOfRange("kerning adjustment"))?;
+                kerning.push((glyph_data.len(), adjust));
+            }
+        }
+    }
+
+    Ok(((ascent, descent, glyph_data, glyph_widths, rects), kerning))
+}
+
+/// Slices `img` into glyphs as described by `grid`, returning them as a single
+/// band.
+fn scan_grid(
+    img: &image::RgbaImage,
+    options: &LoadOptions<'_>,
+    grid: &GridSpec,
+) -> Result<Band, LoadError> {
+    if grid.cell_w == 0 || grid.cell_h == 0 || grid.cols == 0 {
+        return Err(LoadError::BadGrid("cell size and columns must be nonzero"));
+    }
+    if grid.cell_w > 64 {
+        return Err(LoadError::GlyphTooWide { x: 0, y: 0, width: grid.cell_w });
+    }
+    if grid.ascent > grid.cell_h {
+        return Err(LoadError::BadGrid("ascent is taller than a cell"));
+    }
+    if grid.cols.checked_mul(grid.cell_w).is_none_or(|w| w > img.width()) {
+        return Err(LoadError::BadGrid("columns don't fit in the image"));
+    }
+
+    let rows = img.height() / grid.cell_h;
+    let mut glyph_data = vec![];
+    for cy in 0..rows {
+        for cx in 0..grid.cols {
+            let (left, top) = (cx * grid.cell_w, cy * grid.cell_h);
+            let bits = (top..top + grid.cell_h).map(|gy| {
+                let mut row = 0u64;
+                let mut mask = 1 << 63;
+                for gx in left..left + grid.cell_w {
+                    if options.is_ink(*img.get_pixel(gx, gy)) {
+                        row |= mask;
+                    }
+                    mask >>= 1;
+                }
+                row
+            });
+            glyph_data.push(bits.collect());
+        }
+    }
+    let glyph_widths = vec![grid.cell_w as usize; glyph_data.len()];
+    let rects = (0..rows)
+        .flat_map(|cy| (0..grid.cols).map(move |cx| (cx, cy)))
+        .map(|(cx, cy)| (cx * grid.cell_w, cy * grid.cell_h, grid.cell_w, grid.cell_h))
+        .collect();
+
+    Ok((grid.ascent, grid.cell_h - grid.ascent, glyph_data, glyph_widths, rects))
+}
+
+/// Accumulates glyph bitmap data, sharing it between glyphs as directed by a
+/// `DedupMode`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BitmapPacker {
+    pub(crate) bitmap: Vec<u8>,
+    mode: DedupMode,
+    /// For each image length added so far, the offsets of every window of
+    /// that length in `bitmap`, keyed by the window's `window_hash`. Offsets
+    /// are listed in increasing order, so the first that matches is the
+    /// earliest.
+    windows: HashMap<usize, HashMap<u64, Vec<usize>>>,
+}
+
+impl BitmapPacker {
+    pub(crate) fn new(mode: DedupMode) -> Self {
+        Self { mode, ..Self::default() }
+    }
+
+    /// Adds `bytes` to the bitmap data, returning its offset.
+    fn add(&mut self, bytes: Vec<u8>) -> usize {
+        if self.mode == DedupMode::None || bytes.is_empty() {
+            let offset = self.bitmap.len();
+            self.bitmap.extend(bytes);
+            return offset;
+        }
+
+        let bitmap = &self.bitmap;
+        let index = self.windows.entry(bytes.len()).or_insert_with(|| {
+            let mut index = HashMap::new();
+            index_windows(bitmap, bytes.len(), 0, &mut index);
+            index
+        });
+        let found = index.get(&window_hash(&bytes))
+            .and_then(|offsets| offsets.iter().copied()
+                .find(|&o| bitmap[o..o + bytes.len()] == bytes[..]));
+        if let Some(offset) = found {
+            return offset;
+        }
+
+        let offset = self.bitmap.len();
+        self.bitmap.extend(bytes);
+        for (&len, index) in &mut self.windows {
+            // Only windows that reach into the new data are new.
+            let start = (offset + 1).saturating_sub(len);
+            index_windows(&self.bitmap, len, start, index);
+        }
+        offset
+    }
+}
+
+/// Multiplier for `window_hash`.
+const WINDOW_HASH_BASE: u64 = 0x100_0000_01b3;
+
+/// Polynomial hash of `bytes`, which `index_windows` can roll along a slice.
+fn window_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |h, &b| h.wrapping_mul(WINDOW_HASH_BASE).wrapping_add(u64::from(b)))
+}
+
+/// Records in `index` the offset of every `len`-byte window of `bitmap`
+/// starting at or after `start`.
+fn index_windows(
+    bitmap: &[u8],
+    len: usize,
+    start: usize,
+    index: &mut HashMap<u64, Vec<usize>>,
+) {
+    if start + len > bitmap.len() {
+        return;
+    }
+    // Weight of the byte leaving the window as it moves right.
+    let top = (1..len).fold(1u64, |w, _| w.wrapping_mul(WINDOW_HASH_BASE));
+    let mut h = window_hash(&bitmap[start..start + len]);
+    for o in start..=bitmap.len() - len {
+        if o > start {
+            h = h.wrapping_sub(u64::from(bitmap[o - 1]).wrapping_mul(top))
+                .wrapping_mul(WINDOW_HASH_BASE)
+                .wrapping_add(u64::from(bitmap[o + len - 1]));
+        }
+        index.entry(h).or_default().push(o);
+    }
+}
+
+/// Checks that a glyph, given as a sequence of rows, with advance `width`, is
+/// small enough for `pack_glyph`, returning `LoadError::GlyphTooLarge` with
+/// `index` and `ch` if not.
+pub(crate) fn check_glyph_size(
+    glyph: &[u64],
+    width: usize,
+    index: usize,
+    ch: Option<char>,
+) -> Result<(), LoadError> {
+    let height = glyph.len();
+    if width > usize::from(u8::MAX) || height > usize::from(u8::MAX) {
+        return Err(LoadError::GlyphTooLarge { index, ch, width, height });
+    }
+    Ok(())
+}
+
+/// Converts a glyph, given as a sequence of rows with the leftmost pixel in bit
+/// 63, into a `Glyph`. The glyph's image is trimmed to its ink, and the trimmed
+/// bitmap data is added to `packer`.
+pub(crate) fn pack_glyph(
+    glyph: &[u64],
+    width: usize,
+    packer: &mut BitmapPacker,
+) -> Result<Glyph, LoadError> {
+    let pad_top = glyph.iter().take_while(|&&row| row == 0).count();
+    if pad_top == glyph.len() {
+        Ok(Glyph::blank(
+            u8::try_from(width)
+                .map_err(|_| LoadError::OutOfRange("glyph advance"))?,
+        ))
+    } else {
+        let pad_bottom = glyph.iter().rev().take_while(|&&row| row == 0).count();
+        let pad_left = glyph.iter().map(|row| row.leading_zeros()).min().unwrap();
+        let pad_right = glyph.iter().map(|row| row.trailing_zeros()).min().unwrap();
+
+        let x_bits = 64 - pad_right - pad_left;
+        let height = glyph.len() - pad_bottom - pad_top;
+        let row_bytes = u8::try_from(x_bits.div_ceil(8))
+            .map_err(|_| LoadError::OutOfRange("glyph width"))?;
+
+        let pixel_width = u8::try_from(x_bits)
+            .map_err(|_| LoadError::OutOfRange("glyph width"))?;
+        // Trimming to the ink leaves nothing past `x_bits`, but mask anyway:
+        // padding bits must be zero so that identical images produce identical
+        // bytes and can share bitmap data.
+        let mask = !0u64 << (64 - x_bits);
+
+        let mut bytes = vec![];
+
+        for row in glyph[pad_top..glyph.len() - pad_bottom].iter().cloned() {
+            debug_assert_eq!(row << pad_left & !mask, 0);
+            let mut row = row << pad_left & mask;
+            for _ in 0..row_bytes {
+                bytes.push(row.to_be_bytes()[0]);
+                row <<= 8;
+            }
+        }
+
+        let image_offset = u16::try_from(packer.add(bytes))
+            .map_err(|_| LoadError::OutOfRange("bitmap data size"))?;
+
+        Ok(Glyph {
+            row_bytes,
+            pixel_width,
+            image_height: u8::try_from(height)
+                .map_err(|_| LoadError::OutOfRange("glyph height"))?,
+            origin: (
+                u8::try_from(pad_left)
+                    .map_err(|_| LoadError::OutOfRange("glyph origin"))?,
+                u8::try_from(pad_top)
+                    .map_err(|_| LoadError::OutOfRange("glyph origin"))?,
+            ),
+            advance: u8::try_from(width)
+                .map_err(|_| LoadError::OutOfRange("glyph advance"))?,
+
+            image_offset,
+        })
+    }
+}
+
+/// Rebuilds `bitmap` so that glyphs share storage wherever one glyph's image
+/// appears within another's, or the end of one image matches the start of
+/// another. Updates each glyph's `image_offset` to match, and returns the new
+/// bitmap data.
+///
+/// This is the shortest-common-superstring problem, which is NP-hard; we use
+/// the usual greedy approximation of repeatedly joining the pair of images
+/// with the longest overlap.
+fn overlap_bitmaps(
+    glyphs: &mut [Glyph],
+    bitmap: &[u8],
+) -> Result<Vec<u8>, LoadError> {
+    let image = |g: &Glyph| {
+        let s = usize::from(g.image_offset);
+        &bitmap[s..s + usize::from(g.row_bytes) * usize::from(g.image_height)]
     };
+    let contains = |hay: &[u8], needle: &[u8]| {
+        hay.windows(needle.len()).any(|w| w == needle)
+    };
+
+    // Collect the distinct images, longest first, and drop any that are
+    // contained in a longer one -- they'll get shared for free.
+    let mut images: Vec<&[u8]> = glyphs.iter()
+        .map(image)
+        .filter(|i| !i.is_empty())
+        .collect();
+    images.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    images.dedup();
+    let mut kept: Vec<&[u8]> = vec![];
+    for i in images {
+        if !kept.iter().any(|k| contains(k, i)) {
+            kept.push(i);
+        }
+    }
+
+    // Because no kept image contains another, any overlap is strictly
+    // shorter than both images.
+    let overlap = |a: &[u8], b: &[u8]| {
+        (1..a.len().min(b.len())).rev()
+            .find(|&n| a[a.len() - n..] == b[..n])
+    };
+    let mut joins = vec![];
+    for (i, a) in kept.iter().enumerate() {
+        for (j, b) in kept.iter().enumerate() {
+            if i != j {
+                if let Some(n) = overlap(a, b) {
+                    joins.push((n, i, j));
+                }
+            }
+        }
+    }
+    // Stable sort, so that ties are broken the same way every time.
+    joins.sort_by_key(|&(n, _, _)| core::cmp::Reverse(n));
+
+    let mut next: Vec<Option<(usize, usize)>> = vec![None; kept.len()];
+    let mut has_prev = vec![false; kept.len()];
+    for (n, i, j) in joins {
+        if next[i].is_some() || has_prev[j] {
+            continue;
+        }
+        // Refuse joins that would close a cycle.
+        let mut k = j;
+        while let Some((nk, _)) = next[k] {
+            k = nk;
+        }
+        if k == i {
+            continue;
+        }
+        next[i] = Some((j, n));
+        has_prev[j] = true;
+    }
+
+    let mut out = vec![];
+    for start in (0..kept.len()).filter(|&i| !has_prev[i]) {
+        out.extend_from_slice(kept[start]);
+        let mut k = start;
+        while let Some((nk, n)) = next[k] {
+            out.extend_from_slice(&kept[nk][n..]);
+            k = nk;
+        }
+    }
 
-    body(&font)
+    for g in glyphs {
+        let i = image(g);
+        if !i.is_empty() {
+            let offset = out.windows(i.len()).position(|w| w == i).unwrap();
+            g.image_offset = u16::try_from(offset)
+                .map_err(|_| LoadError::OutOfRange("bitmap data size"))?;
+        }
+    }
+    Ok(out)
+}
+
+/// Double-checks the byte reuse logic in `BitmapPacker` and `overlap_bitmaps`,
+/// warning about any glyph whose data could have been found earlier in the
+/// bitmap.
+fn check_bitmap_reuse(out_glyphs: &[Glyph], out_bitmap: &[u8]) {
+    // Yeah, using Aho-Corasick for this is arguably massive overkill, but it's
+    // also _really easy._
+    let patterns = out_glyphs.iter()
+        .map(|g| {
+            let s = usize::from(g.image_offset);
+            let e = s + usize::from(g.image_height) * usize::from(g.row_bytes);
+            &out_bitmap[s..e]
+        })
+        .collect::<Vec<_>>();
+    let fsm = aho_corasick::AhoCorasick::new_auto_configured(&patterns);
+    for mat in fsm.find_overlapping_iter(out_bitmap) {
+        let g = &out_glyphs[mat.pattern()];
+        let io = usize::from(g.image_offset);
+        if mat.start() < io && mat.end() <= io {
+            eprintln!("WARNING: data for glyph {} can be found earlier at {}",
+                mat.pattern(), mat.start());
+            let orig = &out_bitmap[io..io + usize::from(g.row_bytes) * usize::from(g.image_height)];
+            let alt = &out_bitmap[mat.start()..mat.end()];
+            assert_eq!(orig, alt);
+            eprintln!("original at {}: {:x?}", io, orig);
+            eprintln!("alt at {}:      {:x?}", mat.start(), alt);
+        }
+    }
 }
 
 static CP437_CODEPOINTS: [char; 256] = {
@@ -468,3 +1869,706 @@ static CP437_CODEPOINTS: [char; 256] = {
     table
 };
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws a glyph sheet from ASCII art: `.` is white, `#` black ink, `r`
+    /// the red band color, `b` the blue baseline, `g` and `m` the kerning
+    /// marks, `c` cyan, `y` light gray, `h` half-transparent black, and a
+    /// space is transparent.
+    fn sheet(rows: &[&str]) -> image::RgbaImage {
+        let width = rows[0].len() as u32;
+        image::RgbaImage::from_fn(width, rows.len() as u32, |x, y| {
+            match rows[y as usize].as_bytes()[x as usize] {
+                b'.' => Rgba([0xFF, 0xFF, 0xFF, 0xFF]),
+                b'#' => Rgba([0, 0, 0, 0xFF]),
+                b'r' => Rgba([0xFF, 0, 0, 0xFF]),
+                b'b' => Rgba([0, 0, 0xFF, 0xFF]),
+                b'g' => Rgba([0, 0xFF, 0, 0xFF]),
+                b'm' => Rgba([0xFF, 0, 0xFF, 0xFF]),
+                b'c' => Rgba([0, 0xFF, 0xFF, 0xFF]),
+                b'y' => Rgba([0xC0, 0xC0, 0xC0, 0xFF]),
+                b'h' => Rgba([0, 0, 0, 0x80]),
+                b' ' => Rgba([0, 0, 0, 0]),
+                c => panic!("bad sheet pixel {:?}", char::from(c)),
+            }
+        })
+    }
+
+    /// Encodes `img` as a PNG, for the loaders to read.
+    fn png(img: &image::RgbaImage) -> std::io::Cursor<Vec<u8>> {
+        let mut out = std::io::Cursor::new(vec![]);
+        img.write_to(&mut out, image::ImageOutputFormat::Png).unwrap();
+        out.set_position(0);
+        out
+    }
+
+    /// Loads `img` as a one-glyph font for `'A'`, and returns the glyph's
+    /// bitmap.
+    fn bitmap_of(img: &image::RgbaImage, options: &LoadOptions) -> Vec<u8> {
+        load_font_from_png(png(img), GlyphOrder::Iso8859_1, Some(b'A'), options, |font| {
+            let glyph = font.glyph_storage.get('A').unwrap();
+            Ok(glyph.slice_bitmap(font.bitmaps).to_vec())
+        }).unwrap()
+    }
+
+    /// Loads `img`, keeping only the error, if any.
+    fn load(img: &image::RgbaImage, first: Option<u8>) -> Result<(), LoadError> {
+        load_font_from_png(png(img), GlyphOrder::Iso8859_1, first, &LoadOptions::default(), |_| Ok(()))
+            .map_err(|e| *e.downcast::<LoadError>().unwrap())
+    }
+
+    #[test]
+    fn missing_baseline_is_an_error() {
+        let img = sheet(&[
+            "#.r",
+            "..r",
+            "rrr",
+        ]);
+        assert!(matches!(load(&img, Some(b'A')), Err(LoadError::MissingBaseline { band_top: 0 })));
+    }
+
+    #[test]
+    fn ambiguous_baseline_is_an_error() {
+        let img = sheet(&[
+            "#br",
+            ".br",
+            "rrr",
+        ]);
+        assert!(matches!(load(&img, Some(b'A')), Err(LoadError::AmbiguousBaseline { band_top: 0 })));
+    }
+
+    #[test]
+    fn too_wide_glyph_is_an_error() {
+        let ink = format!("{}r", "#".repeat(65));
+        let baseline = format!("{}r", "b".repeat(65));
+        let img = sheet(&[&ink, &baseline, &"r".repeat(66)]);
+        assert!(matches!(
+            load(&img, Some(b'A')),
+            Err(LoadError::GlyphTooWide { x: 0, y: 0, width: 65 }),
+        ));
+    }
+
+    #[test]
+    fn custom_marker_colors() {
+        let img = sheet(&[
+            "g.gr",
+            ".g.r",
+            "cccr",
+            "rrrr",
+        ]);
+        let colors = MarkerColors {
+            baseline: Rgb([0, 0xFF, 0xFF]),
+            ink: Rgb([0, 0xFF, 0]),
+            ..MarkerColors::default()
+        };
+        let options = LoadOptions { colors, ..LoadOptions::default() };
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &options, |font| {
+            let glyph = font.glyph_storage.get('A').unwrap();
+            assert_eq!(font.ascent, 3);
+            assert_eq!(glyph.advance, 3);
+            assert_eq!(glyph.slice_bitmap(font.bitmaps), [0xA0, 0x40]);
+            Ok(())
+        }).unwrap();
+
+        // The default colors see neither the ink nor the baseline.
+        assert!(matches!(load(&img, Some(b'A')), Err(LoadError::MissingBaseline { band_top: 0 })));
+    }
+
+    #[test]
+    fn composite_luma_is_over_white() {
+        assert_eq!(composite_luma(Rgba([0, 0, 0, 0xFF])), 0);
+        assert_eq!(composite_luma(Rgba([0, 0, 0, 0])), 0xFF);
+        assert_eq!(composite_luma(Rgba([0, 0, 0, 0x80])), 0x7F);
+        assert_eq!(composite_luma(Rgba([0xC0, 0xC0, 0xC0, 0xFF])), 0xC0);
+    }
+
+    #[test]
+    fn ink_threshold_matches_thresholded_sheet() {
+        let gray = sheet(&[
+            "#hyr",
+            "h#.r",
+            "bbbr",
+            "rrrr",
+        ]);
+        let threshold = |t| LoadOptions { ink_threshold: Some(t), ..LoadOptions::default() };
+
+        let dark = sheet(&[
+            "##.r",
+            "##.r",
+            "bbbr",
+            "rrrr",
+        ]);
+        assert_eq!(bitmap_of(&gray, &threshold(0x80)), bitmap_of(&dark, &LoadOptions::default()));
+
+        let light = sheet(&[
+            "###r",
+            "##.r",
+            "bbbr",
+            "rrrr",
+        ]);
+        assert_eq!(bitmap_of(&gray, &threshold(0xC8)), bitmap_of(&light, &LoadOptions::default()));
+
+        // Without a threshold, only the ink color counts, whatever its alpha.
+        assert_eq!(bitmap_of(&gray, &LoadOptions::default()), bitmap_of(&dark, &LoadOptions::default()));
+    }
+
+    #[test]
+    fn kerning_mark_makes_one_pair() {
+        let img = sheet(&[
+            "#...#r",
+            "#.g.#r",
+            "bbrbbr",
+            "rrrrrr",
+        ]);
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &LoadOptions::default(), |font| {
+            assert_eq!(font.kerning, Kerning::Narrow(KerningTable::new(&[KerningEntry { pair: (b'A', b'B'), adjust: -2 }])));
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn two_kerning_marks_are_an_error() {
+        let img = sheet(&[
+            "#.m.#r",
+            "#.g.#r",
+            "bbrbbr",
+            "rrrrrr",
+        ]);
+        assert!(matches!(load(&img, Some(b'A')), Err(LoadError::AmbiguousKerning { x: 2, y: 0 })));
+    }
+
+    #[test]
+    fn auto_kern_tightens_open_pairs() {
+        // An "A" whose ink leans left, and a "V" whose ink leans right.
+        let img = sheet(&[
+            "#...r...#r",
+            "##..r..##r",
+            "bbbbrbbbbr",
+            "rrrrrrrrrr",
+        ]);
+        let options = LoadOptions { auto_kern: Some(1), ..LoadOptions::default() };
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &options, |font| {
+            // The closest rows are 4 pixels apart, so 3 pixels come off.
+            assert_eq!(font.kerning.get('A', 'B'), Some(-3));
+            assert_eq!(font.kerning.get('A', 'A'), Some(-1));
+            // These glyphs already touch.
+            assert_eq!(font.kerning.get('B', 'A'), None);
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn unrecognized_blanks_are_reported() {
+        let img = sheet(&[
+            "#r.r.r.r",
+            "brbrbrbr",
+            "rrrrrrrr",
+        ]);
+        match load(&img, None) {
+            Err(LoadError::AmbiguousBlanks(blanks)) => assert_eq!(blanks, [1, 2, 3]),
+            other => panic!("unexpected result {:?}", other),
+        }
+        // Giving the first char gets around it.
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &LoadOptions::default(), |font| {
+            assert!(font.glyph_storage.get('D').is_some());
+            assert!(font.glyph_storage.get('E').is_none());
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn nine_pixel_glyph_has_clear_padding() {
+        let img = sheet(&[
+            "#########r",
+            "#.......#r",
+            "bbbbbbbbbr",
+            "rrrrrrrrrr",
+        ]);
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &LoadOptions::default(), |font| {
+            let glyph = font.glyph_storage.get('A').unwrap();
+            assert_eq!((glyph.row_bytes, glyph.pixel_width), (2, 9));
+            assert_eq!(glyph.width_in_pixels(), 9);
+            assert_eq!(glyph.slice_bitmap(font.bitmaps), [0xFF, 0x80, 0x80, 0x80]);
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn bands_of_different_heights_share_a_baseline() {
+        let img = sheet(&[
+            // 'A': three rows above the baseline, and one below.
+            "###r",
+            "#.#r",
+            "#b#r",
+            ".#.r",
+            "rrrr",
+            // 'B': two rows, with the baseline at the bottom and no descent.
+            "#.r.",
+            "#br.",
+            "rrr.",
+        ]);
+        let font = load_owned_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &LoadOptions::default())
+            .unwrap();
+        let font = font.as_font();
+        assert_eq!((font.ascent, font.descent, font.line_spacing), (3, 1, 4));
+
+        let a = *font.glyph_storage.get('A').unwrap();
+        let b = *font.glyph_storage.get('B').unwrap();
+        assert_eq!((a.origin, a.image_height), ((0, 0), 4));
+        assert_eq!((b.origin, b.image_height), ((0, 1), 2));
+        // Both bands' baseline rows land on the font's baseline.
+        assert_eq!(usize::from(b.origin.1 + b.image_height), usize::from(font.ascent));
+        assert_eq!(font.debug_render("AB"), "\
+            |***  |\n\
+            |* ** |\n\
+            |* ** |\n\
+            | *   |\n");
+    }
+
+    #[test]
+    fn independent_loads_compare_equal() {
+        use std::hash::BuildHasher;
+
+        let load = |first| {
+            let png = std::io::BufReader::new(std::fs::File::open("fonts/tiny-5x7.png").unwrap());
+            load_owned_font_from_png(png, GlyphOrder::Iso8859_1, Some(first), &LoadOptions::default())
+                .unwrap()
+        };
+        let (a, b) = (load(b' '), load(b' '));
+        assert!(!core::ptr::eq(a.bitmaps.as_ptr(), b.bitmaps.as_ptr()));
+        assert_eq!(a, b);
+        assert_eq!(a.as_font(), b.as_font());
+
+        let state = std::collections::hash_map::RandomState::new();
+        assert_eq!(state.hash_one(a.as_font()), state.hash_one(b.as_font()));
+
+        assert_ne!(a.as_font(), load(b'!').as_font());
+    }
+
+    const TINY_BDF: &str = "\
+STARTFONT 2.1
+FONT tiny
+FONTBOUNDINGBOX 4 6 0 -1
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 5 0 0
+BITMAP
+40
+A0
+E0
+A0
+A0
+ENDCHAR
+STARTCHAR B
+ENCODING 66
+DWIDTH 4 0
+BBX 3 5 0 0
+BITMAP
+C0
+A0
+C0
+A0
+C0
+ENDCHAR
+STARTCHAR comma
+ENCODING 44
+DWIDTH 3 0
+BBX 1 2 1 -1
+BITMAP
+80
+80
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn bdf_glyphs() {
+        load_font_from_bdf(TINY_BDF.as_bytes(), |font| {
+            assert_eq!((font.ascent, font.descent, font.line_spacing), (5, 1, 6));
+            // The encodings aren't contiguous, so the glyphs are stored sparsely.
+            let GlyphStorage::Sparse { sorted_glyphs } = font.glyph_storage else {
+                panic!("expected sparse storage");
+            };
+            assert_eq!(sorted_glyphs.len(), 3);
+
+            let a = font.glyph_storage.get('A').unwrap();
+            assert_eq!((a.advance, a.origin, a.image_height), (4, (0, 0), 5));
+            assert_eq!(a.slice_bitmap(font.bitmaps), [0x40, 0xA0, 0xE0, 0xA0, 0xA0]);
+            let b = font.glyph_storage.get('B').unwrap();
+            assert_eq!(b.slice_bitmap(font.bitmaps), [0xC0, 0xA0, 0xC0, 0xA0, 0xC0]);
+            let comma = font.glyph_storage.get(',').unwrap();
+            assert_eq!((comma.advance, comma.origin, comma.image_height), (3, (1, 4), 2));
+            assert_eq!(comma.slice_bitmap(font.bitmaps), [0x80, 0x80]);
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn bdf_too_wide_glyph_is_an_error() {
+        let bdf = TINY_BDF.replace("BBX 3 5 0 0\nBITMAP\n40", "BBX 60 5 8 0\nBITMAP\n40");
+        let err = load_font_from_bdf(bdf.as_bytes(), |_| Ok(())).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LoadError>(),
+            Some(LoadError::Bdf { line: 10, what: "glyph too wide" }),
+        ));
+    }
+
+    const TINY_FNT: &str = "\
+info face=\"tiny\" size=4
+common lineHeight=6 base=5 scaleW=4 scaleH=2 pages=1
+page id=0 file=\"atlas.png\"
+chars count=2
+char id=65 x=0 y=0 width=2 height=2 xoffset=-1 yoffset=1 xadvance=3 page=0 chnl=15
+char id=66 x=2 y=0 width=2 height=2 xoffset=1 yoffset=0 xadvance=4 page=0 chnl=15
+kerning first=65 second=66 amount=-1
+";
+
+    fn load_bmfont<R>(
+        fnt: &str,
+        body: impl FnOnce(&Font<'_, '_, '_, '_>) -> Result<R, Box<dyn std::error::Error>>,
+    ) -> Result<R, Box<dyn std::error::Error>> {
+        let atlas = sheet(&[
+            "#  #",
+            "## #",
+        ]);
+        load_font_from_bmfont(fnt.as_bytes(), |file| {
+            assert_eq!(file, "atlas.png");
+            Ok(png(&atlas))
+        }, 0x80, body)
+    }
+
+    #[test]
+    fn bmfont_glyphs() {
+        load_bmfont(TINY_FNT, |font| {
+            assert_eq!((font.ascent, font.descent, font.line_spacing), (5, 1, 6));
+            assert!(matches!(font.glyph_storage, GlyphStorage::Dense { first: b'A', .. }));
+
+            // The negative X offset is clamped, moving the glyph right.
+            let a = font.glyph_storage.get('A').unwrap();
+            assert_eq!((a.advance, a.origin), (3, (0, 1)));
+            assert_eq!(a.slice_bitmap(font.bitmaps), [0x80, 0xC0]);
+            let b = font.glyph_storage.get('B').unwrap();
+            assert_eq!((b.advance, b.origin), (4, (2, 0)));
+            assert_eq!(b.slice_bitmap(font.bitmaps), [0x80, 0x80]);
+
+            assert_eq!(font.kerning, Kerning::Narrow(KerningTable::new(&[KerningEntry { pair: (b'A', b'B'), adjust: -1 }])));
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn bmfont_char_outside_page_is_an_error() {
+        let fnt = TINY_FNT.replace("id=66 x=2", "id=66 x=3");
+        let err = load_bmfont(&fnt, |_| Ok(())).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LoadError>(),
+            Some(LoadError::BmFont { what: "char extends outside its page", .. }),
+        ));
+    }
+
+    #[test]
+    fn overlapping_bitmaps_are_shared() {
+        let glyph = |image_offset, image_height| Glyph {
+        pixel_width: 0,
+            row_bytes: 1,
+            image_offset,
+            image_height,
+            origin: (0, 0),
+            advance: 8,
+        };
+        // The second image is inside the first, and the third overlaps its
+        // end.
+        let naive = [1, 2, 3, 4, 2, 3, 4, 5, 6];
+        let mut glyphs = [glyph(0, 4), glyph(4, 2), glyph(6, 3)];
+        let original = glyphs;
+
+        let packed = overlap_bitmaps(&mut glyphs, &naive).unwrap();
+        assert_eq!(packed, [1, 2, 3, 4, 5, 6]);
+        for (new, old) in glyphs.iter().zip(&original) {
+            assert_eq!(new.slice_bitmap(&packed), old.slice_bitmap(&naive));
+        }
+    }
+
+    #[test]
+    fn packer_shares_runs_spanning_images() {
+        let mut packer = BitmapPacker::new(DedupMode::ExactSuffix);
+        assert_eq!(packer.add(vec![1, 2]), 0);
+        assert_eq!(packer.add(vec![3, 4]), 2);
+        assert_eq!(packer.add(vec![2, 3]), 1);
+        assert_eq!(packer.add(vec![4, 5, 6]), 4);
+        assert_eq!(packer.add(vec![1, 2, 3, 4]), 0);
+        assert_eq!(packer.bitmap, [1, 2, 3, 4, 4, 5, 6]);
+    }
+
+    /// Loads the ibm-8x8 font with `dedup`, returning the size of its bitmap
+    /// data, its glyphs with their images, and a render of every glyph.
+    fn ibm_8x8(dedup: DedupMode) -> (usize, Vec<(Glyph, Vec<u8>)>, image::GrayImage) {
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/ibm-8x8.png").unwrap());
+        let options = LoadOptions { dedup, ..LoadOptions::default() };
+        load_font_from_png(png, GlyphOrder::Cp437, Some(0), &options, |font| {
+            let glyphs: Vec<_> = (0..)
+                .map_while(|i| font.glyph_storage.get_by_index(i))
+                .map(|g| (*g, g.slice_bitmap(font.bitmaps).to_vec()))
+                .collect();
+            let text: String = CP437_CODEPOINTS.iter().collect();
+            let mut img = image::GrayImage::new(8 * 256, 8);
+            font.render_direct(&text, 0, 0, &mut img, image::Luma([0xFF]));
+            Ok((font.bitmaps.len(), glyphs, img))
+        }).unwrap()
+    }
+
+    #[test]
+    fn exact_suffix_dedup_matches_none() {
+        let (plain_len, plain_glyphs, plain_img) = ibm_8x8(DedupMode::None);
+        let (packed_len, packed_glyphs, packed_img) = ibm_8x8(DedupMode::ExactSuffix);
+        assert!(packed_len < plain_len);
+
+        // Everything but the layout of the bitmap data is the same.
+        assert_eq!(plain_glyphs.len(), packed_glyphs.len());
+        for ((pg, pimage), (qg, qimage)) in plain_glyphs.iter().zip(&packed_glyphs) {
+            assert_eq!(Glyph { image_offset: 0, ..*pg }, Glyph { image_offset: 0, ..*qg });
+            assert_eq!(pimage, qimage);
+        }
+        assert_eq!(plain_img, packed_img);
+    }
+
+
+    /// Loads the glyph sheet at `path`, with its bands scanned serially or in
+    /// parallel, and returns it in the binary format.
+    #[cfg(feature = "rayon")]
+    fn load_binary(path: &str, order: GlyphOrder<'_>, parallel: bool) -> Vec<u8> {
+        let png = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        build_from_pngs(core::iter::once(png), order, None, &LoadOptions::default(), parallel, |font, _| {
+            let mut out = vec![];
+            crate::gen::write_binary(font, &mut out).unwrap();
+            out
+        }).unwrap()
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_scan_matches_serial() {
+        for (path, order) in [
+            ("fonts/ibm-8x8.png", GlyphOrder::Cp437),
+            ("fonts/futuroid.png", GlyphOrder::Iso8859_1),
+        ] {
+            assert_eq!(
+                load_binary(path, order, true),
+                load_binary(path, order, false),
+                "{}", path,
+            );
+        }
+    }
+
+    #[test]
+    fn grid_slices_cells() {
+        // A 2x2 grid of 3x3 cells, with no separators.
+        let img = sheet(&[
+            "#..###",
+            "#...#.",
+            "......",
+            "..#.#.",
+            ".#..#.",
+            "#...##",
+        ]);
+        let grid = GridSpec { cell_w: 3, cell_h: 3, cols: 2, ascent: 2 };
+        let options = LoadOptions { grid: Some(grid), ..LoadOptions::default() };
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &options, |font| {
+            assert_eq!((font.ascent, font.descent), (2, 1));
+            let art = |c| {
+                let glyph = font.glyph_storage.get(c).unwrap();
+                assert_eq!(glyph.advance, 3);
+                let mut rows = vec![String::new(); 3];
+                let pixels = glyph.pixels(font.bitmaps, font.bitmap_encoding);
+                for (x, y, _) in pixels.filter(|&(_, _, set)| set) {
+                    let row = &mut rows[y + usize::from(glyph.origin.1)];
+                    let x = x + usize::from(glyph.origin.0);
+                    while row.len() < x {
+                        row.push('.');
+                    }
+                    row.push('#');
+                }
+                rows
+            };
+            assert_eq!(art('A'), ["#", "#", ""]);
+            assert_eq!(art('B'), ["###", ".#", ""]);
+            assert_eq!(art('C'), ["..#", ".#", "#"]);
+            assert_eq!(art('D'), [".#", ".#", ".##"]);
+            assert!(font.glyph_storage.get('E').is_none());
+            Ok(())
+        }).unwrap();
+
+        let bad = |grid| {
+            let options = LoadOptions { grid: Some(grid), ..LoadOptions::default() };
+            load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &options, |_| Ok(()))
+                .map_err(|e| *e.downcast::<LoadError>().unwrap())
+        };
+        assert!(matches!(bad(GridSpec { cols: 3, ..grid }), Err(LoadError::BadGrid(_))));
+        assert!(matches!(bad(GridSpec { ascent: 4, ..grid }), Err(LoadError::BadGrid(_))));
+        assert!(matches!(bad(GridSpec { cell_w: 0, ..grid }), Err(LoadError::BadGrid(_))));
+    }
+
+    #[test]
+    fn inverted_sheet_with_predicates() {
+        // White ink on black, with gray band markers and any blue pixel
+        // marking the baseline.
+        let img = sheet(&[
+            "..#y",
+            "#.#y",
+            "bbby",
+            "yyyy",
+        ]);
+        let white = |p: Rgba<u8>| p.0 == [0xFF; 4];
+        let gray = |p: Rgba<u8>| p.to_rgb() == Rgb([0xC0; 3]);
+        let blue = |p: Rgba<u8>| p.0[2] > 0x80 && p.0[0] < 0x80;
+        let options = LoadOptions {
+            predicates: MarkerPredicates { band: Some(&gray), baseline: Some(&blue), ink: Some(&white) },
+            ..LoadOptions::default()
+        };
+        load_font_from_png(png(&img), GlyphOrder::Iso8859_1, Some(b'A'), &options, |font| {
+            let glyph = font.glyph_storage.get('A').unwrap();
+            assert_eq!(font.ascent, 3);
+            assert_eq!(glyph.advance, 3);
+            assert_eq!(glyph.slice_bitmap(font.bitmaps), [0xC0, 0x40]);
+            Ok(())
+        }).unwrap();
+
+        // Without the predicates, there are no bands at all.
+        assert!(matches!(load(&img, Some(b'A')), Err(LoadError::NoBands)));
+    }
+
+    #[test]
+    fn transparent_background_is_not_ink() {
+        // The transparent pixels are nominally black, the ink color.
+        let img = sheet(&[
+            "# #r",
+            " # r",
+            "bbbr",
+            "rrrr",
+        ]);
+        assert_eq!(bitmap_of(&img, &LoadOptions::default()), [0xA0, 0x40]);
+        let options = LoadOptions { ink_threshold: Some(0x80), ..LoadOptions::default() };
+        assert_eq!(bitmap_of(&img, &options), [0xA0, 0x40]);
+    }
+
+    #[test]
+    fn kept_blanks_line_up_with_cells() {
+        // 'A' and 'D' are inked, 'B' is blank, and 'C' is the zero-width cell
+        // between two back-to-back separators.
+        let img = sheet(&[
+            "#r.rr#r",
+            "brbrrbr",
+            "rrrrrrr",
+        ]);
+        let load = |keep_blanks, first| {
+            let options = LoadOptions { keep_blanks, ..LoadOptions::default() };
+            load_owned_font_from_png(png(&img), GlyphOrder::Iso8859_1, first, &options)
+        };
+        let kept = load(true, Some(b'A')).unwrap();
+        let font = kept.as_font();
+        assert_eq!(font.glyph_count(), 4);
+        let inked: Vec<bool> = ['A', 'B', 'C', 'D'].iter()
+            .map(|&c| font.glyph_storage.get(c).unwrap().has_image())
+            .collect();
+        assert_eq!(inked, [true, false, false, true]);
+        assert_eq!(font.glyph_storage.get('C').unwrap().advance, 0);
+
+        // Otherwise the zero-width cell is skipped, and 'D' becomes 'C'.
+        let skipped = load(false, Some(b'A')).unwrap();
+        assert_eq!(skipped.as_font().glyph_count(), 3);
+        assert!(skipped.as_font().glyph_storage.get('C').unwrap().has_image());
+
+        assert!(matches!(load(true, None), Err(LoadError::MissingFirst)));
+    }
+
+    #[test]
+    fn leading_separator_is_skipped() {
+        let bare = [
+            "#.#r",
+            "##.r",
+            "bbbr",
+            "rrrr",
+        ];
+        let led = ["rrrr", bare[0], bare[1], bare[2], bare[3]];
+        let owned = |rows: &[&str], leading_separator| {
+            let options = LoadOptions { leading_separator, ..LoadOptions::default() };
+            let font = load_owned_font_from_png(png(&sheet(rows)), GlyphOrder::Iso8859_1, Some(b'A'), &options);
+            // Leave out the sources, which are a row apart.
+            format!("{:?}", OwnedFont { sources: vec![], ..font.unwrap() })
+        };
+        assert_eq!(owned(&led, true), owned(&bare, false));
+
+        let options = LoadOptions { leading_separator: true, ..LoadOptions::default() };
+        let err = load_owned_font_from_png(png(&sheet(&bare)), GlyphOrder::Iso8859_1, Some(b'A'), &options);
+        assert!(matches!(err, Err(LoadError::MissingLeadingSeparator)));
+    }
+
+    #[test]
+    fn pages_are_numbered_in_order() {
+        let page1 = sheet(&[
+            "##r",
+            "#.r",
+            "bbr",
+            "rrr",
+        ]);
+        // A second page with a descent, and a kerned pair.
+        let page2 = sheet(&[
+            "#.g.#r",
+            "#...#r",
+            "bbrbbr",
+            "#....r",
+            "rrrrrr",
+        ]);
+        let pages = [png(&page1), png(&page2)];
+        load_font_from_pngs(pages, GlyphOrder::Iso8859_1, Some(b'A'), &LoadOptions::default(), |font| {
+            assert_eq!((font.ascent, font.descent), (3, 1));
+            assert_eq!(font.glyph_storage.index_of('C'), Some(2));
+            assert_eq!(font.kerning.get('B', 'C'), Some(-1));
+            assert_eq!(font.kerning.get('A', 'B'), None);
+
+            let art = |s| {
+                let mut img = image::GrayImage::new(font.width(s) as u32, 4);
+                font.render(s, 0, 0, &mut img, image::Luma([1]));
+                img.rows()
+                    .map(|row| row.map(|p| if p[0] != 0 { '#' } else { '.' }).collect::<String>())
+                    .collect::<Vec<_>>()
+            };
+            // The first page's glyph is padded down to the second's descent.
+            assert_eq!(art("A"), ["##", "#.", "..", ".."]);
+            assert_eq!(art("B"), ["#.", "#.", "..", "#."]);
+            assert_eq!(art("C"), [".#", ".#", "..", ".."]);
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn owned_fonts_outlive_their_loaders() {
+        let futuroid = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        let atlas = sheet(&[
+            "#  #",
+            "## #",
+        ]);
+        let fonts = [
+            load_owned_font_from_png(futuroid, GlyphOrder::Iso8859_1, Some(b' '), &LoadOptions::default()).unwrap(),
+            load_owned_font_from_bdf(TINY_BDF.as_bytes()).unwrap(),
+            load_owned_font_from_bmfont(TINY_FNT.as_bytes(), |_| Ok(png(&atlas)), 0x80).unwrap(),
+        ];
+        let widths: Vec<usize> = fonts.iter().map(|f| f.as_font().width("AB")).collect();
+        assert_eq!(widths[1..], [8, 6]);
+        assert!(widths[0] > 0);
+        let bdf = fonts[1].as_font();
+        assert_eq!(bdf.glyph_storage.get(',').unwrap().slice_bitmap(&fonts[1].bitmaps), [0x80, 0x80]);
+
+        // Errors come back unboxed.
+        let bdf = TINY_BDF.replace("BBX 3 5 0 0\nBITMAP\n40", "BBX 60 5 8 0\nBITMAP\n40");
+        assert!(matches!(
+            load_owned_font_from_bdf(bdf.as_bytes()),
+            Err(LoadError::Bdf { line: 10, what: "glyph too wide" }),
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+//! Owned counterparts to the borrowed font types.
+
+use crate::{
+    BitmapEncoding, Font, Glyph, GlyphStorage, Kerning, KerningEntry, KerningTable,
+    LigatureTable, WideKerningEntry, WideKerningTable,
+};
+
+/// A font that owns its tables, rather than borrowing them like `Font`.
+///
+/// This is mostly useful for keeping a font around that was loaded or
+/// deserialized at runtime. Use `as_font` to get a `Font` for rendering.
+///
+/// With the `serde` feature, this serializes in the same shape as `Font`, so a
+/// serialized `Font` can be deserialized as an `OwnedFont`.
+///
+/// `OwnedFont` doesn't hold ligatures, because `LigatureTable` borrows its
+/// strings; converting a `Font` into an `OwnedFont` drops them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedFont {
+    /// See `Font::ascent`.
+    pub ascent: u8,
+    /// See `Font::descent`.
+    pub descent: u8,
+    /// See `Font::line_spacing`.
+    pub line_spacing: u8,
+    /// See `Font::glyph_storage`.
+    pub glyph_storage: OwnedGlyphStorage,
+    /// See `Font::replacement`.
+    pub replacement: u8,
+    /// See `Font::replacement_char`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub replacement_char: Option<char>,
+    /// See `Font::bitmaps`.
+    pub bitmaps: Vec<u8>,
+    /// See `Font::bitmap_encoding`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bitmap_encoding: BitmapEncoding,
+    /// See `Font::kerning`.
+    pub kerning: OwnedKerning,
+    /// See `Font::advance_bias`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub advance_bias: i8,
+    /// Where each glyph came from, by glyph index, if the font was loaded
+    /// from a glyph sheet. This is only for tools like `gen` to report, and
+    /// is empty for fonts from anywhere else. It isn't serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub sources: Vec<GlyphSource>,
+}
+
+/// Where a glyph came from in a glyph sheet, for `OwnedFont::sources`.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct GlyphSource {
+    /// Index of the page of the sheet holding the glyph, counting from 0.
+    pub page: usize,
+    /// The glyph's cell in the page, in pixels, as `(x, y, width, height)`.
+    /// For a banded sheet, this spans the whole band from top to bottom, and
+    /// leaves out the separator column.
+    pub rect: (u32, u32, u32, u32),
+}
+
+/// Owned counterpart to `GlyphStorage`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedGlyphStorage {
+    /// See `GlyphStorage::Dense`.
+    Dense {
+        first: u8,
+        glyphs: Vec<Glyph>,
+    },
+    /// See `GlyphStorage::Sparse`.
+    Sparse {
+        sorted_glyphs: Vec<(char, Glyph)>,
+    },
+    /// See `GlyphStorage::Ranges`.
+    Ranges {
+        ranges: Vec<(char, u16, u16)>,
+        glyphs: Vec<Glyph>,
+    },
+}
+
+/// Owned counterpart to `Kerning`. Entries are sorted by `pair`.
+///
+/// With the `serde` feature, this serializes as a plain list of entries, like
+/// `Kerning`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(untagged))]
+pub enum OwnedKerning {
+    /// See `Kerning::Narrow`.
+    Narrow(Vec<KerningEntry>),
+    /// See `Kerning::Wide`.
+    Wide(Vec<WideKerningEntry>),
+}
+
+impl Default for OwnedKerning {
+    fn default() -> Self {
+        Self::Narrow(vec![])
+    }
+}
+
+impl OwnedKerning {
+    /// Stores sorted `entries` compactly if all their chars are in ISO8859-1,
+    /// and as wide entries otherwise.
+    pub(crate) fn from_entries(entries: Vec<WideKerningEntry>) -> Self {
+        let narrow: Option<Vec<KerningEntry>> = entries.iter()
+            .map(|e| {
+                let pair = (u8::try_from(e.pair.0).ok()?, u8::try_from(e.pair.1).ok()?);
+                Some(KerningEntry { pair, adjust: e.adjust })
+            })
+            .collect();
+        match narrow {
+            Some(narrow) => Self::Narrow(narrow),
+            None => Self::Wide(entries),
+        }
+    }
+
+    /// Borrows this as a `Kerning`.
+    pub fn as_kerning(&self) -> Kerning<'_> {
+        match self {
+            Self::Narrow(entries) => Kerning::Narrow(KerningTable { entries }),
+            Self::Wide(entries) => Kerning::Wide(WideKerningTable { entries }),
+        }
+    }
+}
+
+impl From<Kerning<'_>> for OwnedKerning {
+    fn from(kerning: Kerning<'_>) -> Self {
+        match kerning {
+            Kerning::Narrow(table) => Self::Narrow(table.entries.to_vec()),
+            Kerning::Wide(table) => Self::Wide(table.entries.to_vec()),
+            Kerning::Class(table) => Self::from_entries(
+                table.pairs()
+                    .map(|(pair, adjust)| WideKerningEntry { pair, adjust })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl OwnedFont {
+    /// Borrows this as a `Font`.
+    pub fn as_font(&self) -> Font<'_, '_, '_, '_> {
+        Font {
+            ascent: self.ascent,
+            descent: self.descent,
+            line_spacing: self.line_spacing,
+            glyph_storage: self.glyph_storage.as_storage(),
+            replacement: self.replacement,
+            replacement_char: self.replacement_char,
+            bitmaps: &self.bitmaps,
+            bitmap_encoding: self.bitmap_encoding,
+            kerning: self.kerning.as_kerning(),
+            advance_bias: self.advance_bias,
+            ligatures: LigatureTable::default(),
+        }
+    }
+}
+
+impl OwnedGlyphStorage {
+    /// Borrows this as a `GlyphStorage`.
+    pub fn as_storage(&self) -> GlyphStorage<'_> {
+        match self {
+            Self::Dense { first, glyphs } => GlyphStorage::Dense {
+                first: *first,
+                glyphs,
+            },
+            Self::Sparse { sorted_glyphs } => GlyphStorage::Sparse {
+                sorted_glyphs,
+            },
+            Self::Ranges { ranges, glyphs } => GlyphStorage::Ranges {
+                ranges,
+                glyphs,
+            },
+        }
+    }
+}
+
+impl From<&Font<'_, '_, '_, '_>> for OwnedFont {
+    fn from(font: &Font<'_, '_, '_, '_>) -> Self {
+        Self {
+            ascent: font.ascent,
+            descent: font.descent,
+            line_spacing: font.line_spacing,
+            glyph_storage: font.glyph_storage.into(),
+            replacement: font.replacement,
+            replacement_char: font.replacement_char,
+            bitmaps: font.bitmaps.to_vec(),
+            bitmap_encoding: font.bitmap_encoding,
+            kerning: font.kerning.into(),
+            advance_bias: font.advance_bias,
+            sources: vec![],
+        }
+    }
+}
+
+impl From<GlyphStorage<'_>> for OwnedGlyphStorage {
+    fn from(storage: GlyphStorage<'_>) -> Self {
+        match storage {
+            GlyphStorage::Dense { first, glyphs } => Self::Dense {
+                first,
+                glyphs: glyphs.to_vec(),
+            },
+            GlyphStorage::Sparse { sorted_glyphs } => Self::Sparse {
+                sorted_glyphs: sorted_glyphs.to_vec(),
+            },
+            GlyphStorage::Ranges { ranges, glyphs } => Self::Ranges {
+                ranges: ranges.to_vec(),
+                glyphs: glyphs.to_vec(),
+            },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialized_font_deserializes_as_owned() {
+        const GLYPH: Glyph = Glyph {
+        pixel_width: 0,
+            row_bytes: 1,
+            image_offset: 0,
+            image_height: 2,
+            origin: (1, 3),
+            advance: 4,
+        };
+        static GLYPHS: [(char, Glyph); 2] = [
+            ('A', GLYPH),
+            ('\u{e9}', Glyph { image_offset: 1, ..GLYPH }),
+        ];
+        static KERNING: [KerningEntry; 1] = [KerningEntry { pair: (b'A', 0xE9), adjust: -1 }];
+        let font = Font {
+            ascent: 5,
+            descent: 1,
+            line_spacing: 7,
+            glyph_storage: GlyphStorage::Sparse { sorted_glyphs: &GLYPHS },
+            replacement: 1,
+            replacement_char: None,
+            bitmaps: &[0x80, 0xC0, 0xE0],
+            bitmap_encoding: BitmapEncoding::Raw,
+            kerning: Kerning::Narrow(KerningTable { entries: &KERNING }),
+            advance_bias: 0,
+            ligatures: LigatureTable { entries: &[] },
+        };
+
+        let text = ron::to_string(&font).unwrap();
+        let owned: OwnedFont = ron::from_str(&text).unwrap();
+        assert_eq!(owned.bitmaps, font.bitmaps);
+        assert!(matches!(&owned.kerning, OwnedKerning::Narrow(entries) if entries[..] == KERNING));
+        assert!(matches!(
+            &owned.glyph_storage,
+            OwnedGlyphStorage::Sparse { sorted_glyphs } if sorted_glyphs[..] == GLYPHS[..],
+        ));
+        // And back again, unchanged.
+        assert_eq!(ron::to_string(&owned.as_font()).unwrap(), text);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn owned_copy_renders_like_the_original() {
+        let render = |font: &Font<'_, '_, '_, '_>| {
+            let mut img = image::GrayImage::new(font.width("Hello") as u32, font.line_spacing.into());
+            font.render("Hello", 0, 0, &mut img, image::Luma([0xFF]));
+            img
+        };
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        crate::load::load_font_from_png(png, crate::load::GlyphOrder::Iso8859_1, Some(b' '), &Default::default(), |font| {
+            let owned = OwnedFont::from(font);
+            let copy = owned.clone();
+            assert_eq!(render(&owned.as_font()), render(font));
+            assert_eq!(render(&copy.as_font()), render(font));
+            Ok(())
+        }).unwrap();
+    }
+}
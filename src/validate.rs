@@ -0,0 +1,227 @@
+//! Checking fonts for internal consistency.
+
+use core::fmt;
+
+use crate::{ClassKerningTable, Font, GlyphStorage, Kerning, KerningError};
+
+/// Problems found by `Font::validate`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FontError {
+    /// The glyph with this index has an image extending past the end of the
+    /// bitmap data, or (for compressed fonts) whose data doesn't decode to a
+    /// full image.
+    BadGlyphImage { index: usize },
+    /// The `replacement` glyph index is out of range.
+    MissingReplacement { index: u8 },
+    /// `line_spacing` is less than `ascent + descent`, so consecutive lines
+    /// will overlap. This doesn't break rendering, but is probably a mistake.
+    TightLineSpacing,
+    /// The kerning table isn't sorted properly.
+    Kerning(KerningError),
+    /// The `Sparse` glyph at this index has a char that sorts at or before the
+    /// previous glyph's.
+    UnsortedGlyph { index: usize },
+    /// The `Ranges` run at this index starts before the end of the previous
+    /// run.
+    UnsortedRange { index: usize },
+    /// The `Ranges` run at this index refers to glyphs past the end of the
+    /// glyph table.
+    RangeOutOfBounds { index: usize },
+    /// The class kerning entry at this index has a char that sorts at or
+    /// before the previous entry's, or names a class outside the matrix.
+    BadKerningClass { index: usize },
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadGlyphImage { index } => {
+                write!(f, "glyph {index} image out of range")
+            }
+            Self::MissingReplacement { index } => {
+                write!(f, "replacement glyph {index} out of range")
+            }
+            Self::TightLineSpacing => {
+                f.write_str("line spacing is less than ascent + descent")
+            }
+            Self::Kerning(e) => write!(f, "bad kerning table: {e}"),
+            Self::UnsortedGlyph { index } => {
+                write!(f, "glyph {index} is out of order")
+            }
+            Self::UnsortedRange { index } => {
+                write!(f, "glyph range {index} is out of order or overlaps")
+            }
+            Self::RangeOutOfBounds { index } => {
+                write!(f, "glyph range {index} extends past the glyph table")
+            }
+            Self::BadKerningClass { index } => {
+                write!(f, "kerning class entry {index} is out of order or out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl Font<'_, '_, '_, '_> {
+    /// Checks the invariants that the rest of the crate assumes about a
+    /// font, such as that glyph images lie within the bitmap data and tables
+    /// are sorted for binary search. This is mostly useful for fonts built by
+    /// hand.
+    ///
+    /// Every problem found is reported, not just the first.
+    pub fn validate(&self) -> Result<(), Vec<FontError>> {
+        let mut errors = vec![];
+
+        let mut index = 0;
+        while let Some(glyph) = self.glyph_storage.get_by_index(index) {
+            let len = glyph.row_bytes_usize() * usize::from(glyph.image_height);
+            if glyph.bitmap_bytes(self.bitmaps, self.bitmap_encoding).count() != len {
+                errors.push(FontError::BadGlyphImage { index });
+            }
+            index += 1;
+        }
+
+        if self.glyph_storage.get_by_index(usize::from(self.replacement)).is_none() {
+            errors.push(FontError::MissingReplacement { index: self.replacement });
+        }
+
+        if self.line_spacing < self.ascent.saturating_add(self.descent) {
+            errors.push(FontError::TightLineSpacing);
+        }
+
+        // Compare pairs as codepoints, which sort the same way in both kinds
+        // of table.
+        let pairs: Vec<(u32, u32)> = match self.kerning {
+            Kerning::Narrow(table) => table.entries.iter()
+                .map(|e| (u32::from(e.pair.0), u32::from(e.pair.1)))
+                .collect(),
+            Kerning::Wide(table) | Kerning::Class(ClassKerningTable { pairs: table, .. }) => {
+                table.entries.iter()
+                    .map(|e| (u32::from(e.pair.0), u32::from(e.pair.1)))
+                    .collect()
+            }
+        };
+        for index in 1..pairs.len() {
+            let (a, b) = (pairs[index - 1], pairs[index]);
+            if a == b {
+                errors.push(FontError::Kerning(KerningError::Duplicate { index }));
+            } else if a > b {
+                errors.push(FontError::Kerning(KerningError::Unsorted { index }));
+            }
+        }
+
+        if let Kerning::Class(table) = self.kerning {
+            let rows = table.matrix.len() / usize::from(table.right_classes).max(1);
+            for (index, &(c, left, right)) in table.classes.iter().enumerate() {
+                let unsorted = index > 0 && table.classes[index - 1].0 >= c;
+                if unsorted || usize::from(left) >= rows || right >= table.right_classes {
+                    errors.push(FontError::BadKerningClass { index });
+                }
+            }
+        }
+
+        match self.glyph_storage {
+            GlyphStorage::Dense { .. } => (),
+            GlyphStorage::Sparse { sorted_glyphs } => {
+                for index in 1..sorted_glyphs.len() {
+                    if sorted_glyphs[index - 1].0 >= sorted_glyphs[index].0 {
+                        errors.push(FontError::UnsortedGlyph { index });
+                    }
+                }
+            }
+            GlyphStorage::Ranges { ranges, glyphs } => {
+                for (index, &(start, len, offset)) in ranges.iter().enumerate() {
+                    if usize::from(offset) + usize::from(len) > glyphs.len() {
+                        errors.push(FontError::RangeOutOfBounds { index });
+                    }
+                    if index > 0 {
+                        let (prev, prev_len, _) = ranges[index - 1];
+                        if u32::from(prev) + u32::from(prev_len) > u32::from(start) {
+                            errors.push(FontError::UnsortedRange { index });
+                        }
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, KerningEntry, KerningTable};
+    use crate::load::{load_font_from_png, GlyphOrder};
+
+    /// Calls `body` with the futuroid font.
+    fn with_font(body: impl FnOnce(&Font<'_, '_, '_, '_>)) {
+        let png = std::io::BufReader::new(std::fs::File::open("fonts/futuroid.png").unwrap());
+        load_font_from_png(png, GlyphOrder::Iso8859_1, Some(b' '), &Default::default(), |font| {
+            body(font);
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn loaded_font_is_valid() {
+        with_font(|font| assert_eq!(font.validate(), Ok(())));
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        with_font(|font| {
+            let font = Font {
+                replacement: 200,
+                line_spacing: font.ascent - 1,
+                ..*font
+            };
+            assert_eq!(font.validate(), Err(vec![
+                FontError::MissingReplacement { index: 200 },
+                FontError::TightLineSpacing,
+            ]));
+        });
+    }
+
+    #[test]
+    fn table_violations_are_reported_by_index() {
+        const BLANK: Glyph = Glyph {
+        pixel_width: 0,
+            row_bytes: 0,
+            image_offset: 0,
+            image_height: 0,
+            origin: (0, 0),
+            advance: 4,
+        };
+        static GLYPHS: [(char, Glyph); 3] = [
+            ('a', BLANK),
+            ('c', Glyph { row_bytes: 1, image_offset: u16::MAX, image_height: 2, ..BLANK }),
+            ('b', BLANK),
+        ];
+        static KERNING: [KerningEntry; 3] = [
+            KerningEntry { pair: (b'a', b'b'), adjust: -1 },
+            KerningEntry { pair: (b'a', b'b'), adjust: -1 },
+            KerningEntry { pair: (b'a', b'a'), adjust: -1 },
+        ];
+        with_font(|font| {
+            let font = Font {
+                glyph_storage: GlyphStorage::Sparse { sorted_glyphs: &GLYPHS },
+                replacement: 0,
+                // Built directly, as `new` would reject the table.
+                kerning: Kerning::Narrow(KerningTable { entries: &KERNING }),
+                ..*font
+            };
+            assert_eq!(font.validate(), Err(vec![
+                FontError::BadGlyphImage { index: 1 },
+                FontError::Kerning(KerningError::Duplicate { index: 1 }),
+                FontError::Kerning(KerningError::Unsorted { index: 2 }),
+                FontError::UnsortedGlyph { index: 2 },
+            ]));
+        });
+    }
+}